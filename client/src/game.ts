@@ -136,6 +136,7 @@ class Game {
     // TODO: Add an initial state field into the welcome message.
     handleWelcome = (msg: MessageData.Welcome): void => {
         this.welcomeMessage = msg;
+        this.socket.ready();
     }
 
     handleGoAway = (msg: MessageData.GoAway): void => {
@@ -189,6 +190,10 @@ class Game {
         player.direction = msg.direction;
     }
 
+    handlePlayersMoving = (msg: MessageData.PlayersMoving): void => {
+        msg.updates.forEach(this.handlePlayerMoving);
+    }
+
     handlePlayerStopped = (msg: MessageData.PlayerStopped): void => {
         var player = this.game.state.alivePlayers.find((player: Entity): boolean => {
             return player.id == msg.id;
@@ -221,6 +226,7 @@ class Game {
         this.socket.addListener('player_spawned', this.handlePlayerSpawned);
         this.socket.addListener('player_destroyed', this.handlePlayerDestroyed);
         this.socket.addListener('player_moving', this.handlePlayerMoving);
+        this.socket.addListener('players_moving', this.handlePlayersMoving);
         this.socket.addListener('player_stopped', this.handlePlayerStopped);
         this.socket.addListener('world_state', this.handleWorldState);
 
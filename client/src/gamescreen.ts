@@ -53,10 +53,11 @@ export class GameScreen {
         }
     }
 
-    // Process a mouse click
+    // Process a mouse click, requesting a respawn if you are dead
     onMouseClick(x: number, y: number, type: GameInput): void {
         var player = this.getPlayer();
         if (player == null) {
+            this.socket.requestRespawn();
             return;
         }
 
@@ -60,6 +60,14 @@ export class GameSocket extends EventEmitter {
             move_y: aimVector.y,
         });
     }
+
+    requestRespawn(): void {
+        this.send('request_respawn', <MessageData.RequestRespawn>{});
+    }
+
+    ready(): void {
+        this.send('ready', <MessageData.Ready>{});
+    }
 }
 
 export class Entity {
@@ -87,6 +95,7 @@ export namespace MessageData {
         size: number,
         bulletSpeed: number,
         bulletSize: number,
+        features: Array<string>,
     }
 
     export interface GoAway extends MessageData {
@@ -106,6 +115,7 @@ export namespace MessageData {
         bulletID: number,
         position: Victor,
         aim: Victor,
+        tick: number,
     }
 
     export interface PlayerSpawned extends MessageData {
@@ -125,6 +135,10 @@ export namespace MessageData {
         direction: Victor,
     }
 
+    export interface PlayersMoving extends MessageData {
+        updates: Array<PlayerMoving>,
+    }
+
     export interface PlayerStopped extends MessageData {
         id: number,
         position: Victor,
@@ -147,6 +161,10 @@ export namespace MessageData {
         move_x: number,
         move_y: number,
     }
+
+    export interface RequestRespawn extends MessageData { }
+
+    export interface Ready extends MessageData { }
 }
 
 // It's a string enum, no worries
@@ -160,11 +178,14 @@ type MessageType =
     'player_spawned' |
     'player_destroyed' |
     'player_moving' |
+    'players_moving' |
     'player_stopped' |
     'world_state' |
     'start_moving' |
     'stop_moving' |
-    'fire';
+    'fire' |
+    'request_respawn' |
+    'ready';
 const MessageType = {
     Welcome: 'welcome' as MessageType,
     GoAway: 'go_away' as MessageType,
@@ -174,11 +195,14 @@ const MessageType = {
     PlayerSpawned: 'player_spawned' as MessageType,
     PlayerDestroyed: 'player_destroyed' as MessageType,
     PlayerMoving: 'player_moving' as MessageType,
+    PlayersMoving: 'players_moving' as MessageType,
     PlayerStopped: 'player_stopped' as MessageType,
     WorldState: 'world_state' as MessageType,
     StartMoving: 'start_moving' as MessageType,
     StopMoving: 'stop_moving' as MessageType,
     Fire: 'fire' as MessageType,
+    RequestRespawn: 'request_respawn' as MessageType,
+    Ready: 'ready' as MessageType,
 }
 
 function make_message_decomposers(transport: GameWSTransport): { [type: string]: (message: Object) => MessageData } {
@@ -190,6 +214,7 @@ function make_message_decomposers(transport: GameWSTransport): { [type: string]:
                 size: <number>(<any>msg)['size'],
                 bulletSpeed: <number>(<any>msg)['bullet_speed'],
                 bulletSize: <number>(<any>msg)['bullet_size'],
+                features: <Array<string>>(<any>msg)['features'],
             };
         },
         'go_away': (msg: Object): MessageData.GoAway => {
@@ -214,6 +239,7 @@ function make_message_decomposers(transport: GameWSTransport): { [type: string]:
                 bulletID: <number>(<any>msg)['bullet_id'],
                 position: new Victor(<number>(<any>msg)['x'], <number>(<any>msg)['y']),
                 aim: new Victor(<number>(<any>msg)['aim_x'], <number>(<any>msg)['aim_y']),
+                tick: <number>(<any>msg)['tick'],
             };
         },
         'player_spawned': (msg: Object): MessageData.PlayerSpawned => {
@@ -236,6 +262,17 @@ function make_message_decomposers(transport: GameWSTransport): { [type: string]:
                 direction: new Victor(<number>(<any>msg)['move_x'], <number>(<any>msg)['move_y']),
             };
         },
+        'players_moving': (msg: Object): MessageData.PlayersMoving => {
+            return {
+                updates: (<Array<Object>>(<any>msg)['updates']).map((update: Object): MessageData.PlayerMoving => {
+                    return {
+                        id: <number>(<any>update)['id'],
+                        position: new Victor(<number>(<any>update)['x'], <number>(<any>update)['y']),
+                        direction: new Victor(<number>(<any>update)['move_x'], <number>(<any>update)['move_y']),
+                    };
+                }),
+            };
+        },
         'player_stopped': (msg: Object): MessageData.PlayerStopped => {
             return {
                 id: <number>(<any>msg)['id'],
@@ -0,0 +1,85 @@
+//! Benchmarks of `GameState::process_game_update()` as the amounts of players and bullets grow.
+//!
+//! Run with `cargo bench --features nightly`, which needs a nightly compiler for the `test` crate;
+//! without the feature there's nothing to build, so stable compilers can still build every target.
+
+#![cfg(feature = "nightly")]
+#![cfg_attr(feature = "nightly", feature(test))]
+
+extern crate test;
+extern crate tatsoryk_server;
+
+use std::f32::consts::PI;
+use std::sync::mpsc;
+use test::Bencher;
+use tatsoryk_server::message::Message;
+use tatsoryk_server::options::{BoundaryMode, Options};
+use tatsoryk_server::server::{Client, GameState, WebSocketEvent};
+
+/// Create a game with the specified amount of players running in all directions,
+/// and the specified amount of bullets fired by them in all directions.
+///
+/// Bullets bounce off the walls, so they only go away by hitting players, who stay dead, as nobody asks to respawn,
+/// or by running out of range after a few hundred ticks.
+fn populated_game(players: u32, bullets: u32) -> GameState {
+    let mut state = GameState::new(Options { boundary_mode: BoundaryMode::Bounce, ..Options::default() });
+    let (events_tx, events_rx) = mpsc::channel();
+
+    for id in 0..players {
+        // Messages are still serialised and sent, but nobody receives them
+        let (client_tx, _) = mpsc::channel();
+        let angle = 2.0 * PI * id as f32 / players as f32;
+        events_tx.send(WebSocketEvent::ClientCreated { client: Client::new(id, client_tx) }).unwrap();
+        events_tx.send(WebSocketEvent::ClientMessage {
+                     client_id: id,
+                     message: Message::StartMoving {
+                         move_x: angle.cos(),
+                         move_y: angle.sin(),
+                     },
+                     seq: None,
+                 })
+                 .unwrap();
+    }
+    for bullet in 0..bullets {
+        let angle = 2.0 * PI * bullet as f32 / bullets as f32;
+        events_tx.send(WebSocketEvent::ClientMessage {
+                     client_id: bullet % players,
+                     message: Message::Fire {
+                         move_x: angle.cos(),
+                         move_y: angle.sin(),
+                     },
+                     seq: None,
+                 })
+                 .unwrap();
+    }
+    let _ = state.process_websocket_events(&events_rx);
+
+    assert_eq!(state.alive_player_count(), players as usize);
+    assert_eq!(state.bullet_count(), bullets as usize);
+    state
+}
+
+fn bench_game_update(b: &mut Bencher, players: u32, bullets: u32) {
+    let mut state = populated_game(players, bullets);
+    let start_tick = state.current_tick();
+
+    b.iter(|| state.process_game_update());
+
+    assert!(state.current_tick() > start_tick);
+    assert!(state.bullet_count() <= bullets as usize);
+}
+
+#[bench]
+fn game_update_8_players_16_bullets(b: &mut Bencher) {
+    bench_game_update(b, 8, 16);
+}
+
+#[bench]
+fn game_update_32_players_128_bullets(b: &mut Bencher) {
+    bench_game_update(b, 32, 128);
+}
+
+#[bench]
+fn game_update_64_players_512_bullets(b: &mut Bencher) {
+    bench_game_update(b, 64, 512);
+}
@@ -0,0 +1,28 @@
+//! Server for Tatsoryk, with everything but the command-line entry point, so that it can be benchmarked.
+
+#![deny(
+    missing_debug_implementations,
+    missing_copy_implementations,
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_import_braces,
+    unused_qualifications,
+    unsafe_code,
+    dead_code,
+    unused_results,
+)]
+
+extern crate clap;
+extern crate rand;
+extern crate time;
+extern crate serde;
+extern crate serde_json;
+extern crate websocket;
+extern crate flate2;
+
+pub mod options;
+pub mod math;
+pub mod message;
+pub mod server;
+
+pub use options::Options;
@@ -10,49 +10,72 @@
     unused_results,
 )]
 
-extern crate clap;
-extern crate rand;
-extern crate time;
 extern crate ctrlc;
-extern crate serde;
-extern crate serde_json;
-extern crate websocket;
+extern crate tatsoryk_server;
 
-mod options;
-pub mod math;
-pub mod message;
-pub mod server;
+use std::process;
+use std::thread;
+use std::sync::{Arc, Mutex, RwLock};
 
-use websocket::Client;
-use websocket::client::request::Url;
-use std::sync::{Arc, RwLock};
-use std::sync::mpsc::channel;
-
-use server::{listen, start_game_loop};
-pub use options::Options;
+use tatsoryk_server::Options;
+use tatsoryk_server::server::{check, listen, ListenConfig, RoomRegistry};
 
 fn main() {
-    let opts = Options::parse();
+    let opts = match Options::try_parse() {
+        Ok(opts) => opts,
+        Err(error) => {
+            println!("{}", error);
+            process::exit(1);
+        }
+    };
+
+    if opts.check {
+        match check(&opts) {
+            Ok(summary) => println!("{}", summary),
+            Err(error) => {
+                println!("Invalid configuration: {}", error);
+                process::exit(1);
+            }
+        }
+        return;
+    }
 
     let cont = Arc::new(RwLock::new(true));
 
     {
-        let host = opts.host.clone();
-        let port = opts.port;
         let cont = cont.clone();
         ctrlc::set_handler(move || {
             println!("Ctrl+C received, terminating...");
             *cont.write().unwrap() = false;
-            let _ = Client::connect(Url::parse(&format!("ws://{}:{}", host, port)[..]).unwrap());
         });
     }
 
-    // Create the channel which will allow the game loop to recieve messages.
-    let (tx, rx) = channel();
+    // The rooms' game loops are started as clients join them.
+    let rooms = Arc::new(Mutex::new(RoomRegistry::new(opts.clone(), &cont)));
 
-    let game_loop_handle = start_game_loop(rx, &cont);
-    listen(&opts.host, opts.port, tx, &cont);
-    if let Err(error) = game_loop_handle.join() {
-        println!("Game loop thread failed: {:?}", error);
+    // Spectators are routed to the same rooms, but from their own port
+    let observer_listener = opts.observer_port.map(|observer_port| {
+        let config = ListenConfig {
+            port: observer_port,
+            spectators: true,
+            ..ListenConfig::new(&opts)
+        };
+        let rooms = rooms.clone();
+        let cont = cont.clone();
+        thread::spawn(move || {
+            if let Err(error) = listen(&config, rooms, &cont) {
+                println!("Couldn't start the observer server: {}", error);
+                process::exit(1);
+            }
+        })
+    });
+
+    if let Err(error) = listen(&ListenConfig::new(&opts), rooms.clone(), &cont) {
+        println!("Couldn't start the server: {}", error);
+        process::exit(1);
+    }
+    if let Some(observer_listener) = observer_listener {
+        let _ = observer_listener.join();
     }
+    rooms.lock().unwrap().join();
 }
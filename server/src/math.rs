@@ -1,11 +1,97 @@
 //! Various useful reusable mathematical functions.
 
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// A position or vector on a plane.
+///
+/// # Examples
+///
+/// ```
+/// # use tatsoryk_server::math::Vec2;
+/// let offset = Vec2::new(3.0, 0.0) - Vec2::new(0.0, 4.0);
+/// assert_eq!(offset.length(), 5.0);
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Vec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Vec2 {
+    pub fn new(x: f32, y: f32) -> Vec2 {
+        Vec2 { x: x, y: y }
+    }
+
+    /// Length of this vector, i.e. the distance of this position from the origin.
+    pub fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    /// Vector of length 1 pointing the same way as this one, or the zero vector for the zero vector.
+    pub fn normalize(self) -> Vec2 {
+        let length = self.length();
+        if length == 0.0 {
+            self
+        } else {
+            Vec2::new(self.x / length, self.y / length)
+        }
+    }
+
+    pub fn dot(self, other: Vec2) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// Distance between this position and the specified one, like `distance_between()`.
+    pub fn distance(self, other: Vec2) -> f32 {
+        distance_between(self.x, self.y, other.x, other.y)
+    }
+}
+
+impl From<(f32, f32)> for Vec2 {
+    fn from((x, y): (f32, f32)) -> Vec2 {
+        Vec2::new(x, y)
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Vec2;
+
+    fn add(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Vec2;
+
+    fn sub(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Mul<f32> for Vec2 {
+    type Output = Vec2;
+
+    fn mul(self, scale: f32) -> Vec2 {
+        Vec2::new(self.x * scale, self.y * scale)
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Vec2;
+
+    fn neg(self) -> Vec2 {
+        Vec2::new(-self.x, -self.y)
+    }
+}
+
 /// Calculate the distance between two points on a plane.
 ///
 /// # Examples
 ///
 /// ```
-/// let distance = distance_between(0, 0, 1, 1);
+/// # use tatsoryk_server::math::distance_between;
+/// let distance = distance_between(0.0, 0.0, 1.0, 1.0);
 /// assert_eq!((distance * 10.0).round() / 10.0, 1.4);
 /// ```
 pub fn distance_between(x0: f32, y0: f32, x1: f32, y1: f32) -> f32 {
@@ -14,9 +100,70 @@ pub fn distance_between(x0: f32, y0: f32, x1: f32, y1: f32) -> f32 {
     (dx * dx + dy * dy).sqrt()
 }
 
+/// Check whether a point lies within an axis-aligned rectangle, edges included.
+///
+/// # Examples
+///
+/// ```
+/// # use tatsoryk_server::math::rect_contains;
+/// assert!(rect_contains(0.0, 0.0, 10.0, 10.0, 10.0, 5.0));
+/// assert!(!rect_contains(0.0, 0.0, 10.0, 10.0, 11.0, 5.0));
+/// ```
+pub fn rect_contains(left: f32, top: f32, right: f32, bottom: f32, x: f32, y: f32) -> bool {
+    x >= left && x <= right && y >= top && y <= bottom
+}
+
+/// Check whether a circle overlaps an axis-aligned rectangle, edges touching not included.
+///
+/// # Examples
+///
+/// ```
+/// # use tatsoryk_server::math::aabb_circle_hit;
+/// assert!(aabb_circle_hit(0.0, 0.0, 10.0, 10.0, 12.0, 12.0, 3.0));
+/// assert!(!aabb_circle_hit(0.0, 0.0, 10.0, 10.0, 12.0, 12.0, 2.0));
+/// ```
+pub fn aabb_circle_hit(left: f32, top: f32, right: f32, bottom: f32, x: f32, y: f32, radius: f32) -> bool {
+    let closest_x = x.max(left).min(right);
+    let closest_y = y.max(top).min(bottom);
+    distance_between(x, y, closest_x, closest_y) < radius
+}
+
 #[cfg(test)]
 mod tests {
-    use self::super::distance_between;
+    use self::super::{aabb_circle_hit, distance_between, rect_contains, Vec2};
+
+    #[test]
+    fn vec2_length_and_distance_match_distance_between() {
+        assert_eq!(Vec2::new(3.0, 4.0).length(), 5.0);
+        assert_eq!(Vec2::default().length(), 0.0);
+        assert_eq!(Vec2::new(0.0, 3.0).distance(Vec2::new(4.0, 0.0)), 5.0);
+        assert_eq!(Vec2::new(1.5, -2.5).distance(Vec2::new(-7.25, 3.0)),
+                   distance_between(1.5, -2.5, -7.25, 3.0));
+    }
+
+    #[test]
+    fn vec2_normalize_keeps_direction() {
+        assert_eq!(Vec2::new(0.0, -5.0).normalize(), Vec2::new(0.0, -1.0));
+        assert_eq!(Vec2::new(3.0, 4.0).normalize(), Vec2::new(0.6, 0.8));
+        assert_eq!(Vec2::default().normalize(), Vec2::default());
+    }
+
+    #[test]
+    fn vec2_dot_product() {
+        assert_eq!(Vec2::new(1.0, 2.0).dot(Vec2::new(3.0, 4.0)), 11.0);
+        assert_eq!(Vec2::new(1.0, 0.0).dot(Vec2::new(0.0, 1.0)), 0.0);
+    }
+
+    #[test]
+    fn vec2_arithmetic_is_per_axis() {
+        let a = Vec2::new(1.0, 2.0);
+        let b = Vec2::new(0.5, -4.0);
+        assert_eq!(a + b, Vec2::new(1.5, -2.0));
+        assert_eq!(a - b, Vec2::new(0.5, 6.0));
+        assert_eq!(a * 3.0, Vec2::new(3.0, 6.0));
+        assert_eq!(-a, Vec2::new(-1.0, -2.0));
+        assert_eq!(Vec2::from((1.0, 2.0)), a);
+    }
 
     #[test]
     fn distance_between_produces_expected_results() {
@@ -24,4 +171,22 @@ mod tests {
         assert_eq!((distance * 100000.0).round() / 100000.0, 1.41421);
         assert_eq!(distance_between(0.0, 3.0, 4.0, 0.0), 5.0);
     }
+
+    #[test]
+    fn rect_contains_includes_edges() {
+        assert!(rect_contains(0.0, 0.0, 10.0, 20.0, 5.0, 5.0));
+        assert!(rect_contains(0.0, 0.0, 10.0, 20.0, 0.0, 20.0));
+        assert!(rect_contains(0.0, 0.0, 10.0, 20.0, 10.0, 0.0));
+        assert!(!rect_contains(0.0, 0.0, 10.0, 20.0, 10.5, 5.0));
+        assert!(!rect_contains(0.0, 0.0, 10.0, 20.0, 5.0, -0.5));
+    }
+
+    #[test]
+    fn aabb_circle_hit_checks_sides_and_corners() {
+        assert!(aabb_circle_hit(0.0, 0.0, 10.0, 20.0, 5.0, 5.0, 1.0));
+        assert!(aabb_circle_hit(0.0, 0.0, 10.0, 20.0, 12.0, 5.0, 3.0));
+        assert!(!aabb_circle_hit(0.0, 0.0, 10.0, 20.0, 12.0, 5.0, 2.0));
+        assert!(aabb_circle_hit(0.0, 0.0, 10.0, 20.0, 12.0, 22.0, 3.0));
+        assert!(!aabb_circle_hit(0.0, 0.0, 10.0, 20.0, 12.0, 22.0, 2.5));
+    }
 }
@@ -6,6 +6,7 @@ pub enum MessageError {
     PropertyMissing(String),
     ExtraneousProperty(String),
     BadType(String),
+    InvalidValue(String),
 }
 
 impl From<serde_json::Error> for MessageError {
@@ -0,0 +1,255 @@
+//! Fast path for parsing the small, fixed-shape messages clients send the most.
+//!
+//! **start_moving**, **stop_moving** and **fire** are deserialised straight from the text,
+//! without building a `serde_json::Value` tree and collecting its keys first.
+//! Anything else, including malformed versions of those, is left to the general parser,
+//! so that it can report the error.
+
+use serde::de::{Deserialize, Deserializer, Error, MapVisitor, Visitor};
+use serde::de::impls::IgnoredAny;
+use serde_json;
+
+use self::super::Message;
+
+/// Parse the specified text if it's a well-formed fast-path message, `None` otherwise.
+pub fn parse(s: &str) -> Option<Message> {
+    match serde_json::from_str::<FastMessage>(s) {
+        Ok(FastMessage(message)) => message,
+        Err(_) => None,
+    }
+}
+
+/// Top-level message, `None` if it isn't exactly one of the fast-path ones.
+struct FastMessage(Option<Message>);
+
+impl Deserialize for FastMessage {
+    fn deserialize<D>(deserializer: &mut D) -> Result<FastMessage, D::Error>
+        where D: Deserializer
+    {
+        deserializer.deserialize_map(FastMessageVisitor)
+    }
+}
+
+struct FastMessageVisitor;
+
+impl Visitor for FastMessageVisitor {
+    type Value = FastMessage;
+
+    fn visit_map<V>(&mut self, mut visitor: V) -> Result<FastMessage, V::Error>
+        where V: MapVisitor
+    {
+        let mut msg_type = None;
+        let mut data = None;
+        let mut well_formed = true;
+
+        while let Some(key) = try!(visitor.visit_key::<Key>()) {
+            match key {
+                Key::Type if msg_type.is_none() => msg_type = Some(try!(visitor.visit_value::<Type>())),
+                Key::Data if data.is_none() => data = Some(try!(visitor.visit_value::<Moves>())),
+                _ => {
+                    let _ = try!(visitor.visit_value::<IgnoredAny>());
+                    well_formed = false;
+                }
+            }
+        }
+        try!(visitor.end());
+
+        if !well_formed {
+            return Ok(FastMessage(None));
+        }
+        Ok(FastMessage(match (msg_type, data) {
+            (Some(Type::StopMoving), None) |
+            (Some(Type::StopMoving), Some(Moves { move_x: None, move_y: None, well_formed: true })) => {
+                Some(Message::StopMoving)
+            }
+            (Some(Type::StartMoving), Some(Moves { move_x: Some(move_x), move_y: Some(move_y), well_formed: true })) => {
+                Some(Message::StartMoving {
+                    move_x: move_x,
+                    move_y: move_y,
+                })
+            }
+            (Some(Type::Fire), Some(Moves { move_x: Some(move_x), move_y: Some(move_y), well_formed: true })) => {
+                Some(Message::Fire {
+                    move_x: move_x,
+                    move_y: move_y,
+                })
+            }
+            _ => None,
+        }))
+    }
+}
+
+/// Top-level key, recognised without allocating it.
+enum Key {
+    Type,
+    Data,
+    Other,
+}
+
+impl Deserialize for Key {
+    fn deserialize<D>(deserializer: &mut D) -> Result<Key, D::Error>
+        where D: Deserializer
+    {
+        deserializer.deserialize_string(KeyVisitor)
+    }
+}
+
+struct KeyVisitor;
+
+impl Visitor for KeyVisitor {
+    type Value = Key;
+
+    fn visit_str<E>(&mut self, value: &str) -> Result<Key, E>
+        where E: Error
+    {
+        Ok(match value {
+            "type" => Key::Type,
+            "data" => Key::Data,
+            _ => Key::Other,
+        })
+    }
+}
+
+/// Value of the top-level `"type"`, recognised without allocating it.
+enum Type {
+    StartMoving,
+    StopMoving,
+    Fire,
+    Other,
+}
+
+impl Deserialize for Type {
+    fn deserialize<D>(deserializer: &mut D) -> Result<Type, D::Error>
+        where D: Deserializer
+    {
+        deserializer.deserialize_string(TypeVisitor)
+    }
+}
+
+struct TypeVisitor;
+
+impl Visitor for TypeVisitor {
+    type Value = Type;
+
+    fn visit_str<E>(&mut self, value: &str) -> Result<Type, E>
+        where E: Error
+    {
+        Ok(match value {
+            "start_moving" => Type::StartMoving,
+            "stop_moving" => Type::StopMoving,
+            "fire" => Type::Fire,
+            _ => Type::Other,
+        })
+    }
+}
+
+/// Contents of the top-level `"data"`, whether it has exactly the movement vector or not.
+struct Moves {
+    move_x: Option<f32>,
+    move_y: Option<f32>,
+    well_formed: bool,
+}
+
+impl Deserialize for Moves {
+    fn deserialize<D>(deserializer: &mut D) -> Result<Moves, D::Error>
+        where D: Deserializer
+    {
+        deserializer.deserialize_map(MovesVisitor)
+    }
+}
+
+struct MovesVisitor;
+
+impl Visitor for MovesVisitor {
+    type Value = Moves;
+
+    fn visit_map<V>(&mut self, mut visitor: V) -> Result<Moves, V::Error>
+        where V: MapVisitor
+    {
+        let mut moves = Moves {
+            move_x: None,
+            move_y: None,
+            well_formed: true,
+        };
+
+        while let Some(key) = try!(visitor.visit_key::<MoveKey>()) {
+            match key {
+                MoveKey::MoveX if moves.move_x.is_none() => moves.move_x = Some(try!(visitor.visit_value::<Number>()).0),
+                MoveKey::MoveY if moves.move_y.is_none() => moves.move_y = Some(try!(visitor.visit_value::<Number>()).0),
+                _ => {
+                    let _ = try!(visitor.visit_value::<IgnoredAny>());
+                    moves.well_formed = false;
+                }
+            }
+        }
+        try!(visitor.end());
+
+        Ok(moves)
+    }
+}
+
+/// Key inside the top-level `"data"`, recognised without allocating it.
+enum MoveKey {
+    MoveX,
+    MoveY,
+    Other,
+}
+
+impl Deserialize for MoveKey {
+    fn deserialize<D>(deserializer: &mut D) -> Result<MoveKey, D::Error>
+        where D: Deserializer
+    {
+        deserializer.deserialize_string(MoveKeyVisitor)
+    }
+}
+
+struct MoveKeyVisitor;
+
+impl Visitor for MoveKeyVisitor {
+    type Value = MoveKey;
+
+    fn visit_str<E>(&mut self, value: &str) -> Result<MoveKey, E>
+        where E: Error
+    {
+        Ok(match value {
+            "move_x" => MoveKey::MoveX,
+            "move_y" => MoveKey::MoveY,
+            _ => MoveKey::Other,
+        })
+    }
+}
+
+/// A JSON number converted to `f32` the same way the general parser does, rejecting anything else.
+struct Number(f32);
+
+impl Deserialize for Number {
+    fn deserialize<D>(deserializer: &mut D) -> Result<Number, D::Error>
+        where D: Deserializer
+    {
+        deserializer.deserialize_f64(NumberVisitor)
+    }
+}
+
+struct NumberVisitor;
+
+impl Visitor for NumberVisitor {
+    type Value = Number;
+
+    fn visit_f64<E>(&mut self, value: f64) -> Result<Number, E>
+        where E: Error
+    {
+        Ok(Number(value as f32))
+    }
+
+    fn visit_i64<E>(&mut self, value: i64) -> Result<Number, E>
+        where E: Error
+    {
+        Ok(Number(value as f32))
+    }
+
+    fn visit_u64<E>(&mut self, value: u64) -> Result<Number, E>
+        where E: Error
+    {
+        Ok(Number(value as f32))
+    }
+}
@@ -36,6 +36,12 @@
 //! The `data` key MAY be omitted if the message doesn't define any properties.
 //! Optional properties MUST be omitted if they're not present (and not set to `null`).
 //!
+//! Messages from the server to a client which asked for it when connecting also have a top-level `seq` key,
+//! counting the messages sent to that client so far, so that it can detect gaps and reordering.
+//! Clients MAY number their messages the same way, the server then acknowledges them with **input_ack**,
+//! so that clients predicting the results of their inputs know which ones the server has caught up with.
+//! It's not part of the message itself, see `Message::from_str_with_seq()` for getting it.
+//!
 //! Newlines and indenting added for example purposes: all the exchanged messages SHOULD NOT contain any unnecessary whitespace.
 //!
 //! A message is malformed if it:
@@ -48,19 +54,33 @@
 //! All malformed messages MUST be rejected.
 
 mod err;
+mod fast;
 mod player_bullet;
+mod score;
 
+use std::io;
 use std::str::FromStr;
 use std::collections::BTreeMap;
+use std::{u32, u64, u8, usize};
 use serde;
 use serde_json;
 
 pub use self::err::*;
 pub use self::player_bullet::*;
+pub use self::score::*;
 
 #[cfg(test)]
 mod tests;
 
+/// Amount of available emotes, **emote** messages' `emote` MUST be lower than this, others are answered with **nack**.
+pub static EMOTE_COUNT: u8 = 8;
+
+/// Maximum length of **go_away** and **nack** messages' `reason`, in characters, longer ones are truncated when sent and rejected when parsed.
+pub static MAX_REASON_LENGTH: usize = 256;
+
+/// Maximum difference between the magnitude of a vector which MUST be normalised and 1, allowing for floating-point error.
+pub static NORMALISATION_EPSILON: f32 = 0.001;
+
 /// Representation of discrete messages used for communication with the client.
 ///
 /// Refer to the module-level documentation for more.
@@ -70,21 +90,23 @@ mod tests;
 /// Serialising a message for sending to a client:
 ///
 /// ```
-/// # let (id, x, y) = (0, 0, 0);
+/// # use tatsoryk_server::message::Message;
+/// # let (id, x, y) = (0, 0.0, 0.0);
 /// let message = Message::PlayerSpawned{
 ///     id: id,
 ///     x: x,
 ///     y: y,
-/// }
+/// };
 /// let to_send = message.to_string();
 /// ```
 ///
 /// Deserialising a message received from a client:
 ///
 /// ```
+/// # use tatsoryk_server::message::Message;
 /// let msg_text = r#"{"type": "stop_moving"}"#.to_string();  // example
-/// match str::parse(&msg_text) {
-///     Ok(message: Message) => println!("Great! Message correct!"),
+/// match msg_text.parse::<Message>() {
+///     Ok(message) => println!("Great! Message correct: {:?}", message),
 ///     Err(error) => println!("Message malformed: {:?}", error),
 /// }
 /// ```
@@ -99,17 +121,19 @@ pub enum Message {
     /// - `size` (f32) — size of the player vehicle
     /// - `bullet_speed` (f32) — speed of movement of player bullets
     /// - `bullet_size` (f32) — size of the player bullets
+    /// - `features` (str[]) — optional features enabled on the server, e.g. `"ready"` if it waits for **ready** to spawn players
     Welcome {
         id: u32,
         speed: f32,
         size: f32,
         bullet_speed: f32,
         bullet_size: f32,
+        features: Vec<String>,
     },
     /// **go_away** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
     ///
     /// **go_away** — sent by the server if it rejects/terminates client connection for any reason
-    /// - `reason` (str) — a message to be displayed to the user
+    /// - `reason` (str) — a message to be displayed to the user, at most `MAX_REASON_LENGTH` characters long
     GoAway {
         reason: String,
     },
@@ -138,6 +162,8 @@ pub enum Message {
     /// - `aim_x` (f32) — player's aiming vector X at the moment of firing
     /// - `aim_y` (f32) — player's aiming direction vector Y at the moment of firing
     ///                   (aiming direction vector MUST be normalised, i.e. its magnitude MUST be equal to 1)
+    /// - `tick` (u64) — server tick during which the bullet was fired; absent drag, the bullet moves `bullet_speed` along
+    ///                  the aiming vector every tick after that, so clients can extrapolate its position without updates
     ShotsFired {
         id: u32,
         bullet_id: u32,
@@ -145,6 +171,7 @@ pub enum Message {
         y: f32,
         aim_x: f32,
         aim_y: f32,
+        tick: u64,
     },
     /// **player_spawned** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
     ///
@@ -174,8 +201,8 @@ pub enum Message {
     /// - `id` (u32) — ID of the player
     /// - `x` (f32) — position X of the player when they started to move (center)
     /// - `y` (f32) — position Y of the player when they started to move (center)
-    /// - `move_x` (f32) — player's movement vector X
-    /// - `move_y` (f32) — player's movement vector Y (movement vector MUST be normalised)
+    /// - `move_x` (f32) — player's movement vector X; MAY be omitted when receiving, meaning 0
+    /// - `move_y` (f32) — player's movement vector Y (movement vector MUST be normalised); MAY be omitted when receiving, meaning 0
     PlayerMoving {
         id: u32,
         x: f32,
@@ -185,10 +212,12 @@ pub enum Message {
     },
     /// **player_stopped** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
     ///
-    /// **player_stopped** — sent by the server to all connected clients when a player stops moving
+    /// **player_stopped** — sent by the server to all connected clients when a player stops moving,
+    ///                      including when it runs into a wall or another player
     /// - `id` (u32) — ID of the player
-    /// - `x` (f32) — final position X of the player (center)
-    /// - `y` (f32) — final position Y of the player (center)
+    /// - `x` (f32) — final position X of the player (center), which clients snap to rather than interpolate towards,
+    ///               i.e. exactly against the wall if it ran into one
+    /// - `y` (f32) — final position Y of the player (center), likewise
     PlayerStopped {
         id: u32,
         x: f32,
@@ -202,26 +231,121 @@ pub enum Message {
     ///   - `id` (u32) — ID of the player
     ///   - `x` (f32) — current position X of the player
     ///   - `y` (f32) — current position Y of the player
-    ///   - `move_x` (Optional&lt;f32&gt;) — current movement vector X of the player, if player is moving
-    ///   - `move_y` (Optional&lt;f32&gt;) — current movement vector Y of the player, if player is moving
+    ///   - `move_x` (Optional&lt;f32&gt;) — current movement vector X of the player, if player is moving along X
+    ///   - `move_y` (Optional&lt;f32&gt;) — current movement vector Y of the player, if player is moving along Y
+    ///                                      (either can be present without the other, the missing one meaning 0)
     /// - `alive_bullets` (Bullet[]) — an array of all currently alive bullets, each containing:
     ///   - `id` (u32) — ID of the bullet
     ///   - `x` (f32) — current position X of the bullet
     ///   - `y` (f32) — current position Y of the bullet
     ///   - `move_x` (f32) — current movement vector X of the bullet
     ///   - `move_y` (f32) — current movement direction vector Y of the bullet
-    ///                      (movement direction vectors MUST be normalised, i.e. their magnitude MUST be equal to 1)
+    ///                      (movement direction vectors MUST be normalised, i.e. their magnitude MUST be equal to 1,
+    ///                       unless the bullet has been slowed down, as in **bullet_moving**)
     WorldState {
         player_count: u32,
         alive_players: Vec<Player>,
         alive_bullets: Vec<Bullet>,
     },
+    /// **player_teleported** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **player_teleported** — sent by the server to all connected clients when the server relocates a player discontinuously
+    ///                         (clients SHOULD snap the player to the new position instead of interpolating)
+    /// - `id` (u32) — ID of the player
+    /// - `x` (f32) — new position X of the player (center)
+    /// - `y` (f32) — new position Y of the player (center)
+    PlayerTeleported {
+        id: u32,
+        x: f32,
+        y: f32,
+    },
+    /// **bullet_moving** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **bullet_moving** — sent by the server to all connected clients when a bullet's velocity changes (e.g. slowed down by drag)
+    /// - `id` (u32) — ID of the bullet
+    /// - `x` (f32) — position X of the bullet when its velocity changed (center)
+    /// - `y` (f32) — position Y of the bullet when its velocity changed (center)
+    /// - `move_x` (f32) — bullet's new movement vector X
+    /// - `move_y` (f32) — bullet's new movement vector Y (movement vector is relative to `bullet_speed`,
+    ///                    so its magnitude MAY be less than 1)
+    BulletMoving {
+        id: u32,
+        x: f32,
+        y: f32,
+        move_x: f32,
+        move_y: f32,
+    },
+    /// **time_sync_response** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **time_sync_response** — sent by the server to a client in response to its **time_sync_request**
+    ///                          (the client can then estimate the round-trip time and its clock's offset from the server's)
+    /// - `client_time` (f64) — `client_time` from the request, unchanged
+    /// - `server_time` (f64) — time elapsed on the server's monotonic clock since the game started when the request was processed
+    TimeSyncResponse {
+        client_time: f64,
+        server_time: f64,
+    },
+    /// **bullet_destroyed** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **bullet_destroyed** — sent by the server to all connected clients when a bullet is removed without hitting anything
+    ///                        (e.g. evicted to make room for a newer one)
+    /// - `id` (u32) — ID of the bullet
+    BulletDestroyed {
+        id: u32,
+    },
+    /// **player_appearance** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **player_appearance** — sent by the server to all connected clients when a player changes its appearance,
+    ///                         and to a newly connected client for every player which set one before
+    /// - `id` (u32) — ID of the player
+    /// - `color` (u32) — player's color, packed as `0xRRGGBB`
+    PlayerAppearance {
+        id: u32,
+        color: u32,
+    },
+    /// **player_count** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **player_count** — sent by the server to all connected clients whenever a player joins or leaves
+    /// - `count` (u32) — amount of players currently connected, same as **world_state**'s `player_count`
+    PlayerCount {
+        count: u32,
+    },
+    /// **fire_cooldown** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **fire_cooldown** — sent by the server to a client which fired, if fire rate is limited,
+    ///                     whether the shot went through or was suppressed
+    /// - `ready_in_ticks` (u32) — amount of ticks until the player can fire again
+    FireCooldown {
+        ready_in_ticks: u32,
+    },
+    /// **round_start** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **round_start** — sent by the server to all connected clients when a round starts, if the game is played in rounds,
+    ///                   and to a newly connected client for the round in progress
+    ///                   (all players are respawned right after it, and all bullets are destroyed right before it)
+    /// - `round` (u32) — number of the round, starting at 1
+    /// - `duration_ticks` (u32) — amount of ticks until the round ends
+    RoundStart {
+        round: u32,
+        duration_ticks: u32,
+    },
+    /// **round_end** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **round_end** — sent by the server to all connected clients when a round ends, right before the next one starts
+    /// - `round` (u32) — number of the round which ended
+    /// - `scores` (Score[]) — an array of the scores of all connected players in the round, each containing:
+    ///   - `id` (u32) — ID of the player
+    ///   - `kills` (u32) — amount of players it destroyed during the round
+    RoundEnd {
+        round: u32,
+        scores: Vec<Score>,
+    },
     /// **start_moving** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
     ///
     /// **start_moving** — sent by the client to the server when the player wants to start moving or change its movement direction
     ///                    (i.e. presses/releases one or more movement keys, as long as at least one of them is still held)
-    /// - `move_x` (f32) — player's movement vector X
-    /// - `move_y` (f32) — player's movement vector Y
+    /// - `move_x` (f32) — player's movement vector X; MAY be omitted, meaning 0
+    /// - `move_y` (f32) — player's movement vector Y; MAY be omitted, meaning 0
     /// (movement vector SHOULD be normalised, but the server MUST NOT assume that it is)
     StartMoving {
         move_x: f32,
@@ -240,23 +364,322 @@ pub enum Message {
         move_x: f32,
         move_y: f32,
     },
+    /// **request_respawn** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **request_respawn** — sent by the client to the server when a dead player wants to respawn
+    ///                       (the server ignores it unless the player is dead and the respawn delay has passed)
+    RequestRespawn,
+    /// **time_sync_request** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **time_sync_request** — sent by the client to the server when it wants to synchronise its clock with the server's
+    /// - `client_time` (f64) — client's time when sending the request, opaque to the server
+    TimeSyncRequest {
+        client_time: f64,
+    },
+    /// **set_appearance** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **set_appearance** — sent by the client to the server when the player wants to change its appearance
+    /// - `color` (u32) — player's color, packed as `0xRRGGBB`
+    SetAppearance {
+        color: u32,
+    },
+    /// **position_report** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **position_report** — sent by the client to the server with the position it computed for its player,
+    ///                       which the server accepts only if the player could have moved there, if at all
+    /// - `x` (f32) — position X of the player vehicle (center)
+    /// - `y` (f32) — position Y of the player vehicle (center)
+    PositionReport {
+        x: f32,
+        y: f32,
+    },
+    /// **ready** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **ready** — sent by the client to the server after receiving **welcome**, when it's ready for its player to spawn
+    ///             (the server spawns players without it unless configured otherwise)
+    Ready,
+    /// **emote** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **emote** — sent by the client to the server when the player wants to express itself,
+    ///             and by the server to all connected clients on its behalf
+    /// - `id` (Option&lt;u32&gt;) — ID of the player; MAY be omitted by clients, as the server fills in the sender's
+    /// - `emote` (u8) — index of the emote, lower than `EMOTE_COUNT`
+    Emote {
+        id: Option<u32>,
+        emote: u8,
+    },
+    /// **game_paused** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **game_paused** — sent by the server to all connected clients when the game is paused or resumed,
+    ///                   and to joining clients if it's paused (nothing moves while the game is paused)
+    /// - `paused` (bool) — whether the game is paused now
+    GamePaused {
+        paused: bool,
+    },
+    /// **server_shutdown** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **server_shutdown** — sent by the server to all connected clients when it starts shutting down,
+    ///                       ahead of the **go_away** sent when it's done
+    /// - `seconds_remaining` (u32) — amount of seconds the game goes on for before the server goes away
+    ServerShutdown {
+        seconds_remaining: u32,
+    },
+    /// **players_moving** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **players_moving** — sent by the server to all connected clients instead of several **player_moving**
+    ///                      when multiple players start moving in the same tick
+    /// - `updates` (Player[]) — the players which started moving, with their positions when they did and movement vectors
+    PlayersMoving {
+        updates: Vec<Player>,
+    },
+    /// **player_facing** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **player_facing** — sent by the server to all connected clients when a player standing still turns to face elsewhere,
+    ///                     as moving players face where they move
+    /// - `id` (u32) — ID of the player
+    /// - `facing_x` (f32) — X of the direction the player is facing
+    /// - `facing_y` (f32) — Y of the direction the player is facing
+    PlayerFacing {
+        id: u32,
+        facing_x: f32,
+        facing_y: f32,
+    },
+    /// **request_stats** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **request_stats** — sent by the client to the server to get statistics of the messages sent so far,
+    ///                     answered with **stats** only if the server counts them
+    RequestStats,
+    /// **stats** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **stats** — sent by the server to a client in response to **request_stats**
+    /// - `per_type` ({type: str, count: u64, bytes: u64}[]) — amount of every type of message sent to clients so far,
+    ///                                                       and their total size in bytes, ordered by type
+    Stats {
+        per_type: Vec<(String, u64, u64)>,
+    },
+    /// **shot_blocked** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **shot_blocked** — sent by the server to a client which fired into a wall too close for the bullet to fit,
+    ///                    so no bullet was fired
+    ShotBlocked,
+    /// **request_player_stats** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **request_player_stats** — sent by the client to the server to inspect a player,
+    ///                            answered with **player_stats** if a player with that ID is connected, and **nack** otherwise
+    /// - `id` (u32) — ID of the player
+    RequestPlayerStats {
+        id: u32,
+    },
+    /// **player_stats** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **player_stats** — sent by the server to a client in response to **request_player_stats**,
+    ///                    there's no team to report as every player plays for themselves
+    /// - `id` (u32) — ID of the player
+    /// - `kills` (u32) — amount of players the player destroyed in the round in progress
+    /// - `deaths` (u32) — amount of times the player was destroyed in the round in progress
+    /// - `health` (f32) — health the player has left, 0 if it isn't alive
+    PlayerStats {
+        id: u32,
+        kills: u32,
+        deaths: u32,
+        health: f32,
+    },
+    /// **input_ack** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **input_ack** — sent by the server to a client numbering its messages at the end of every tick in which any of them
+    ///                 were processed, their effects being included in the updates sent that tick
+    /// - `seq` (u64) — highest `seq` of the client's messages processed so far
+    InputAck {
+        seq: u64,
+    },
+    /// **kill_streak** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **kill_streak** — sent by the server to all clients when a player destroys a notable amount of players in a row,
+    ///                   each within a short time of the previous one, without being destroyed itself
+    /// - `id` (u32) — ID of the player on the streak
+    /// - `streak` (u32) — amount of players destroyed in a row so far
+    KillStreak {
+        id: u32,
+        streak: u32,
+    },
+    /// **nack** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **nack** — sent by the server to a client whose message it refused, without disconnecting it,
+    ///            e.g. an **emote** with an index out of range
+    /// - `reason` (str) — why the message was refused, at most `MAX_REASON_LENGTH` characters long
+    Nack {
+        reason: String,
+    },
+    /// **set_paused** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec)
+    ///
+    /// **set_paused** — sent by the client to the server when an administrator wants to pause or resume the game,
+    ///                  answered with **nack** if the server doesn't allow it or the password is wrong
+    /// - `paused` (bool) — whether the game should be paused
+    /// - `password` (str) — the administrator password the server was configured with
+    SetPaused {
+        paused: bool,
+        password: String,
+    },
 }
 
 impl ToString for Message {
     fn to_string(&self) -> String {
+        self.to_string_with_precision(None)
+    }
+}
+
+impl Message {
+    /// Create a **world_state** message with the specified amount of connected players, alive players and bullets.
+    ///
+    /// Connected players which aren't alive count towards `player_count` too, so it can't be lower than the alive ones.
+    pub fn world_state<P, B>(player_count: u32, players: P, bullets: B) -> Message
+        where P: IntoIterator<Item = Player>,
+              B: IntoIterator<Item = Bullet>
+    {
+        Message::WorldState {
+            player_count: player_count,
+            alive_players: players.into_iter().collect(),
+            alive_bullets: bullets.into_iter().collect(),
+        }
+    }
+
+    /// Check the message's semantic invariants, which its structure alone doesn't guarantee.
+    ///
+    /// All positions, vectors and sizes must be finite, **world_state** can't have more alive players than connected ones,
+    /// and **player_destroyed** needs both the killer and the bullet or neither.
+    pub fn validate(&self) -> Result<(), MessageError> {
+        match self {
+            &Message::Welcome { speed, size, bullet_speed, bullet_size, .. } => {
+                validate_finite("welcome", &[speed, size, bullet_speed, bullet_size])
+            }
+            &Message::ShotsFired { x, y, aim_x, aim_y, .. } => validate_finite("shots_fired", &[x, y, aim_x, aim_y]),
+            &Message::PlayerSpawned { x, y, .. } => validate_finite("player_spawned", &[x, y]),
+            &Message::PlayerStats { health, .. } => validate_finite("player_stats", &[health]),
+            &Message::PlayerDestroyed { killer_id, bullet_id, .. } => {
+                if killer_id.is_some() == bullet_id.is_some() {
+                    Ok(())
+                } else {
+                    Err(MessageError::InvalidValue(format!("player_destroyed with killer {:?} but bullet {:?}",
+                                                           killer_id,
+                                                           bullet_id)))
+                }
+            }
+            &Message::PlayerMoving { x, y, move_x, move_y, .. } => validate_finite("player_moving", &[x, y, move_x, move_y]),
+            &Message::PlayerStopped { x, y, .. } => validate_finite("player_stopped", &[x, y]),
+            &Message::WorldState { player_count, ref alive_players, ref alive_bullets } => {
+                if alive_players.len() > player_count as usize {
+                    return Err(MessageError::InvalidValue(format!("world_state with {} alive players out of {}",
+                                                                  alive_players.len(),
+                                                                  player_count)));
+                }
+                if alive_players.iter().any(|player| !player.is_finite()) ||
+                   alive_bullets.iter().any(|bullet| !bullet.is_finite()) {
+                    return Err(MessageError::InvalidValue("world_state with non-finite positions or vectors".to_string()));
+                }
+                Ok(())
+            }
+            &Message::PlayerTeleported { x, y, .. } => validate_finite("player_teleported", &[x, y]),
+            &Message::BulletMoving { x, y, move_x, move_y, .. } => validate_finite("bullet_moving", &[x, y, move_x, move_y]),
+            &Message::TimeSyncResponse { client_time, server_time } => {
+                if client_time.is_finite() && server_time.is_finite() {
+                    Ok(())
+                } else {
+                    Err(MessageError::InvalidValue("time_sync_response with non-finite times".to_string()))
+                }
+            }
+            &Message::PlayersMoving { ref updates } => {
+                if updates.iter().any(|player| !player.is_finite()) {
+                    return Err(MessageError::InvalidValue("players_moving with non-finite positions or vectors".to_string()));
+                }
+                if updates.iter().any(|player| player.move_x.is_none() || player.move_y.is_none()) {
+                    return Err(MessageError::InvalidValue("players_moving with players not moving".to_string()));
+                }
+                Ok(())
+            }
+            &Message::PlayerFacing { facing_x, facing_y, .. } => validate_finite("player_facing", &[facing_x, facing_y]),
+            &Message::StartMoving { move_x, move_y } => validate_finite("start_moving", &[move_x, move_y]),
+            &Message::Fire { move_x, move_y } => validate_finite("fire", &[move_x, move_y]),
+            &Message::PositionReport { x, y } => validate_finite("position_report", &[x, y]),
+            &Message::TimeSyncRequest { client_time } => {
+                if client_time.is_finite() {
+                    Ok(())
+                } else {
+                    Err(MessageError::InvalidValue("time_sync_request with non-finite time".to_string()))
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check whether the vectors the message MUST have normalised are, within `NORMALISATION_EPSILON`.
+    ///
+    /// Bullets in **world_state** may also be slower, the rest of the vectors aren't required to be normalised.
+    pub fn check_normalised(&self) -> Result<(), MessageError> {
+        match self {
+            &Message::ShotsFired { aim_x, aim_y, .. } => check_normalised("shots_fired", aim_x, aim_y, false),
+            &Message::PlayerMoving { move_x, move_y, .. } => check_normalised("player_moving", move_x, move_y, false),
+            &Message::PlayersMoving { ref updates } => {
+                for (move_x, move_y) in updates.iter().filter_map(Player::movement) {
+                    try!(check_normalised("players_moving", move_x, move_y, false));
+                }
+                Ok(())
+            }
+            &Message::WorldState { ref alive_bullets, .. } => {
+                for (move_x, move_y) in alive_bullets.iter().filter_map(Bullet::movement) {
+                    try!(check_normalised("world_state", move_x, move_y, true));
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Serialise the message, rounding its positions and vectors to the specified amount of decimal places, if any.
+    ///
+    /// Rounding shrinks the payload and gets rid of the noise from widening `f32`s, e.g. `34.66000000000001`.
+    /// Times in **time_sync_request** and **time_sync_response** are never rounded.
+    pub fn to_string_with_precision(&self, precision: Option<u32>) -> String {
+        self.to_typed_string_with_precision(precision).1
+    }
+
+    /// Serialise the message like `to_string_with_precision()` does, along with its type, e.g. `"world_state"`.
+    pub fn to_typed_string_with_precision(&self, precision: Option<u32>) -> (&'static str, String) {
+        let (msg_type, json) = self.to_typed_json_with_precision(precision);
+        (msg_type, serde_json::to_string(&json).unwrap())
+    }
+
+    /// Serialise the message straight into the specified writer, producing the same bytes as `to_string()`,
+    /// without allocating a `String` for it first.
+    pub fn write_to<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        self.write_to_with_precision(w, None)
+    }
+
+    /// Serialise the message straight into the specified writer, producing the same bytes as `to_string_with_precision()`.
+    pub fn write_to_with_precision<W: io::Write>(&self, w: &mut W, precision: Option<u32>) -> io::Result<()> {
+        match serde_json::to_writer(w, &self.to_typed_json_with_precision(precision).1) {
+            Ok(()) => Ok(()),
+            Err(serde_json::Error::Io(error)) => Err(error),
+            // Values always serialise, barring the writer failing
+            Err(error) => Err(io::Error::new(io::ErrorKind::Other, error.to_string())),
+        }
+    }
+
+    /// Convert the message into its JSON representation, rounded like `to_string_with_precision()` does, along with its type.
+    fn to_typed_json_with_precision(&self, precision: Option<u32>) -> (&'static str, serde_json::Value) {
         let mut values = BTreeMap::new();
         let msg_type = match self {
-            &Message::Welcome { id, speed, size, bullet_speed, bullet_size } => {
+            &Message::Welcome { id, speed, size, bullet_speed, bullet_size, ref features } => {
                 add_data_id_speeds_sizes_entries(&mut values,
                                                  id,
                                                  speed,
                                                  size,
                                                  bullet_speed,
                                                  bullet_size);
+                add_data_entry(&mut values, "features", &features);
                 "welcome"
             }
             &Message::GoAway { ref reason } => {
-                add_data_entry(&mut values, "reason", &reason);
+                add_data_entry(&mut values, "reason", &truncate_reason(reason));
                 "go_away"
             }
             &Message::PlayerJoined { id } => {
@@ -267,8 +690,8 @@ impl ToString for Message {
                 add_data_entry(&mut values, "id", &id);
                 "player_left"
             }
-            &Message::ShotsFired { id, bullet_id, x, y, aim_x, aim_y } => {
-                add_shot_data_entries(&mut values, id, bullet_id, x, y, aim_x, aim_y);
+            &Message::ShotsFired { id, bullet_id, x, y, aim_x, aim_y, tick } => {
+                add_shot_data_entries(&mut values, id, bullet_id, x, y, aim_x, aim_y, tick);
                 "shots_fired"
             }
             &Message::PlayerSpawned { id, x, y } => {
@@ -305,6 +728,48 @@ impl ToString for Message {
                                &alive_bullets.iter().map(|ref b| b.to_json()).collect::<Vec<_>>());
                 "world_state"
             }
+            &Message::PlayerTeleported { id, x, y } => {
+                add_data_id_pos_entries(&mut values, id, x, y);
+                "player_teleported"
+            }
+            &Message::BulletMoving { id, x, y, move_x, move_y } => {
+                add_data_id_pos_moves_entries(&mut values, id, x, y, move_x, move_y);
+                "bullet_moving"
+            }
+            &Message::TimeSyncResponse { client_time, server_time } => {
+                add_data_entry(&mut values, "client_time", &client_time);
+                add_data_entry(&mut values, "server_time", &server_time);
+                "time_sync_response"
+            }
+            &Message::BulletDestroyed { id } => {
+                add_data_entry(&mut values, "id", &id);
+                "bullet_destroyed"
+            }
+            &Message::PlayerAppearance { id, color } => {
+                add_data_entry(&mut values, "id", &id);
+                add_data_entry(&mut values, "color", &color);
+                "player_appearance"
+            }
+            &Message::PlayerCount { count } => {
+                add_data_entry(&mut values, "count", &count);
+                "player_count"
+            }
+            &Message::FireCooldown { ready_in_ticks } => {
+                add_data_entry(&mut values, "ready_in_ticks", &ready_in_ticks);
+                "fire_cooldown"
+            }
+            &Message::RoundStart { round, duration_ticks } => {
+                add_data_entry(&mut values, "round", &round);
+                add_data_entry(&mut values, "duration_ticks", &duration_ticks);
+                "round_start"
+            }
+            &Message::RoundEnd { round, ref scores } => {
+                add_data_entry(&mut values, "round", &round);
+                add_data_entry(&mut values,
+                               "scores",
+                               &scores.iter().map(|ref s| s.to_json()).collect::<Vec<_>>());
+                "round_end"
+            }
             &Message::StartMoving { move_x, move_y } => {
                 add_data_move_entries(&mut values, move_x, move_y);
                 "start_moving"
@@ -314,7 +779,104 @@ impl ToString for Message {
                 add_data_move_entries(&mut values, move_x, move_y);
                 "fire"
             }
+            &Message::RequestRespawn => "request_respawn",
+            &Message::TimeSyncRequest { client_time } => {
+                add_data_entry(&mut values, "client_time", &client_time);
+                "time_sync_request"
+            }
+            &Message::SetAppearance { color } => {
+                add_data_entry(&mut values, "color", &color);
+                "set_appearance"
+            }
+            &Message::PositionReport { x, y } => {
+                add_data_entry(&mut values, "x", &x);
+                add_data_entry(&mut values, "y", &y);
+                "position_report"
+            }
+            &Message::Ready => "ready",
+            &Message::Emote { id, emote } => {
+                if let Some(id) = id {
+                    add_data_entry(&mut values, "id", &id);
+                }
+                add_data_entry(&mut values, "emote", &emote);
+                "emote"
+            }
+            &Message::GamePaused { paused } => {
+                add_data_entry(&mut values, "paused", &paused);
+                "game_paused"
+            }
+            &Message::ServerShutdown { seconds_remaining } => {
+                add_data_entry(&mut values, "seconds_remaining", &seconds_remaining);
+                "server_shutdown"
+            }
+            &Message::PlayersMoving { ref updates } => {
+                add_data_entry(&mut values,
+                               "updates",
+                               &updates.iter().map(|ref p| p.to_json()).collect::<Vec<_>>());
+                "players_moving"
+            }
+            &Message::PlayerFacing { id, facing_x, facing_y } => {
+                add_data_entry(&mut values, "id", &id);
+                add_data_entry(&mut values, "facing_x", &facing_x);
+                add_data_entry(&mut values, "facing_y", &facing_y);
+                "player_facing"
+            }
+            &Message::RequestStats => "request_stats",
+            &Message::Stats { ref per_type } => {
+                add_data_entry(&mut values,
+                               "per_type",
+                               &per_type.iter()
+                                        .map(|&(ref msg_type, count, bytes)| type_stats_json(msg_type, count, bytes))
+                                        .collect::<Vec<_>>());
+                "stats"
+            }
+            &Message::ShotBlocked => "shot_blocked",
+            &Message::RequestPlayerStats { id } => {
+                add_data_entry(&mut values, "id", &id);
+                "request_player_stats"
+            }
+            &Message::PlayerStats { id, kills, deaths, health } => {
+                add_data_entry(&mut values, "id", &id);
+                add_data_entry(&mut values, "kills", &kills);
+                add_data_entry(&mut values, "deaths", &deaths);
+                add_data_entry(&mut values, "health", &health);
+                "player_stats"
+            }
+            &Message::InputAck { seq } => {
+                add_data_entry(&mut values, "seq", &seq);
+                "input_ack"
+            }
+            &Message::KillStreak { id, streak } => {
+                add_data_entry(&mut values, "id", &id);
+                add_data_entry(&mut values, "streak", &streak);
+                "kill_streak"
+            }
+            &Message::Nack { ref reason } => {
+                add_data_entry(&mut values, "reason", &truncate_reason(reason));
+                "nack"
+            }
+            &Message::SetPaused { paused, ref password } => {
+                add_data_entry(&mut values, "paused", &paused);
+                add_data_entry(&mut values, "password", password);
+                "set_paused"
+            }
+        };
+
+        debug_assert!(decompose_assert_schema(&values, schema(msg_type).unwrap()).is_ok(),
+                      "{} serialised with keys not in SCHEMA: {:?}",
+                      msg_type,
+                      values.keys().collect::<Vec<_>>());
+
+        let exact_floats = match self {
+            &Message::TimeSyncRequest { .. } |
+            &Message::TimeSyncResponse { .. } => true,
+            _ => false,
         };
+        if let (Some(precision), false) = (precision, exact_floats) {
+            for (_, value) in &mut values {
+                round_floats(value, precision);
+            }
+        }
 
         let mut root_obj = BTreeMap::new();
         let _ = root_obj.insert("type".to_string(),
@@ -323,15 +885,74 @@ impl ToString for Message {
             let _ = root_obj.insert("data".to_string(), serde_json::Value::Object(values));
         }
 
-        serde_json::to_string(&serde_json::Value::Object(root_obj)).unwrap()
+        (msg_type, serde_json::Value::Object(root_obj))
     }
 }
 
+/// Add the specified sequence number to a serialised message, as a top-level `seq` alongside `type` and `data`.
+pub fn with_seq(encoded: &str, seq: u64) -> String {
+    debug_assert!(encoded.starts_with('{'), "{} not a serialised message", encoded);
+    format!(r#"{{"seq":{},{}"#, seq, &encoded[1..])
+}
+
 impl FromStr for Message {
     type Err = MessageError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Message::from_str_with_max_array_length(s, usize::MAX)
+    }
+}
+
+impl Message {
+    /// Parse a message like `from_str()` does, rejecting it if any of its arrays has more than the specified amount of elements.
+    ///
+    /// The length of an array is checked before any of its elements are unpacked.
+    pub fn from_str_with_max_array_length(s: &str, max_array_length: usize) -> Result<Message, MessageError> {
+        Ok(try!(Message::from_str_with_seq(s, max_array_length)).0)
+    }
+
+    /// Parse a message like `from_str_with_max_array_length()` does, along with its top-level `seq`, if it has one.
+    pub fn from_str_with_seq(s: &str, max_array_length: usize) -> Result<(Message, Option<u64>), MessageError> {
+        // Messages with a seq never take the fast path
+        let (message, seq) = match fast::parse(s) {
+            Some(message) => (message, None),
+            None => try!(Message::parse_sequenced(s, max_array_length)),
+        };
+        try!(message.validate());
+        Ok((message, seq))
+    }
+
+    /// Parse the message at the start of the specified text, returning it along with the rest of the text,
+    /// so that a buffer holding several messages can be consumed one at a time.
+    ///
+    /// Whitespace before the message is skipped, whitespace after it is left in the rest.
+    pub fn parse_prefix(input: &str) -> Result<(Message, &str), MessageError> {
+        match json_object_end(input) {
+            Some(end) => Ok((try!(input[..end].parse()), &input[end..])),
+            None => {
+                let _ = try!(serde_json::from_str::<serde_json::Value>(input));
+                Err(MessageError::BadType("Top-level JSON not an Object".to_string()))
+            }
+        }
+    }
+
+    /// Parse a message from its JSON representation, without checking its semantic invariants.
+    fn parse(s: &str, max_array_length: usize) -> Result<Message, MessageError> {
+        Ok(try!(Message::parse_sequenced(s, max_array_length)).0)
+    }
+
+    /// Parse a message like `parse()` does, along with its top-level `seq`, if it has one.
+    fn parse_sequenced(s: &str, max_array_length: usize) -> Result<(Message, Option<u64>), MessageError> {
         let json: serde_json::Value = try!(serde_json::from_str(s));
 
+        let seq = match json.as_object().and_then(|msg| msg.get("seq")) {
+            Some(seq) => Some(try!(unpack_u64(seq))),
+            None => None,
+        };
+        Ok((try!(Message::parse_json(&json, max_array_length)), seq))
+    }
+
+    /// Parse a message from its already decoded JSON representation, without checking its semantic invariants.
+    fn parse_json(json: &serde_json::Value, max_array_length: usize) -> Result<Message, MessageError> {
         match json.as_object() {
             Some(msg) => {
                 let msg_type = try!(match msg.get("type") {
@@ -346,8 +967,10 @@ impl FromStr for Message {
                     }
                 });
 
-                let keys = msg.keys().collect::<Vec<_>>();
-                if msg_type == "stop_moving" {
+                // The seq was already unpacked by parse_sequenced()
+                let mut keys = msg.keys().collect::<Vec<_>>();
+                keys.retain(|key| *key != "seq");
+                if is_dataless(msg_type) {
                     if keys != vec!["data", "type"] && keys != vec!["type"] {
                         return Err(MessageError::PropertyMissing(format!(r#"Top-level Object is a mismatch for `{{"type"[, "data"]}}`: {:?}"#, keys)));
                     }
@@ -357,10 +980,9 @@ impl FromStr for Message {
 
                 match msg.get("data") {
                     None => {
-                        if msg_type == "stop_moving" {
-                            Ok(Message::StopMoving)
-                        } else {
-                            Err(MessageError::PropertyMissing(r#"Top-level Object doesn't have "data""#.to_string()))
+                        match dataless_message(msg_type) {
+                            Some(message) => Ok(message),
+                            None => Err(MessageError::PropertyMissing(r#"Top-level Object doesn't have "data""#.to_string())),
                         }
                     }
                     Some(data) => {
@@ -370,20 +992,24 @@ impl FromStr for Message {
                                                               .to_string()))
                             }
                             Some(data) => {
-                                if msg_type == "stop_moving" && !data.is_empty() {
+                                if is_dataless(msg_type) && !data.is_empty() {
                                     return Err(MessageError::ExtraneousProperty(r#"Non-empty "data" for dataless message"#.to_string()));
                                 }
+                                if let Some(fields) = schema(msg_type) {
+                                    try!(decompose_assert_schema(&data, fields));
+                                }
 
                                 match &msg_type[..] {
                                     "welcome" => {
-                                        let (id, speed, size, bullet_speed, bullet_size) =
-                                            try!(decompose_stats(&data));
+                                        let (id, speed, size, bullet_speed, bullet_size, features) =
+                                            try!(decompose_stats(&data, max_array_length));
                                         Ok(Message::Welcome {
                                             id: id,
                                             speed: speed,
                                             size: size,
                                             bullet_speed: bullet_speed,
                                             bullet_size: bullet_size,
+                                            features: features,
                                         })
                                     }
                                     "go_away" => {
@@ -398,7 +1024,7 @@ impl FromStr for Message {
                                         Ok(Message::PlayerLeft { id: try!(decompose_id(&data)) })
                                     }
                                     "shots_fired" => {
-                                        let (id, bullet_id, x, y, aim_x, aim_y) =
+                                        let (id, bullet_id, x, y, aim_x, aim_y, tick) =
                                             try!(decompose_shot(&data));
                                         Ok(Message::ShotsFired {
                                             id: id,
@@ -407,6 +1033,7 @@ impl FromStr for Message {
                                             y: y,
                                             aim_x: aim_x,
                                             aim_y: aim_y,
+                                            tick: tick,
                                         })
                                     }
                                     "player_spawned" => {
@@ -427,8 +1054,8 @@ impl FromStr for Message {
                                         })
                                     }
                                     "player_moving" => {
-                                        let (id, x, y, move_x, move_y) =
-                                            try!(decompose_id_pos_moves(&data));
+                                        let (id, x, y) = try!(decompose_id_pos(&data));
+                                        let (move_x, move_y) = try!(decompose_optional_moves(&data));
                                         Ok(Message::PlayerMoving {
                                             id: id,
                                             x: x,
@@ -447,21 +1074,143 @@ impl FromStr for Message {
                                     }
                                     "world_state" => {
                                         let (player_count, alive_players, alive_bullets) =
-                                            try!(decompose_world_state(&data));
+                                            try!(decompose_world_state(&data, max_array_length));
                                         Ok(Message::WorldState {
                                             player_count: player_count,
                                             alive_players: alive_players,
                                             alive_bullets: alive_bullets,
                                         })
                                     }
+                                    "player_teleported" => {
+                                        let (id, x, y) = try!(decompose_id_pos(&data));
+                                        Ok(Message::PlayerTeleported {
+                                            id: id,
+                                            x: x,
+                                            y: y,
+                                        })
+                                    }
+                                    "bullet_moving" => {
+                                        let (id, x, y, move_x, move_y) =
+                                            try!(decompose_id_pos_moves(&data));
+                                        Ok(Message::BulletMoving {
+                                            id: id,
+                                            x: x,
+                                            y: y,
+                                            move_x: move_x,
+                                            move_y: move_y,
+                                        })
+                                    }
+                                    "time_sync_response" => {
+                                        let (client_time, server_time) =
+                                            try!(decompose_time_sync_response(&data));
+                                        Ok(Message::TimeSyncResponse {
+                                            client_time: client_time,
+                                            server_time: server_time,
+                                        })
+                                    }
+                                    "bullet_destroyed" => {
+                                        let id = try!(decompose_id(&data));
+                                        Ok(Message::BulletDestroyed { id: id })
+                                    }
+                                    "player_appearance" => {
+                                        let (id, color) = try!(decompose_id_color(&data));
+                                        Ok(Message::PlayerAppearance {
+                                            id: id,
+                                            color: color,
+                                        })
+                                    }
+                                    "player_count" => {
+                                        let count = try!(decompose_count(&data));
+                                        Ok(Message::PlayerCount { count: count })
+                                    }
+                                    "fire_cooldown" => {
+                                        let ready_in_ticks = try!(decompose_fire_cooldown(&data));
+                                        Ok(Message::FireCooldown { ready_in_ticks: ready_in_ticks })
+                                    }
+                                    "round_start" => {
+                                        let (round, duration_ticks) = try!(decompose_round_start(&data));
+                                        Ok(Message::RoundStart {
+                                            round: round,
+                                            duration_ticks: duration_ticks,
+                                        })
+                                    }
+                                    "round_end" => {
+                                        let (round, scores) = try!(decompose_round_end(&data, max_array_length));
+                                        Ok(Message::RoundEnd {
+                                            round: round,
+                                            scores: scores,
+                                        })
+                                    }
                                     "start_moving" => {
-                                        let (move_x, move_y) = try!(decompose_moves(&data));
+                                        let (move_x, move_y) = try!(decompose_optional_moves(&data));
                                         Ok(Message::StartMoving {
                                             move_x: move_x,
                                             move_y: move_y,
                                         })
                                     }
                                     "stop_moving" => Ok(Message::StopMoving),
+                                    "request_respawn" => Ok(Message::RequestRespawn),
+                                    "ready" => Ok(Message::Ready),
+                                    "request_stats" => Ok(Message::RequestStats),
+                                    "shot_blocked" => Ok(Message::ShotBlocked),
+                                    "stats" => {
+                                        let per_type = try!(decompose_per_type(&data, max_array_length));
+                                        Ok(Message::Stats { per_type: per_type })
+                                    }
+                                    "request_player_stats" => {
+                                        Ok(Message::RequestPlayerStats { id: try!(decompose_id(&data)) })
+                                    }
+                                    "player_stats" => {
+                                        let (id, kills, deaths, health) = try!(decompose_player_stats(&data));
+                                        Ok(Message::PlayerStats {
+                                            id: id,
+                                            kills: kills,
+                                            deaths: deaths,
+                                            health: health,
+                                        })
+                                    }
+                                    "input_ack" => Ok(Message::InputAck { seq: try!(unpack_u64(data.get("seq").unwrap())) }),
+                                    "kill_streak" => {
+                                        let (id, streak) = try!(decompose_kill_streak(&data));
+                                        Ok(Message::KillStreak {
+                                            id: id,
+                                            streak: streak,
+                                        })
+                                    }
+                                    "nack" => Ok(Message::Nack { reason: try!(decompose_reason(&data)) }),
+                                    "set_paused" => {
+                                        Ok(Message::SetPaused {
+                                            paused: try!(decompose_paused(&data)),
+                                            password: try!(unpack_str(data.get("password").unwrap())),
+                                        })
+                                    }
+                                    "emote" => {
+                                        let (id, emote) = try!(decompose_id_emote(&data));
+                                        Ok(Message::Emote {
+                                            id: id,
+                                            emote: emote,
+                                        })
+                                    }
+                                    "game_paused" => {
+                                        let paused = try!(decompose_paused(&data));
+                                        Ok(Message::GamePaused { paused: paused })
+                                    }
+                                    "server_shutdown" => {
+                                        let seconds_remaining = try!(decompose_seconds_remaining(&data));
+                                        Ok(Message::ServerShutdown { seconds_remaining: seconds_remaining })
+                                    }
+                                    "players_moving" => {
+                                        let updates = try!(decompose_updates(&data, max_array_length));
+                                        Ok(Message::PlayersMoving { updates: updates })
+                                    }
+                                    "player_facing" => {
+                                        let (id, facing_x, facing_y) = try!(decompose_id_facing(&data));
+                                        Ok(Message::PlayerFacing {
+                                            id: id,
+                                            facing_x: facing_x,
+                                            facing_y: facing_y,
+                                        })
+                                    }
                                     "fire" => {
                                         let (move_x, move_y) = try!(decompose_moves(&data));
                                         Ok(Message::Fire {
@@ -469,10 +1218,20 @@ impl FromStr for Message {
                                             move_y: move_y,
                                         })
                                     }
+                                    "time_sync_request" => {
+                                        let client_time = try!(decompose_time_sync_request(&data));
+                                        Ok(Message::TimeSyncRequest { client_time: client_time })
+                                    }
+                                    "set_appearance" => {
+                                        let color = try!(decompose_color(&data));
+                                        Ok(Message::SetAppearance { color: color })
+                                    }
+                                    "position_report" => {
+                                        let (x, y) = try!(decompose_pos(&data));
+                                        Ok(Message::PositionReport { x: x, y: y })
+                                    }
                                     msg_type => Err(MessageError::BadType(format!(r#"Expected any of {:?}, got: {:?}"#,
-                                                                          vec!["welcome", "go_away", "player_joined", "player_left",
-                                                                               "shots_fired", "player_spawned", "player_destroyed", "player_moving",
-                                                                               "player_stopped", "world_state", "start_moving", "stop_moving", "fire"],
+                                                                          SCHEMA.iter().map(|&(name, _)| name).collect::<Vec<_>>(),
                                                                           msg_type))),
                                 }
                             }
@@ -485,6 +1244,127 @@ impl FromStr for Message {
     }
 }
 
+/// Fields of every message type's `data` Object, in declaration order, and whether each of them is required.
+///
+/// This is the single source of truth for which keys a message has: it's checked when parsing and, in debug builds, when serialising.
+/// Key order doesn't matter on either side, serialised messages have their keys sorted alphabetically.
+static SCHEMA: &'static [(&'static str, &'static [(&'static str, bool)])] =
+    &[("welcome", &[("id", true), ("speed", true), ("size", true), ("bullet_speed", true), ("bullet_size", true), ("features", true)]),
+      ("go_away", &[("reason", true)]),
+      ("player_joined", &[("id", true)]),
+      ("player_left", &[("id", true)]),
+      ("shots_fired", &[("id", true), ("bullet_id", true), ("x", true), ("y", true), ("aim_x", true), ("aim_y", true),
+                       ("tick", true)]),
+      ("player_spawned", &[("id", true), ("x", true), ("y", true)]),
+      ("player_destroyed", &[("id", true), ("killer_id", false), ("bullet_id", false)]),
+      ("player_moving", &[("id", true), ("x", true), ("y", true), ("move_x", false), ("move_y", false)]),
+      ("player_stopped", &[("id", true), ("x", true), ("y", true)]),
+      ("world_state", &[("player_count", true), ("alive_players", true), ("alive_bullets", true)]),
+      ("start_moving", &[("move_x", false), ("move_y", false)]),
+      ("stop_moving", &[]),
+      ("fire", &[("move_x", true), ("move_y", true)]),
+      ("request_respawn", &[]),
+      ("player_teleported", &[("id", true), ("x", true), ("y", true)]),
+      ("bullet_moving", &[("id", true), ("x", true), ("y", true), ("move_x", true), ("move_y", true)]),
+      ("time_sync_request", &[("client_time", true)]),
+      ("time_sync_response", &[("client_time", true), ("server_time", true)]),
+      ("bullet_destroyed", &[("id", true)]),
+      ("player_appearance", &[("id", true), ("color", true)]),
+      ("set_appearance", &[("color", true)]),
+      ("player_count", &[("count", true)]),
+      ("fire_cooldown", &[("ready_in_ticks", true)]),
+      ("round_start", &[("round", true), ("duration_ticks", true)]),
+      ("round_end", &[("round", true), ("scores", true)]),
+      ("position_report", &[("x", true), ("y", true)]),
+      ("ready", &[]),
+      ("emote", &[("id", false), ("emote", true)]),
+      ("game_paused", &[("paused", true)]),
+      ("server_shutdown", &[("seconds_remaining", true)]),
+      ("players_moving", &[("updates", true)]),
+      ("player_facing", &[("id", true), ("facing_x", true), ("facing_y", true)]),
+      ("request_stats", &[]),
+      ("stats", &[("per_type", true)]),
+      ("shot_blocked", &[]),
+      ("request_player_stats", &[("id", true)]),
+      ("player_stats", &[("id", true), ("kills", true), ("deaths", true), ("health", true)]),
+      ("input_ack", &[("seq", true)]),
+      ("kill_streak", &[("id", true), ("streak", true)]),
+      ("nack", &[("reason", true)]),
+      ("set_paused", &[("paused", true), ("password", true)])];
+
+/// Get the fields of the specified message type from `SCHEMA`, or `None` if there's no such type.
+fn schema(msg_type: &str) -> Option<&'static [(&'static str, bool)]> {
+    SCHEMA.iter().find(|&&(name, _)| name == msg_type).map(|&(_, fields)| fields)
+}
+
+fn validate_finite(msg_type: &str, values: &[f32]) -> Result<(), MessageError> {
+    if values.iter().all(|value| value.is_finite()) {
+        Ok(())
+    } else {
+        Err(MessageError::InvalidValue(format!("{} with non-finite values: {:?}", msg_type, values)))
+    }
+}
+
+fn check_normalised(msg_type: &str, x: f32, y: f32, may_be_shorter: bool) -> Result<(), MessageError> {
+    let magnitude = (x * x + y * y).sqrt();
+    if (magnitude - 1.0).abs() <= NORMALISATION_EPSILON || (may_be_shorter && magnitude < 1.0) {
+        Ok(())
+    } else {
+        Err(MessageError::InvalidValue(format!("{} with a vector of magnitude {}: ({}, {})", msg_type, magnitude, x, y)))
+    }
+}
+
+fn is_dataless(msg_type: &str) -> bool {
+    dataless_message(msg_type).is_some()
+}
+
+fn dataless_message(msg_type: &str) -> Option<Message> {
+    match msg_type {
+        "stop_moving" => Some(Message::StopMoving),
+        "request_respawn" => Some(Message::RequestRespawn),
+        "ready" => Some(Message::Ready),
+        "request_stats" => Some(Message::RequestStats),
+        "shot_blocked" => Some(Message::ShotBlocked),
+        _ => None,
+    }
+}
+
+/// Find the end of the JSON Object at the start of the specified text, skipping leading whitespace.
+///
+/// Only brackets outside of strings are matched, it's up to the parser to reject anything else malformed.
+fn json_object_end(s: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match c {
+            '"' if depth > 0 => in_string = true,
+            '{' | '[' if depth > 0 || c == '{' => depth += 1,
+            '}' | ']' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            c if depth == 0 && !c.is_whitespace() => return None,
+            _ => {}
+        }
+    }
+
+    None
+}
+
 fn add_data_id_speeds_sizes_entries(data: &mut BTreeMap<String, serde_json::Value>,
                                     id: u32,
                                     speed: f32,
@@ -530,13 +1410,45 @@ fn add_shot_data_entries(data: &mut BTreeMap<String, serde_json::Value>,
                          x: f32,
                          y: f32,
                          aim_x: f32,
-                         aim_y: f32) {
+                         aim_y: f32,
+                         tick: u64) {
     add_data_entry(data, "id", &id);
     add_data_entry(data, "bullet_id", &bullet_id);
     add_data_entry(data, "x", &x);
     add_data_entry(data, "y", &y);
     add_data_entry(data, "aim_x", &aim_x);
     add_data_entry(data, "aim_y", &aim_y);
+    add_data_entry(data, "tick", &tick);
+}
+
+/// Round all floats in the specified JSON value, however nested, to the specified amount of decimal places.
+fn round_floats(value: &mut serde_json::Value, precision: u32) {
+    match value {
+        &mut serde_json::Value::F64(ref mut f) => {
+            let scale = 10f64.powi(precision as i32);
+            *f = (*f * scale).round() / scale;
+        }
+        &mut serde_json::Value::Array(ref mut values) => {
+            for value in values {
+                round_floats(value, precision);
+            }
+        }
+        &mut serde_json::Value::Object(ref mut values) => {
+            for (_, value) in values {
+                round_floats(value, precision);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Serialise one entry of **stats**' `per_type`.
+fn type_stats_json(msg_type: &str, count: u64, bytes: u64) -> serde_json::Value {
+    let mut values = BTreeMap::new();
+    let _ = values.insert("type".to_string(), serde_json::Value::String(msg_type.to_string()));
+    let _ = values.insert("count".to_string(), serde_json::Value::U64(count));
+    let _ = values.insert("bytes".to_string(), serde_json::Value::U64(bytes));
+    serde_json::Value::Object(values)
 }
 
 fn add_data_entry<T: serde::Serialize>(data: &mut BTreeMap<String, serde_json::Value>,
@@ -546,62 +1458,83 @@ fn add_data_entry<T: serde::Serialize>(data: &mut BTreeMap<String, serde_json::V
 }
 
 fn decompose_moves(data: &BTreeMap<String, serde_json::Value>) -> Result<(f32, f32), MessageError> {
-    try!(decompose_assert_size(data.len(), 2));
-    try!(decompose_assert_keys(data.keys().collect::<Vec<_>>(), vec!["move_x", "move_y"]));
-
     Ok((try!(unpack_f32(data.get("move_x").unwrap())),
         try!(unpack_f32(data.get("move_y").unwrap()))))
 }
 
+/// Decompose a movement vector either component of which may be omitted, the missing one being 0.
+fn decompose_optional_moves(data: &BTreeMap<String, serde_json::Value>) -> Result<(f32, f32), MessageError> {
+    let unpack = |key: &str| data.get(key).map_or(Ok(0.0), unpack_f32);
+    Ok((try!(unpack("move_x")), try!(unpack("move_y"))))
+}
+
+fn decompose_pos(data: &BTreeMap<String, serde_json::Value>) -> Result<(f32, f32), MessageError> {
+    Ok((try!(unpack_f32(data.get("x").unwrap())),
+        try!(unpack_f32(data.get("y").unwrap()))))
+}
+
 fn decompose_id_pos(data: &BTreeMap<String, serde_json::Value>)
                     -> Result<(u32, f32, f32), MessageError> {
-    try!(decompose_assert_size(data.len(), 3));
-    try!(decompose_assert_keys(data.keys().collect::<Vec<_>>(), vec!["id", "x", "y"]));
-
     Ok((try!(unpack_u32(data.get("id").unwrap())),
         try!(unpack_f32(data.get("x").unwrap())),
         try!(unpack_f32(data.get("y").unwrap()))))
 }
 
-fn decompose_stats(data: &BTreeMap<String, serde_json::Value>)
-                   -> Result<(u32, f32, f32, f32, f32), MessageError> {
-    try!(decompose_assert_size(data.len(), 5));
-    try!(decompose_assert_keys(data.keys().collect::<Vec<_>>(),
-                               vec!["bullet_size", "bullet_speed", "id", "size", "speed"]));
+fn decompose_stats(data: &BTreeMap<String, serde_json::Value>,
+                   max_array_length: usize)
+                   -> Result<(u32, f32, f32, f32, f32, Vec<String>), MessageError> {
+    let features = try!(try!(unpack_arr(data.get("features").unwrap(), max_array_length)).iter().map(unpack_str).collect::<Result<Vec<_>, _>>());
 
     Ok((try!(unpack_u32(data.get("id").unwrap())),
         try!(unpack_f32(data.get("speed").unwrap())),
         try!(unpack_f32(data.get("size").unwrap())),
         try!(unpack_f32(data.get("bullet_speed").unwrap())),
-        try!(unpack_f32(data.get("bullet_size").unwrap()))))
+        try!(unpack_f32(data.get("bullet_size").unwrap())),
+        features))
 }
 
 fn decompose_reason(data: &BTreeMap<String, serde_json::Value>) -> Result<String, MessageError> {
-    try!(decompose_assert_size(data.len(), 1));
-    try!(decompose_assert_keys(data.keys().collect::<Vec<_>>(), vec!["reason"]));
+    let reason = try!(unpack_str(data.get("reason").unwrap()));
+    if reason.chars().count() > MAX_REASON_LENGTH {
+        return Err(MessageError::InvalidValue(format!("reason longer than {} characters", MAX_REASON_LENGTH)));
+    }
+    Ok(reason)
+}
 
-    Ok(try!(unpack_str(data.get("reason").unwrap())))
+/// Cut the specified reason down to `MAX_REASON_LENGTH` characters, if it's longer.
+fn truncate_reason(reason: &str) -> &str {
+    match reason.char_indices().nth(MAX_REASON_LENGTH) {
+        Some((end, _)) => &reason[..end],
+        None => reason,
+    }
 }
 
 fn decompose_id(data: &BTreeMap<String, serde_json::Value>) -> Result<u32, MessageError> {
-    try!(decompose_assert_size(data.len(), 1));
-    try!(decompose_assert_keys(data.keys().collect::<Vec<_>>(), vec!["id"]));
-
     Ok(try!(unpack_u32(data.get("id").unwrap())))
 }
 
 fn decompose_shot(data: &BTreeMap<String, serde_json::Value>)
-                  -> Result<(u32, u32, f32, f32, f32, f32), MessageError> {
-    try!(decompose_assert_size(data.len(), 6));
-    try!(decompose_assert_keys(data.keys().collect::<Vec<_>>(),
-                               vec!["aim_x", "aim_y", "bullet_id", "id", "x", "y"]));
-
+                  -> Result<(u32, u32, f32, f32, f32, f32, u64), MessageError> {
     Ok((try!(unpack_u32(data.get("id").unwrap())),
         try!(unpack_u32(data.get("bullet_id").unwrap())),
         try!(unpack_f32(data.get("x").unwrap())),
         try!(unpack_f32(data.get("y").unwrap())),
         try!(unpack_f32(data.get("aim_x").unwrap())),
-        try!(unpack_f32(data.get("aim_y").unwrap()))))
+        try!(unpack_f32(data.get("aim_y").unwrap())),
+        try!(unpack_u64(data.get("tick").unwrap()))))
+}
+
+fn decompose_player_stats(data: &BTreeMap<String, serde_json::Value>)
+                          -> Result<(u32, u32, u32, f32), MessageError> {
+    Ok((try!(unpack_u32(data.get("id").unwrap())),
+        try!(unpack_u32(data.get("kills").unwrap())),
+        try!(unpack_u32(data.get("deaths").unwrap())),
+        try!(unpack_f32(data.get("health").unwrap()))))
+}
+
+fn decompose_kill_streak(data: &BTreeMap<String, serde_json::Value>) -> Result<(u32, u32), MessageError> {
+    Ok((try!(unpack_u32(data.get("id").unwrap())),
+        try!(unpack_u32(data.get("streak").unwrap()))))
 }
 
 fn decompose_destruction(data: &BTreeMap<String, serde_json::Value>)
@@ -609,47 +1542,41 @@ fn decompose_destruction(data: &BTreeMap<String, serde_json::Value>)
     match data.len() {
         1 => Ok((try!(decompose_id(data)), None, None)),
         3 => {
-            try!(decompose_assert_keys(data.keys().collect::<Vec<_>>(),
-                                       vec!["bullet_id", "id", "killer_id"]));
-
             Ok((try!(unpack_u32(data.get("id").unwrap())),
                 Some(try!(unpack_u32(data.get("killer_id").unwrap()))),
                 Some(try!(unpack_u32(data.get("bullet_id").unwrap())))))
         }
-        len => {
-            if len > 3 {
-                Err(MessageError::ExtraneousProperty(format!(r#"Expected 1 or 3, got {}"#, len)))
-            } else {
-                Err(MessageError::PropertyMissing(format!(r#"Expected 1 or 3, got {}"#, len)))
-            }
-        }
+        len => Err(MessageError::PropertyMissing(format!(r#"Expected 1 or 3, got {}"#, len))),
     }
 }
 
-fn decompose_world_state(data: &BTreeMap<String, serde_json::Value>)
+fn decompose_world_state(data: &BTreeMap<String, serde_json::Value>,
+                         max_array_length: usize)
                          -> Result<(u32, Vec<Player>, Vec<Bullet>), MessageError> {
-    try!(decompose_assert_size(data.len(), 3));
-    try!(decompose_assert_keys(data.keys().collect::<Vec<_>>(),
-                               vec!["alive_bullets", "alive_players", "player_count"]));
-
     let alive_players = try!(unpack_from_jsonnable(try!(unpack_arr(data.get("alive_players")
-                                                                       .unwrap())),
+                                                                       .unwrap(),
+                                                                   max_array_length)),
                                                    Player::from_json,
                                                    Player::not_moving(0, 0f32, 0f32)));
     let alive_bullets = try!(unpack_from_jsonnable(try!(unpack_arr(data.get("alive_bullets")
-                                                                       .unwrap())),
+                                                                       .unwrap(),
+                                                                   max_array_length)),
                                                    Bullet::from_json,
                                                    Bullet::not_moving(0, 0f32, 0f32)));
 
     Ok((try!(unpack_u32(data.get("player_count").unwrap())), alive_players, alive_bullets))
 }
 
+fn decompose_updates(data: &BTreeMap<String, serde_json::Value>,
+                     max_array_length: usize)
+                     -> Result<Vec<Player>, MessageError> {
+    unpack_from_jsonnable(try!(unpack_arr(data.get("updates").unwrap(), max_array_length)),
+                          Player::from_json,
+                          Player::not_moving(0, 0f32, 0f32))
+}
+
 fn decompose_id_pos_moves(data: &BTreeMap<String, serde_json::Value>)
                           -> Result<(u32, f32, f32, f32, f32), MessageError> {
-    try!(decompose_assert_size(data.len(), 5));
-    try!(decompose_assert_keys(data.keys().collect::<Vec<_>>(),
-                               vec!["id", "move_x", "move_y", "x", "y"]));
-
     Ok((try!(unpack_u32(data.get("id").unwrap())),
         try!(unpack_f32(data.get("x").unwrap())),
         try!(unpack_f32(data.get("y").unwrap())),
@@ -657,28 +1584,101 @@ fn decompose_id_pos_moves(data: &BTreeMap<String, serde_json::Value>)
         try!(unpack_f32(data.get("move_y").unwrap()))))
 }
 
-fn decompose_assert_size(len: usize, expected: usize) -> Result<(), MessageError> {
-    if len > expected {
-        return Err(MessageError::ExtraneousProperty(format!(r#"Expected {}, got {}"#,
-                                                            expected,
-                                                            len)));
-    } else if len < expected {
-        return Err(MessageError::PropertyMissing(format!(r#"Expected {}, got {}"#, expected, len)));
-    } else {
-        Ok(())
-    }
+fn decompose_time_sync_request(data: &BTreeMap<String, serde_json::Value>) -> Result<f64, MessageError> {
+    Ok(try!(unpack_f64(data.get("client_time").unwrap())))
 }
 
-fn decompose_assert_keys(keys: Vec<&String>,
-                         expected: Vec<&'static str>)
-                         -> Result<(), MessageError> {
-    if keys != expected {
-        return Err(MessageError::ExtraneousProperty(format!(r#"Data Object is a mismatch for {:?}: {:?}"#, expected, keys)));
-    } else {
-        Ok(())
+fn decompose_time_sync_response(data: &BTreeMap<String, serde_json::Value>)
+                                -> Result<(f64, f64), MessageError> {
+    Ok((try!(unpack_f64(data.get("client_time").unwrap())),
+        try!(unpack_f64(data.get("server_time").unwrap()))))
+}
+
+fn decompose_count(data: &BTreeMap<String, serde_json::Value>) -> Result<u32, MessageError> {
+    Ok(try!(unpack_u32(data.get("count").unwrap())))
+}
+
+fn decompose_fire_cooldown(data: &BTreeMap<String, serde_json::Value>) -> Result<u32, MessageError> {
+    Ok(try!(unpack_u32(data.get("ready_in_ticks").unwrap())))
+}
+
+fn decompose_color(data: &BTreeMap<String, serde_json::Value>) -> Result<u32, MessageError> {
+    Ok(try!(unpack_color(data.get("color").unwrap())))
+}
+
+fn decompose_round_start(data: &BTreeMap<String, serde_json::Value>) -> Result<(u32, u32), MessageError> {
+    Ok((try!(unpack_u32(data.get("round").unwrap())),
+        try!(unpack_u32(data.get("duration_ticks").unwrap()))))
+}
+
+fn decompose_round_end(data: &BTreeMap<String, serde_json::Value>,
+                       max_array_length: usize)
+                       -> Result<(u32, Vec<Score>), MessageError> {
+    let scores = try!(unpack_from_jsonnable(try!(unpack_arr(data.get("scores").unwrap(), max_array_length)),
+                                            Score::from_json,
+                                            Score::new(0, 0)));
+
+    Ok((try!(unpack_u32(data.get("round").unwrap())), scores))
+}
+
+fn decompose_paused(data: &BTreeMap<String, serde_json::Value>) -> Result<bool, MessageError> {
+    Ok(try!(unpack_bool(data.get("paused").unwrap())))
+}
+
+fn decompose_seconds_remaining(data: &BTreeMap<String, serde_json::Value>) -> Result<u32, MessageError> {
+    Ok(try!(unpack_u32(data.get("seconds_remaining").unwrap())))
+}
+
+fn decompose_id_emote(data: &BTreeMap<String, serde_json::Value>)
+                      -> Result<(Option<u32>, u8), MessageError> {
+    let id = match data.get("id") {
+        Some(id) => Some(try!(unpack_u32(id))),
+        None => None,
+    };
+    Ok((id, try!(unpack_u8(data.get("emote").unwrap()))))
+}
+
+fn decompose_per_type(data: &BTreeMap<String, serde_json::Value>,
+                      max_array_length: usize)
+                      -> Result<Vec<(String, u64, u64)>, MessageError> {
+    try!(unpack_arr(data.get("per_type").unwrap(), max_array_length)).iter().map(unpack_type_stats).collect()
+}
+
+fn decompose_id_facing(data: &BTreeMap<String, serde_json::Value>)
+                       -> Result<(u32, f32, f32), MessageError> {
+    Ok((try!(unpack_u32(data.get("id").unwrap())),
+        try!(unpack_f32(data.get("facing_x").unwrap())),
+        try!(unpack_f32(data.get("facing_y").unwrap()))))
+}
+
+fn decompose_id_color(data: &BTreeMap<String, serde_json::Value>)
+                      -> Result<(u32, u32), MessageError> {
+    Ok((try!(unpack_u32(data.get("id").unwrap())),
+        try!(unpack_color(data.get("color").unwrap()))))
+}
+
+/// Check the keys of a message's `data` Object against the fields its type has in `SCHEMA`, regardless of their order.
+fn decompose_assert_schema(data: &BTreeMap<String, serde_json::Value>,
+                           fields: &[(&'static str, bool)])
+                           -> Result<(), MessageError> {
+    let required = fields.iter().filter(|&&(_, required)| required).count();
+    if data.len() > fields.len() {
+        return Err(MessageError::ExtraneousProperty(format!(r#"Expected at most {}, got {}"#, fields.len(), data.len())));
+    } else if data.len() < required {
+        return Err(MessageError::PropertyMissing(format!(r#"Expected at least {}, got {}"#, required, data.len())));
     }
+
+    let names = fields.iter().map(|&(name, _)| name).collect::<Vec<_>>();
+    if let Some(key) = data.keys().find(|key| !names.contains(&&key[..])) {
+        return Err(MessageError::ExtraneousProperty(format!(r#"Data Object is a mismatch for {:?}: unexpected {:?}"#, names, key)));
+    }
+    if let Some(&(name, _)) = fields.iter().find(|&&(name, required)| required && !data.contains_key(name)) {
+        return Err(MessageError::PropertyMissing(format!(r#"Data Object is a mismatch for {:?}: missing {:?}"#, names, name)));
+    }
+    Ok(())
 }
 
+/// Unpack a float field, accepting integers too, since clients may well send `1` rather than `1.0`.
 fn unpack_f32(val: &serde_json::Value) -> Result<f32, MessageError> {
     match val {
         &serde_json::Value::F64(f) => Ok(f as f32),
@@ -688,14 +1688,65 @@ fn unpack_f32(val: &serde_json::Value) -> Result<f32, MessageError> {
     }
 }
 
-fn unpack_u32(val: &serde_json::Value) -> Result<u32, MessageError> {
+fn unpack_f64(val: &serde_json::Value) -> Result<f64, MessageError> {
     match val {
-        &serde_json::Value::I64(i) => Ok(i as u32),
-        &serde_json::Value::U64(u) => Ok(u as u32),
+        &serde_json::Value::F64(f) => Ok(f),
+        &serde_json::Value::I64(i) => Ok(i as f64),
+        &serde_json::Value::U64(u) => Ok(u as f64),
+        _ => Err(MessageError::BadType("Expected f64-compatible type".to_string())),
+    }
+}
+
+/// Unpack an integer between 0 and the specified maximum, inclusive, `None` if it's outside that range or not an integer.
+///
+/// Casting straight to the target type would silently wrap negative and too large values instead.
+fn unpack_bounded(val: &serde_json::Value, max: u64) -> Option<u64> {
+    match val {
+        &serde_json::Value::U64(u) if u <= max => Some(u),
+        &serde_json::Value::I64(i) if i >= 0 && i as u64 <= max => Some(i as u64),
+        _ => None,
+    }
+}
+
+fn unpack_u32(val: &serde_json::Value) -> Result<u32, MessageError> {
+    match unpack_bounded(val, u32::MAX as u64) {
+        Some(u) => Ok(u as u32),
+        None => Err(MessageError::BadType("Expected u32-compatible type".to_string())),
+    }
+}
+
+fn unpack_u64(val: &serde_json::Value) -> Result<u64, MessageError> {
+    match unpack_bounded(val, u64::MAX) {
+        Some(u) => Ok(u),
+        None => Err(MessageError::BadType("Expected u64-compatible type".to_string())),
+    }
+}
+
+fn unpack_color(val: &serde_json::Value) -> Result<u32, MessageError> {
+    static MAX_COLOR: u64 = 0xFFFFFF;
+
+    match (val, unpack_bounded(val, MAX_COLOR)) {
+        (_, Some(u)) => Ok(u as u32),
+        (&serde_json::Value::U64(_), None) |
+        (&serde_json::Value::I64(_), None) => Err(MessageError::BadType("Expected color packed as 0xRRGGBB".to_string())),
         _ => Err(MessageError::BadType("Expected u32-compatible type".to_string())),
     }
 }
 
+fn unpack_u8(val: &serde_json::Value) -> Result<u8, MessageError> {
+    match unpack_bounded(val, u8::MAX as u64) {
+        Some(u) => Ok(u as u8),
+        None => Err(MessageError::BadType("Expected u8-compatible type".to_string())),
+    }
+}
+
+fn unpack_bool(val: &serde_json::Value) -> Result<bool, MessageError> {
+    match val {
+        &serde_json::Value::Bool(b) => Ok(b),
+        _ => Err(MessageError::BadType("Expected Bool".to_string())),
+    }
+}
+
 fn unpack_str(val: &serde_json::Value) -> Result<String, MessageError> {
     match val {
         &serde_json::Value::String(ref s) => Ok(s.clone()),
@@ -703,8 +1754,28 @@ fn unpack_str(val: &serde_json::Value) -> Result<String, MessageError> {
     }
 }
 
-fn unpack_arr<'v>(val: &'v serde_json::Value) -> Result<&'v Vec<serde_json::Value>, MessageError> {
+fn unpack_type_stats(val: &serde_json::Value) -> Result<(String, u64, u64), MessageError> {
+    match val.as_object() {
+        Some(entry) => {
+            let keys = entry.keys().collect::<Vec<_>>();
+            if keys != vec!["bytes", "count", "type"] {
+                return Err(MessageError::PropertyMissing(format!(r#"Stats entry Object is a mismatch for `{{"type", "count", "bytes"}}`: {:?}"#,
+                                                                 keys)));
+            }
+
+            Ok((try!(unpack_str(entry.get("type").unwrap())),
+                try!(unpack_u64(entry.get("count").unwrap())),
+                try!(unpack_u64(entry.get("bytes").unwrap()))))
+        }
+        None => Err(MessageError::BadType("Stats entry JSON not an Object".to_string())),
+    }
+}
+
+fn unpack_arr<'v>(val: &'v serde_json::Value, max_length: usize) -> Result<&'v Vec<serde_json::Value>, MessageError> {
     match val {
+        &serde_json::Value::Array(ref s) if s.len() > max_length => {
+            Err(MessageError::ExtraneousProperty(format!("Array of {} elements, expected at most {}", s.len(), max_length)))
+        }
         &serde_json::Value::Array(ref s) => Ok(s),
         _ => Err(MessageError::BadType("Expected Array".to_string())),
     }
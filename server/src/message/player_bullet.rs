@@ -1,9 +1,11 @@
 use std::collections::BTreeMap;
-use self::super::MessageError;
+use self::super::{unpack_f32, unpack_u32, MessageError};
 use serde_json;
 
+/// Define a player- or bullet-like struct with a position, an optional movement vector,
+/// and the specified extra optional `f32` fields, along with its JSON (de)serialisation.
 macro_rules! player_or_bullet {
-    ($name:ident, $name_s:expr) => {
+    ($name:ident, $name_s:expr $(, $extra:ident)*) => {
 /// Part of the **world_state** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec).")]
         #[derive(Copy, Clone, Debug, PartialEq)]
         pub struct $name {
@@ -12,6 +14,7 @@ macro_rules! player_or_bullet {
             pub y: f32,
             pub move_x: Option<f32>,
             pub move_y: Option<f32>,
+            $(pub $extra: Option<f32>,)*
         }
 
         impl $name {
@@ -23,6 +26,7 @@ macro_rules! player_or_bullet {
                     y: y,
                     move_x: None,
                     move_y: None,
+                    $($extra: None,)*
                 }
             }
 
@@ -33,23 +37,46 @@ macro_rules! player_or_bullet {
                     y: y,
                     move_x: Some(move_x),
                     move_y: Some(move_y),
+                    $($extra: None,)*
                 }
             }
 
+            /// The movement vector, present if either of its components is, the missing one being 0.
+            pub fn movement(&self) -> Option<(f32, f32)> {
+                match (self.move_x, self.move_y) {
+                    (None, None) => None,
+                    (move_x, move_y) => Some((move_x.unwrap_or(0.0), move_y.unwrap_or(0.0))),
+                }
+            }
+
+            /// Whether there's a movement vector, i.e. either of its components is present.
+            pub fn is_moving(&self) -> bool {
+                self.movement().is_some()
+            }
+
+            /// Whether the position, the movement vector and the extra fields, if any, are finite.
+            pub fn is_finite(&self) -> bool {
+                self.x.is_finite() && self.y.is_finite() &&
+                self.move_x.map_or(true, |move_x| move_x.is_finite()) &&
+                self.move_y.map_or(true, |move_y| move_y.is_finite())
+                $(&& self.$extra.map_or(true, |$extra| $extra.is_finite()))*
+            }
+
             pub fn to_json(&self) -> serde_json::Value {
                 let mut values = BTreeMap::new();
                 let _ = values.insert("id".to_string(), serde_json::Value::U64(self.id as u64));
                 let _ = values.insert("x".to_string(), serde_json::Value::F64(self.x as f64));
                 let _ = values.insert("y".to_string(), serde_json::Value::F64(self.y as f64));
 
-                match (self.move_x, self.move_y) {
-                    (Some(move_x), Some(move_y)) => {
-                        let _ = values.insert("move_x".to_string(), serde_json::Value::F64(move_x as f64));
-                        let _ = values.insert("move_y".to_string(), serde_json::Value::F64(move_y as f64));
-                    }
-                    (None, None) => {}
-                    _ => panic!("move_x and move_y must be either both Some or both None"),
+                if let Some(move_x) = self.move_x {
+                    let _ = values.insert("move_x".to_string(), serde_json::Value::F64(move_x as f64));
                 }
+                if let Some(move_y) = self.move_y {
+                    let _ = values.insert("move_y".to_string(), serde_json::Value::F64(move_y as f64));
+                }
+                $(if let Some($extra) = self.$extra {
+                    let _ = values.insert(stringify!($extra).to_string(), serde_json::Value::F64($extra as f64));
+                })*
 
                 serde_json::Value::Object(values)
             }
@@ -57,11 +84,14 @@ macro_rules! player_or_bullet {
             pub fn from_json(json: &serde_json::Value) -> Result<Self, MessageError> {
                 match json.as_object() {
                     Some(msg) => {
+                        let optional_keys = ["move_x", "move_y" $(, stringify!($extra))*];
                         let keys = msg.keys().collect::<Vec<_>>();
-                        if keys != vec!["id", "move_x", "move_y", "x", "y"] &&
-                           keys != vec!["id", "x", "y"] {
+                        if !["id", "x", "y"].iter().all(|key| msg.contains_key(*key)) ||
+                           keys.iter().any(|key| !["id", "x", "y"].contains(&&key[..]) && !optional_keys.contains(&&key[..])) {
                             return Err(MessageError::PropertyMissing(
-                                format!(concat!($name_s, r#" Object is a mismatch for `"{{"id", "x", "y"[, "move_x", "move_y"]}}"`: {:?}"#), keys)));
+                                format!(concat!($name_s, r#" Object is a mismatch for `"{{"id", "x", "y"{}}}"`: {:?}"#),
+                                        optional_keys.iter().map(|key| format!("[, {:?}]", key)).collect::<String>(),
+                                        keys)));
                         }
 
                         let id = try!(unpack_u32(msg.get("id").unwrap()));
@@ -75,6 +105,10 @@ macro_rules! player_or_bullet {
                             Some(move_y) => Some(try!(unpack_f32(move_y))),
                             None => None,
                         };
+                        $(let $extra = match msg.get(stringify!($extra)) {
+                            Some($extra) => Some(try!(unpack_f32($extra))),
+                            None => None,
+                        };)*
 
                         Ok($name{
                             id: id,
@@ -82,6 +116,7 @@ macro_rules! player_or_bullet {
                             y: y,
                             move_x: move_x,
                             move_y: move_y,
+                            $($extra: $extra,)*
                         })
                     }
                     None => Err(MessageError::BadType(concat!($name_s, " JSON not an Object").to_string())),
@@ -91,7 +126,8 @@ macro_rules! player_or_bullet {
     }
 }
 
-player_or_bullet!(Player, "Player");
+// Players also carry the direction they're facing, once they've aimed or moved anywhere
+player_or_bullet!(Player, "Player", facing_x, facing_y);
 player_or_bullet!(Bullet, "Bullet");
 
 /// A Bullet owned by a player specified by its ID
@@ -101,35 +137,25 @@ pub struct OwnedBullet {
     pub bullet: Bullet,
     /// The owner's ID
     pub owner_id: u32,
+    /// Distance the bullet travelled since it was fired
+    pub distance_travelled: f32,
+    /// Length of the movement vector everybody was last told about
+    pub broadcast_velocity: f32,
 }
 
 impl OwnedBullet {
     pub fn new(bullet: Bullet, owner_id: u32) -> Self {
+        let velocity = bullet.movement().map_or(0.0, |(move_x, move_y)| (move_x * move_x + move_y * move_y).sqrt());
         OwnedBullet {
             bullet: bullet,
             owner_id: owner_id,
+            distance_travelled: 0.0,
+            broadcast_velocity: velocity,
         }
     }
 }
 
-fn unpack_f32(val: &serde_json::Value) -> Result<f32, MessageError> {
-    match val {
-        &serde_json::Value::F64(f) => Ok(f as f32),
-        &serde_json::Value::I64(i) => Ok(i as f32),
-        &serde_json::Value::U64(u) => Ok(u as f32),
-        _ => Err(MessageError::BadType("Expected f32-compatible type".to_string())),
-    }
-}
-
-fn unpack_u32(val: &serde_json::Value) -> Result<u32, MessageError> {
-    match val {
-        &serde_json::Value::I64(i) => Ok(i as u32),
-        &serde_json::Value::U64(u) => Ok(u as u32),
-        _ => Err(MessageError::BadType("Expected u32-compatible type".to_string())),
-    }
-}
-
-/// Only testing Player, because Bullet is literally identical
+/// Mostly testing Player, because Bullet is identical, save for not facing anywhere
 #[cfg(test)]
 mod tests {
     use std::iter::FromIterator;
@@ -163,6 +189,57 @@ mod tests {
                    moving_player_expected_json(id, x, y, move_x, move_y));
     }
 
+    #[test]
+    fn player_with_only_move_x_serializes_only_move_x() {
+        let mut rng = thread_rng();
+        let id: u32 = rng.gen();
+        let x = gen_f32(&mut rng);
+        let y = gen_f32(&mut rng);
+        let move_x = gen_f32(&mut rng);
+
+        let mut player = Player::not_moving(id, x, y);
+        player.move_x = Some(move_x);
+
+        let mut expected_json = moving_player_expected_json(id, x, y, move_x, 0f32);
+        let _ = expected_json.as_object_mut()
+                             .unwrap()
+                             .remove("move_y")
+                             .unwrap();
+        assert_eq!(player.to_json(), expected_json);
+    }
+
+    #[test]
+    fn player_moving_along_one_axis_is_moving() {
+        let mut rng = thread_rng();
+        let move_y = gen_f32(&mut rng);
+
+        let mut player = Player::not_moving(rng.gen(), gen_f32(&mut rng), gen_f32(&mut rng));
+        player.move_y = Some(move_y);
+
+        assert!(player.is_moving());
+        assert_eq!(player.movement(), Some((0.0, move_y)));
+    }
+
+    #[test]
+    fn moving_player_is_moving() {
+        let mut rng = thread_rng();
+        let move_x = gen_f32(&mut rng);
+        let move_y = gen_f32(&mut rng);
+        let player = Player::moving(rng.gen(), gen_f32(&mut rng), gen_f32(&mut rng), move_x, move_y);
+
+        assert!(player.is_moving());
+        assert_eq!(player.movement(), Some((move_x, move_y)));
+    }
+
+    #[test]
+    fn static_player_is_not_moving() {
+        let mut rng = thread_rng();
+        let player = Player::not_moving(rng.gen(), gen_f32(&mut rng), gen_f32(&mut rng));
+
+        assert!(!player.is_moving());
+        assert_eq!(player.movement(), None);
+    }
+
     #[test]
     fn static_player_deserializes_properly() {
         let mut rng = thread_rng();
@@ -189,18 +266,54 @@ mod tests {
     }
 
     #[test]
-    fn player_with_move_x_no_move_y_deserialize_fails() {
+    fn player_with_move_x_no_move_y_deserializes_properly() {
         let mut rng = thread_rng();
         let id: u32 = rng.gen();
         let x = gen_f32(&mut rng);
         let y = gen_f32(&mut rng);
         let move_x = gen_f32(&mut rng);
 
-        let mut unexpected_json = moving_player_expected_json(id, x, y, move_x, 0f32);
+        let mut json = moving_player_expected_json(id, x, y, move_x, 0f32);
+        let _ = json.as_object_mut()
+                    .unwrap()
+                    .remove("move_y")
+                    .unwrap();
+
+        let mut expected_player = Player::not_moving(id, x, y);
+        expected_player.move_x = Some(move_x);
+        assert_eq!(Player::from_json(&json).unwrap(), expected_player);
+    }
+
+    #[test]
+    fn player_with_move_y_no_move_x_deserializes_properly() {
+        let mut rng = thread_rng();
+        let id: u32 = rng.gen();
+        let x = gen_f32(&mut rng);
+        let y = gen_f32(&mut rng);
+        let move_y = gen_f32(&mut rng);
+
+        let mut json = moving_player_expected_json(id, x, y, 0f32, move_y);
+        let _ = json.as_object_mut()
+                    .unwrap()
+                    .remove("move_x")
+                    .unwrap();
+
+        let mut expected_player = Player::not_moving(id, x, y);
+        expected_player.move_y = Some(move_y);
+        assert_eq!(Player::from_json(&json).unwrap(), expected_player);
+    }
+
+    #[test]
+    fn player_with_unknown_key_deserialize_fails() {
+        let mut rng = thread_rng();
+        let id: u32 = rng.gen();
+        let x = gen_f32(&mut rng);
+        let y = gen_f32(&mut rng);
+
+        let mut unexpected_json = static_player_expected_json(id, x, y);
         let _ = unexpected_json.as_object_mut()
                                .unwrap()
-                               .remove("move_y")
-                               .unwrap();
+                               .insert("move_z".to_string(), Value::F64(1.0));
 
         match Player::from_json(&unexpected_json).unwrap_err() {
             MessageError::PropertyMissing(_) => {}
@@ -209,20 +322,26 @@ mod tests {
     }
 
     #[test]
-    fn player_with_move_y_no_move_x_deserialize_fails() {
+    fn facing_player_round_trips() {
         let mut rng = thread_rng();
-        let id: u32 = rng.gen();
-        let x = gen_f32(&mut rng);
-        let y = gen_f32(&mut rng);
-        let move_y = gen_f32(&mut rng);
+        let mut player = Player::not_moving(rng.gen(), gen_f32(&mut rng), gen_f32(&mut rng));
+        player.facing_x = Some(gen_f32(&mut rng));
+        player.facing_y = Some(gen_f32(&mut rng));
 
-        let mut unexpected_json = moving_player_expected_json(id, x, y, 0f32, move_y);
+        let json = player.to_json();
+        assert_eq!(json.as_object().unwrap().get("facing_x"), Some(&Value::F64(player.facing_x.unwrap() as f64)));
+        assert_eq!(Player::from_json(&json).unwrap(), player);
+    }
+
+    #[test]
+    fn facing_bullet_deserialize_fails() {
+        let mut rng = thread_rng();
+        let mut unexpected_json = static_player_expected_json(rng.gen(), gen_f32(&mut rng), gen_f32(&mut rng));
         let _ = unexpected_json.as_object_mut()
                                .unwrap()
-                               .remove("move_x")
-                               .unwrap();
+                               .insert("facing_x".to_string(), Value::F64(1.0));
 
-        match Player::from_json(&unexpected_json).unwrap_err() {
+        match Bullet::from_json(&unexpected_json).unwrap_err() {
             MessageError::PropertyMissing(_) => {}
             me => panic!(format!("Incorrect error type: {:?}, should be PropertyMissing", me)),
         }
@@ -241,6 +360,8 @@ mod tests {
                    OwnedBullet {
                        bullet: bullet,
                        owner_id: owner_id,
+                       distance_travelled: 0.0,
+                       broadcast_velocity: 0.0,
                    });
     }
 
@@ -259,6 +380,8 @@ mod tests {
                    OwnedBullet {
                        bullet: bullet,
                        owner_id: owner_id,
+                       distance_travelled: 0.0,
+                       broadcast_velocity: (move_x * move_x + move_y * move_y).sqrt(),
                    });
     }
 
@@ -0,0 +1,90 @@
+use std::collections::BTreeMap;
+use self::super::{unpack_u32, MessageError};
+use serde_json;
+
+/// Part of the **round_end** message, as defined by [Protocol spec](https://github.com/LoungeCPP/Tatsoryk/wiki/Protocol-spec).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Score {
+    pub id: u32,
+    pub kills: u32,
+}
+
+impl Score {
+    pub fn new(id: u32, kills: u32) -> Score {
+        Score {
+            id: id,
+            kills: kills,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut values = BTreeMap::new();
+        let _ = values.insert("id".to_string(), serde_json::Value::U64(self.id as u64));
+        let _ = values.insert("kills".to_string(), serde_json::Value::U64(self.kills as u64));
+        serde_json::Value::Object(values)
+    }
+
+    pub fn from_json(json: &serde_json::Value) -> Result<Score, MessageError> {
+        match json.as_object() {
+            Some(msg) => {
+                let keys = msg.keys().collect::<Vec<_>>();
+                if keys != vec!["id", "kills"] {
+                    return Err(MessageError::PropertyMissing(format!(r#"Score Object is a mismatch for `"{{"id", "kills"}}"`: {:?}"#,
+                                                                     keys)));
+                }
+
+                Ok(Score {
+                    id: try!(unpack_u32(msg.get("id").unwrap())),
+                    kills: try!(unpack_u32(msg.get("kills").unwrap())),
+                })
+            }
+            None => Err(MessageError::BadType("Score JSON not an Object".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter::FromIterator;
+    use std::collections::BTreeMap;
+    use rand::{thread_rng, Rng};
+    use serde_json::Value;
+    use self::super::Score;
+    use self::super::super::MessageError;
+
+    #[test]
+    fn score_serializes_properly() {
+        let mut rng = thread_rng();
+        let id: u32 = rng.gen();
+        let kills: u32 = rng.gen();
+
+        assert_eq!(Score::new(id, kills).to_json(), score_expected_json(id, kills));
+    }
+
+    #[test]
+    fn score_deserializes_properly() {
+        let mut rng = thread_rng();
+        let id: u32 = rng.gen();
+        let kills: u32 = rng.gen();
+
+        assert_eq!(Score::from_json(&score_expected_json(id, kills)).unwrap(),
+                   Score::new(id, kills));
+    }
+
+    #[test]
+    fn score_without_kills_fails() {
+        let json = Value::Object(BTreeMap::from_iter(vec![("id".to_string(), Value::U64(0))]));
+
+        match Score::from_json(&json).unwrap_err() {
+            MessageError::PropertyMissing(_) => {}
+            _ => panic!("Incorrect error kind"),
+        }
+    }
+
+    fn score_expected_json(id: u32, kills: u32) -> Value {
+        Value::Object(BTreeMap::from_iter(vec![
+            ("id".to_string(), Value::U64(id as u64)),
+            ("kills".to_string(), Value::U64(kills as u64)),
+        ]))
+    }
+}
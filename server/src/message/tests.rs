@@ -1,10 +1,11 @@
 use std::iter::FromIterator;
 use std::collections::BTreeMap;
 use rand::Rng;
-use self::super::{Player, Bullet};
+use self::super::{Player, Bullet, Score};
 use serde_json::Value;
 
 mod ser {
+    use std::iter;
     use self::super::*;
     use self::super::super::*;
     use rand::{Rng, thread_rng};
@@ -18,6 +19,7 @@ mod ser {
         let size = gen_f32(&mut rng);
         let bullet_speed = gen_f32(&mut rng);
         let bullet_size = gen_f32(&mut rng);
+        let features = vec!["ready".to_string(), "rounds".to_string()];
 
         let json_txt = Message::Welcome {
                            id: id,
@@ -25,11 +27,12 @@ mod ser {
                            size: size,
                            bullet_speed: bullet_speed,
                            bullet_size: bullet_size,
+                           features: features.clone(),
                        }
                        .to_string();
 
         assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
-                   welcome_expected_json(id, speed, size, bullet_speed, bullet_size));
+                   welcome_expected_json(id, speed, size, bullet_speed, bullet_size, features));
     }
 
     #[test]
@@ -46,6 +49,16 @@ mod ser {
                    go_away_expected_json(reason));
     }
 
+    #[test]
+    fn go_away_with_overlong_reason_is_truncated() {
+        let reason: String = iter::repeat('\u{e9}').take(MAX_REASON_LENGTH + 10).collect();
+
+        let json_txt = Message::GoAway { reason: reason.clone() }.to_string();
+
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   go_away_expected_json(reason.chars().take(MAX_REASON_LENGTH).collect()));
+    }
+
     #[test]
     fn player_joined_serializes_properly() {
         let mut rng = thread_rng();
@@ -77,6 +90,7 @@ mod ser {
         let y = gen_f32(&mut rng);
         let aim_x = gen_f32(&mut rng);
         let aim_y = gen_f32(&mut rng);
+        let tick: u64 = rng.gen();
 
         let json_txt = Message::ShotsFired {
                            id: id,
@@ -85,11 +99,12 @@ mod ser {
                            y: y,
                            aim_x: aim_x,
                            aim_y: aim_y,
+                           tick: tick,
                        }
                        .to_string();
 
         assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
-                   shots_fired_expected_json(id, bullet_id, x, y, aim_x, aim_y));
+                   shots_fired_expected_json(id, bullet_id, x, y, aim_x, aim_y, tick));
     }
 
     #[test]
@@ -240,6 +255,84 @@ mod ser {
                    expected_json);
     }
 
+    #[test]
+    fn players_moving_serializes_properly() {
+        let mut rng = thread_rng();
+        let updates = gen_bullets_or_players(&mut rng,
+                                             Player::moving,
+                                             |id, x, y| Player::moving(id, x, y, 0.0, 1.0),
+                                             10);
+
+        let expected_json = players_moving_expected_json(&updates);
+        let json_txt = Message::PlayersMoving { updates: updates.clone() }.to_string();
+
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   expected_json);
+        assert_eq!(str::parse::<Message>(&json_txt).unwrap(),
+                   Message::PlayersMoving { updates: updates });
+    }
+
+    #[test]
+    fn world_state_constructor_keeps_player_count() {
+        let mut rng = thread_rng();
+        for _ in 0..10 {
+            let upper_range: u32 = rng.gen_range(1, 100);
+            let alive_players = gen_bullets_or_players(&mut rng,
+                                                       Player::moving,
+                                                       Player::not_moving,
+                                                       upper_range);
+            let alive_bullets = gen_bullets_or_players(&mut rng, Bullet::moving, Bullet::not_moving, 10);
+            let player_count = alive_players.len() as u32 + rng.gen_range(0, 10);
+
+            assert_eq!(Message::world_state(player_count, alive_players.clone(), alive_bullets.clone()),
+                       Message::WorldState {
+                           player_count: player_count,
+                           alive_players: alive_players,
+                           alive_bullets: alive_bullets,
+                       });
+        }
+    }
+
+    #[test]
+    fn player_teleported_serializes_properly() {
+        let mut rng = thread_rng();
+        let id: u32 = rng.gen();
+        let x = gen_f32(&mut rng);
+        let y = gen_f32(&mut rng);
+
+        let json_txt = Message::PlayerTeleported {
+                           id: id,
+                           x: x,
+                           y: y,
+                       }
+                       .to_string();
+
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   player_teleported_expected_json(id, x, y));
+    }
+
+    #[test]
+    fn bullet_moving_serializes_properly() {
+        let mut rng = thread_rng();
+        let id: u32 = rng.gen();
+        let x = gen_f32(&mut rng);
+        let y = gen_f32(&mut rng);
+        let move_x = gen_f32(&mut rng);
+        let move_y = gen_f32(&mut rng);
+
+        let json_txt = Message::BulletMoving {
+                           id: id,
+                           x: x,
+                           y: y,
+                           move_x: move_x,
+                           move_y: move_y,
+                       }
+                       .to_string();
+
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   bullet_moving_expected_json(id, x, y, move_x, move_y));
+    }
+
     #[test]
     fn start_moving_serializes_properly() {
         let mut rng = thread_rng();
@@ -279,176 +372,558 @@ mod ser {
         assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
                    fire_expected_json(move_x, move_y));
     }
-}
 
-mod de {
-    mod correct {
-        use self::super::super::*;
-        use self::super::super::super::*;
-        use rand::{Rng, thread_rng};
-        use serde_json;
+    #[test]
+    fn request_respawn_serializes_properly() {
+        let json_txt = Message::RequestRespawn.to_string();
 
-        #[test]
-        fn welcome_deserializes_properly() {
-            let mut rng = thread_rng();
-            let id: u32 = rng.gen();
-            let speed = gen_f32(&mut rng);
-            let size = gen_f32(&mut rng);
-            let bullet_speed = gen_f32(&mut rng);
-            let bullet_size = gen_f32(&mut rng);
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   request_respawn_expected_json());
+    }
 
-            let expected_message = Message::Welcome {
-                id: id,
-                speed: speed,
-                size: size,
-                bullet_speed: bullet_speed,
-                bullet_size: bullet_size,
-            };
+    #[test]
+    fn request_stats_serializes_properly() {
+        let json_txt = Message::RequestStats.to_string();
 
-            assert_eq!(str::parse::<Message>(&serde_json::to_string(&welcome_expected_json(id, speed, size, bullet_speed, bullet_size))
-                                                        .unwrap())
-                               .unwrap(),
-                           expected_message);
-        }
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   request_stats_expected_json());
+    }
 
-        #[test]
-        fn go_away_deserializes_properly() {
-            let mut rng = thread_rng();
-            let reason: String = {
-                let len = rng.gen_range(1, 100);
-                rng.gen_ascii_chars().take(len).collect()
-            };
+    #[test]
+    fn shot_blocked_serializes_properly() {
+        let json_txt = Message::ShotBlocked.to_string();
 
-            let expected_message = Message::GoAway { reason: reason.clone() };
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   shot_blocked_expected_json());
+    }
 
-            assert_eq!(str::parse::<Message>(&serde_json::to_string(&go_away_expected_json(reason))
-                                                        .unwrap())
-                               .unwrap(),
-                           expected_message);
-        }
+    #[test]
+    fn request_player_stats_serializes_properly() {
+        let mut rng = thread_rng();
+        let id: u32 = rng.gen();
 
-        #[test]
-        fn player_joined_deserializes_properly() {
-            let mut rng = thread_rng();
-            let id: u32 = rng.gen();
+        let json_txt = Message::RequestPlayerStats { id: id }.to_string();
 
-            let expected_message = Message::PlayerJoined { id: id };
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   request_player_stats_expected_json(id));
+    }
 
-            assert_eq!(str::parse::<Message>(&serde_json::to_string(&player_joined_expected_json(id))
-                                                        .unwrap())
-                               .unwrap(),
-                           expected_message);
-        }
+    #[test]
+    fn player_stats_serializes_properly() {
+        let mut rng = thread_rng();
+        let id: u32 = rng.gen();
+        let kills: u32 = rng.gen();
+        let deaths: u32 = rng.gen();
+        let health = gen_f32(&mut rng);
 
-        #[test]
-        fn player_left_deserializes_properly() {
-            let mut rng = thread_rng();
-            let id: u32 = rng.gen();
+        let json_txt = Message::PlayerStats {
+                           id: id,
+                           kills: kills,
+                           deaths: deaths,
+                           health: health,
+                       }
+                       .to_string();
 
-            let expected_message = Message::PlayerLeft { id: id };
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   player_stats_expected_json(id, kills, deaths, health));
+    }
 
-            assert_eq!(str::parse::<Message>(&serde_json::to_string(&player_left_expected_json(id))
-                                                        .unwrap())
-                               .unwrap(),
-                           expected_message);
-        }
+    #[test]
+    fn input_ack_serializes_properly() {
+        let mut rng = thread_rng();
+        let seq: u64 = rng.gen();
 
-        #[test]
-        fn shots_fired_deserializes_properly() {
-            let mut rng = thread_rng();
-            let id: u32 = rng.gen();
-            let bullet_id: u32 = rng.gen();
-            let x = gen_f32(&mut rng);
-            let y = gen_f32(&mut rng);
-            let aim_x = gen_f32(&mut rng);
-            let aim_y = gen_f32(&mut rng);
+        let json_txt = Message::InputAck { seq: seq }.to_string();
 
-            let expected_message = Message::ShotsFired {
-                id: id,
-                bullet_id: bullet_id,
-                x: x,
-                y: y,
-                aim_x: aim_x,
-                aim_y: aim_y,
-            };
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   input_ack_expected_json(seq));
+    }
 
-            assert_eq!(str::parse::<Message>(&serde_json::to_string(&shots_fired_expected_json(id, bullet_id, x, y, aim_x, aim_y))
-                                                        .unwrap())
-                               .unwrap(),
-                           expected_message);
-        }
+    #[test]
+    fn kill_streak_serializes_properly() {
+        let mut rng = thread_rng();
+        let id: u32 = rng.gen();
+        let streak: u32 = rng.gen();
 
-        #[test]
-        fn player_spawned_deserializes_properly() {
-            let mut rng = thread_rng();
-            let id: u32 = rng.gen();
-            let x = gen_f32(&mut rng);
-            let y = gen_f32(&mut rng);
+        let json_txt = Message::KillStreak {
+                           id: id,
+                           streak: streak,
+                       }
+                       .to_string();
 
-            let expected_message = Message::PlayerSpawned {
-                id: id,
-                x: x,
-                y: y,
-            };
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   kill_streak_expected_json(id, streak));
+    }
 
-            assert_eq!(str::parse::<Message>(&serde_json::to_string(&player_spawned_expected_json(id, x, y))
-                                                        .unwrap())
-                               .unwrap(),
-                           expected_message);
-        }
+    #[test]
+    fn stats_serializes_properly() {
+        let mut rng = thread_rng();
+        let per_type = vec![("player_moving".to_string(), rng.gen(), rng.gen()), ("world_state".to_string(), rng.gen(), rng.gen())];
 
-        #[test]
-        fn player_destroyed_no_killer_deserializes_properly() {
-            let mut rng = thread_rng();
-            let id: u32 = rng.gen();
+        let json_txt = Message::Stats { per_type: per_type.clone() }.to_string();
 
-            let expected_message = Message::PlayerDestroyed {
-                id: id,
-                killer_id: None,
-                bullet_id: None,
-            };
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   stats_expected_json(&per_type));
+    }
 
-            assert_eq!(str::parse::<Message>(&serde_json::to_string(&player_destroyed_no_killer_expected_json(id))
-                                                        .unwrap())
-                               .unwrap(),
-                           expected_message);
-        }
+    #[test]
+    fn ready_serializes_properly() {
+        let json_txt = Message::Ready.to_string();
 
-        #[test]
-        fn player_destroyed_with_killer_deserializes_properly() {
-            let mut rng = thread_rng();
-            let id: u32 = rng.gen();
-            let killer_id: u32 = rng.gen();
-            let bullet_id: u32 = rng.gen();
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   ready_expected_json());
+    }
 
-            let expected_message = Message::PlayerDestroyed {
-                id: id,
-                killer_id: Some(killer_id),
-                bullet_id: Some(bullet_id),
-            };
+    #[test]
+    fn emote_serializes_properly() {
+        let mut rng = thread_rng();
+        let id: u32 = rng.gen();
+        let emote = rng.gen_range(0, EMOTE_COUNT);
 
-            assert_eq!(str::parse::<Message>(&serde_json::to_string(&player_destroyed_with_killer_expected_json(id, killer_id, bullet_id))
-                                                        .unwrap())
-                               .unwrap(),
-                           expected_message);
-        }
+        let json_txt = Message::Emote {
+                           id: Some(id),
+                           emote: emote,
+                       }
+                       .to_string();
 
-        #[test]
-        fn player_moving_deserializes_properly() {
-            let mut rng = thread_rng();
-            let id: u32 = rng.gen();
-            let x = gen_f32(&mut rng);
-            let y = gen_f32(&mut rng);
-            let move_x = gen_f32(&mut rng);
-            let move_y = gen_f32(&mut rng);
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   emote_expected_json(Some(id), emote as u64));
+    }
 
-            let expected_message = Message::PlayerMoving {
-                id: id,
-                x: x,
-                y: y,
-                move_x: move_x,
-                move_y: move_y,
-            };
+    #[test]
+    fn nack_serializes_properly() {
+        let mut rng = thread_rng();
+        let reason: String = {
+            let len = rng.gen_range(1, 100);
+            rng.gen_ascii_chars().take(len).collect()
+        };
+
+        let json_txt = Message::Nack { reason: reason.clone() }.to_string();
+
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   nack_expected_json(reason));
+    }
+
+    #[test]
+    fn set_paused_serializes_properly() {
+        for &paused in &[true, false] {
+            let json_txt = Message::SetPaused {
+                               paused: paused,
+                               password: "hunter2".to_string(),
+                           }
+                           .to_string();
+
+            assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                       set_paused_expected_json(paused, "hunter2"));
+        }
+    }
+
+    #[test]
+    fn game_paused_serializes_properly() {
+        for &paused in &[true, false] {
+            let json_txt = Message::GamePaused { paused: paused }.to_string();
+
+            assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                       game_paused_expected_json(paused));
+        }
+    }
+
+    #[test]
+    fn server_shutdown_serializes_properly() {
+        let seconds_remaining: u32 = thread_rng().gen();
+        let json_txt = Message::ServerShutdown { seconds_remaining: seconds_remaining }.to_string();
+
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   server_shutdown_expected_json(seconds_remaining));
+    }
+
+    #[test]
+    fn player_appearance_serializes_properly() {
+        let mut rng = thread_rng();
+        let id: u32 = rng.gen();
+        let color = gen_color(&mut rng);
+
+        let json_txt = Message::PlayerAppearance {
+                           id: id,
+                           color: color,
+                       }
+                       .to_string();
+
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   player_appearance_expected_json(id, color));
+    }
+
+    #[test]
+    fn player_facing_serializes_properly() {
+        let mut rng = thread_rng();
+        let id: u32 = rng.gen();
+        let facing_x = gen_f32(&mut rng);
+        let facing_y = gen_f32(&mut rng);
+
+        let json_txt = Message::PlayerFacing {
+                           id: id,
+                           facing_x: facing_x,
+                           facing_y: facing_y,
+                       }
+                       .to_string();
+
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   player_facing_expected_json(id, facing_x, facing_y));
+    }
+
+    #[test]
+    fn position_report_serializes_properly() {
+        let mut rng = thread_rng();
+        let x = gen_f32(&mut rng);
+        let y = gen_f32(&mut rng);
+
+        let json_txt = Message::PositionReport { x: x, y: y }.to_string();
+
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   position_report_expected_json(x, y));
+    }
+
+    #[test]
+    fn set_appearance_serializes_properly() {
+        let mut rng = thread_rng();
+        let color = gen_color(&mut rng);
+
+        let json_txt = Message::SetAppearance { color: color }.to_string();
+
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   set_appearance_expected_json(color));
+    }
+
+    #[test]
+    fn rounded_floats_serialize_properly() {
+        let message = Message::PlayerSpawned {
+            id: 1,
+            x: 34.66,
+            y: 10.0,
+        };
+        let json_txt = message.to_string_with_precision(Some(1));
+
+        assert!(json_txt.contains("34.7"), "{}", json_txt);
+        assert!(!json_txt.contains("34.66"), "{}", json_txt);
+        assert_eq!(str::parse::<Message>(&json_txt).unwrap(),
+                   Message::PlayerSpawned {
+                       id: 1,
+                       x: 34.7,
+                       y: 10.0,
+                   });
+    }
+
+    #[test]
+    fn rounded_world_state_rounds_nested_floats() {
+        let message = Message::WorldState {
+            player_count: 1,
+            alive_players: vec![Player::moving(0, 34.66, 21.44, 0.123, 0.987)],
+            alive_bullets: vec![],
+        };
+
+        assert_eq!(str::parse::<Message>(&message.to_string_with_precision(Some(1))).unwrap(),
+                   Message::WorldState {
+                       player_count: 1,
+                       alive_players: vec![Player::moving(0, 34.7, 21.4, 0.1, 1.0)],
+                       alive_bullets: vec![],
+                   });
+    }
+
+    #[test]
+    fn written_messages_match_strings() {
+        let messages = vec![Message::world_state(1,
+                                                 vec![Player::moving(0, 34.66, 21.44, 0.123, 0.987)],
+                                                 vec![Bullet::moving(0, 10.5, 10.5, 0.0, 1.0)]),
+                            Message::GoAway { reason: "Caf\u{e9} \"closed\"".to_string() },
+                            Message::StopMoving];
+
+        for message in messages {
+            let mut written = Vec::new();
+            message.write_to(&mut written).unwrap();
+            assert_eq!(written, message.to_string().into_bytes());
+
+            let mut written = Vec::new();
+            message.write_to_with_precision(&mut written, Some(1)).unwrap();
+            assert_eq!(written, message.to_string_with_precision(Some(1)).into_bytes());
+        }
+    }
+
+    #[test]
+    fn time_sync_is_never_rounded() {
+        let message = Message::TimeSyncResponse {
+            client_time: 1234.5678,
+            server_time: 0.125,
+        };
+
+        assert_eq!(message.to_string_with_precision(Some(1)), message.to_string());
+    }
+
+    #[test]
+    fn player_count_serializes_properly() {
+        let mut rng = thread_rng();
+        let count: u32 = rng.gen();
+
+        let json_txt = Message::PlayerCount { count: count }.to_string();
+
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   player_count_expected_json(count));
+    }
+
+    #[test]
+    fn fire_cooldown_serializes_properly() {
+        let mut rng = thread_rng();
+        let ready_in_ticks: u32 = rng.gen();
+
+        let json_txt = Message::FireCooldown { ready_in_ticks: ready_in_ticks }.to_string();
+
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   fire_cooldown_expected_json(ready_in_ticks));
+    }
+
+    #[test]
+    fn round_start_serializes_properly() {
+        let mut rng = thread_rng();
+        let round: u32 = rng.gen();
+        let duration_ticks: u32 = rng.gen();
+
+        let json_txt = Message::RoundStart {
+                           round: round,
+                           duration_ticks: duration_ticks,
+                       }
+                       .to_string();
+
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   round_start_expected_json(round, duration_ticks));
+    }
+
+    #[test]
+    fn round_end_serializes_properly() {
+        let mut rng = thread_rng();
+        let round: u32 = rng.gen();
+        let scores = vec![Score::new(rng.gen(), rng.gen()), Score::new(rng.gen(), rng.gen())];
+
+        let json_txt = Message::RoundEnd {
+                           round: round,
+                           scores: scores.clone(),
+                       }
+                       .to_string();
+
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   round_end_expected_json(round, &scores));
+    }
+
+    #[test]
+    fn bullet_destroyed_serializes_properly() {
+        let mut rng = thread_rng();
+        let id: u32 = rng.gen();
+
+        let json_txt = Message::BulletDestroyed { id: id }.to_string();
+
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   bullet_destroyed_expected_json(id));
+    }
+
+    #[test]
+    fn time_sync_request_serializes_properly() {
+        let mut rng = thread_rng();
+        let client_time = gen_f64(&mut rng);
+
+        let json_txt = Message::TimeSyncRequest { client_time: client_time }.to_string();
+
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   time_sync_request_expected_json(client_time));
+    }
+
+    #[test]
+    fn time_sync_response_serializes_properly() {
+        let mut rng = thread_rng();
+        let client_time = gen_f64(&mut rng);
+        let server_time = gen_f64(&mut rng);
+
+        let json_txt = Message::TimeSyncResponse {
+                           client_time: client_time,
+                           server_time: server_time,
+                       }
+                       .to_string();
+
+        assert_eq!(serde_json::from_str::<Value>(&json_txt).unwrap(),
+                   time_sync_response_expected_json(client_time, server_time));
+    }
+}
+
+mod de {
+    mod correct {
+        use std::usize;
+        use self::super::super::*;
+        use self::super::super::super::*;
+        use rand::{Rng, thread_rng};
+        use serde_json;
+
+        #[test]
+        fn welcome_deserializes_properly() {
+            let mut rng = thread_rng();
+            let id: u32 = rng.gen();
+            let speed = gen_f32(&mut rng);
+            let size = gen_f32(&mut rng);
+            let bullet_speed = gen_f32(&mut rng);
+            let bullet_size = gen_f32(&mut rng);
+            let features = vec!["ready".to_string(), "rounds".to_string()];
+
+            let expected_message = Message::Welcome {
+                id: id,
+                speed: speed,
+                size: size,
+                bullet_speed: bullet_speed,
+                bullet_size: bullet_size,
+                features: features.clone(),
+            };
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&welcome_expected_json(id, speed, size, bullet_speed, bullet_size, features))
+                                                        .unwrap())
+                               .unwrap(),
+                           expected_message);
+        }
+
+        #[test]
+        fn go_away_deserializes_properly() {
+            let mut rng = thread_rng();
+            let reason: String = {
+                let len = rng.gen_range(1, 100);
+                rng.gen_ascii_chars().take(len).collect()
+            };
+
+            let expected_message = Message::GoAway { reason: reason.clone() };
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&go_away_expected_json(reason))
+                                                        .unwrap())
+                               .unwrap(),
+                           expected_message);
+        }
+
+        #[test]
+        fn player_joined_deserializes_properly() {
+            let mut rng = thread_rng();
+            let id: u32 = rng.gen();
+
+            let expected_message = Message::PlayerJoined { id: id };
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&player_joined_expected_json(id))
+                                                        .unwrap())
+                               .unwrap(),
+                           expected_message);
+        }
+
+        #[test]
+        fn player_left_deserializes_properly() {
+            let mut rng = thread_rng();
+            let id: u32 = rng.gen();
+
+            let expected_message = Message::PlayerLeft { id: id };
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&player_left_expected_json(id))
+                                                        .unwrap())
+                               .unwrap(),
+                           expected_message);
+        }
+
+        #[test]
+        fn shots_fired_deserializes_properly() {
+            let mut rng = thread_rng();
+            let id: u32 = rng.gen();
+            let bullet_id: u32 = rng.gen();
+            let x = gen_f32(&mut rng);
+            let y = gen_f32(&mut rng);
+            let aim_x = gen_f32(&mut rng);
+            let aim_y = gen_f32(&mut rng);
+            let tick: u64 = rng.gen();
+
+            let expected_message = Message::ShotsFired {
+                id: id,
+                bullet_id: bullet_id,
+                x: x,
+                y: y,
+                aim_x: aim_x,
+                aim_y: aim_y,
+                tick: tick,
+            };
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&shots_fired_expected_json(id, bullet_id, x, y, aim_x, aim_y, tick))
+                                                        .unwrap())
+                               .unwrap(),
+                           expected_message);
+        }
+
+        #[test]
+        fn player_spawned_deserializes_properly() {
+            let mut rng = thread_rng();
+            let id: u32 = rng.gen();
+            let x = gen_f32(&mut rng);
+            let y = gen_f32(&mut rng);
+
+            let expected_message = Message::PlayerSpawned {
+                id: id,
+                x: x,
+                y: y,
+            };
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&player_spawned_expected_json(id, x, y))
+                                                        .unwrap())
+                               .unwrap(),
+                           expected_message);
+        }
+
+        #[test]
+        fn player_destroyed_no_killer_deserializes_properly() {
+            let mut rng = thread_rng();
+            let id: u32 = rng.gen();
+
+            let expected_message = Message::PlayerDestroyed {
+                id: id,
+                killer_id: None,
+                bullet_id: None,
+            };
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&player_destroyed_no_killer_expected_json(id))
+                                                        .unwrap())
+                               .unwrap(),
+                           expected_message);
+        }
+
+        #[test]
+        fn player_destroyed_with_killer_deserializes_properly() {
+            let mut rng = thread_rng();
+            let id: u32 = rng.gen();
+            let killer_id: u32 = rng.gen();
+            let bullet_id: u32 = rng.gen();
+
+            let expected_message = Message::PlayerDestroyed {
+                id: id,
+                killer_id: Some(killer_id),
+                bullet_id: Some(bullet_id),
+            };
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&player_destroyed_with_killer_expected_json(id, killer_id, bullet_id))
+                                                        .unwrap())
+                               .unwrap(),
+                           expected_message);
+        }
+
+        #[test]
+        fn player_moving_deserializes_properly() {
+            let mut rng = thread_rng();
+            let id: u32 = rng.gen();
+            let x = gen_f32(&mut rng);
+            let y = gen_f32(&mut rng);
+            let move_x = gen_f32(&mut rng);
+            let move_y = gen_f32(&mut rng);
+
+            let expected_message = Message::PlayerMoving {
+                id: id,
+                x: x,
+                y: y,
+                move_x: move_x,
+                move_y: move_y,
+            };
 
             assert_eq!(str::parse::<Message>(&serde_json::to_string(&player_moving_expected_json(id, x, y, move_x, move_y))
                                                         .unwrap())
@@ -457,102 +932,630 @@ mod de {
         }
 
         #[test]
-        fn player_stopped_deserializes_properly() {
+        fn player_stopped_deserializes_properly() {
+            let mut rng = thread_rng();
+            let id: u32 = rng.gen();
+            let x = gen_f32(&mut rng);
+            let y = gen_f32(&mut rng);
+
+            let expected_message = Message::PlayerStopped {
+                id: id,
+                x: x,
+                y: y,
+            };
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&player_stopped_expected_json(id, x, y))
+                                                        .unwrap())
+                               .unwrap(),
+                           expected_message);
+        }
+
+        #[test]
+        fn world_state_deserializes_properly() {
+            let mut rng = thread_rng();
+            let player_count: u32 = rng.gen_range(1, 100);
+            let alive_players = gen_bullets_or_players(&mut rng,
+                                                       Player::moving,
+                                                       Player::not_moving,
+                                                       player_count);
+            let alive_bullets = {
+                let bullet_count = rng.gen_range(player_count, player_count * 3);
+                gen_bullets_or_players(&mut rng, Bullet::moving, Bullet::not_moving, bullet_count)
+            };
+
+            let expected_json = world_state_expected_json(player_count,
+                                                          &alive_players,
+                                                          &alive_bullets);
+            let expected_message = Message::WorldState {
+                player_count: player_count,
+                alive_players: alive_players,
+                alive_bullets: alive_bullets,
+            };
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&expected_json).unwrap())
+                           .unwrap(),
+                       expected_message);
+        }
+
+        #[test]
+        fn player_teleported_deserializes_properly() {
+            let mut rng = thread_rng();
+            let id: u32 = rng.gen();
+            let x = gen_f32(&mut rng);
+            let y = gen_f32(&mut rng);
+
+            let expected_message = Message::PlayerTeleported {
+                id: id,
+                x: x,
+                y: y,
+            };
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&player_teleported_expected_json(id, x, y))
+                                                        .unwrap())
+                               .unwrap(),
+                           expected_message);
+        }
+
+        #[test]
+        fn bullet_moving_deserializes_properly() {
+            let mut rng = thread_rng();
+            let id: u32 = rng.gen();
+            let x = gen_f32(&mut rng);
+            let y = gen_f32(&mut rng);
+            let move_x = gen_f32(&mut rng);
+            let move_y = gen_f32(&mut rng);
+
+            let expected_message = Message::BulletMoving {
+                id: id,
+                x: x,
+                y: y,
+                move_x: move_x,
+                move_y: move_y,
+            };
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&bullet_moving_expected_json(id, x, y, move_x, move_y))
+                                                        .unwrap())
+                               .unwrap(),
+                           expected_message);
+        }
+
+        #[test]
+        fn start_moving_deserializes_properly() {
+            let mut rng = thread_rng();
+            let move_x = gen_f32(&mut rng);
+            let move_y = gen_f32(&mut rng);
+
+            let expected_message = Message::StartMoving {
+                move_x: move_x,
+                move_y: move_y,
+            };
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&start_moving_expected_json(move_x, move_y))
+                                                        .unwrap())
+                               .unwrap(),
+                           expected_message);
+        }
+
+        #[test]
+        fn stop_moving_deserializes_properly() {
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&stop_moving_expected_json())
+                                                  .unwrap())
+                           .unwrap(),
+                       Message::StopMoving);
+        }
+
+        #[test]
+        fn fire_deserializes_properly() {
+            let mut rng = thread_rng();
+            let move_x = gen_f32(&mut rng);
+            let move_y = gen_f32(&mut rng);
+
+            let expected_message = Message::Fire {
+                move_x: move_x,
+                move_y: move_y,
+            };
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&fire_expected_json(move_x,
+                                                                                        move_y))
+                                                  .unwrap())
+                           .unwrap(),
+                       expected_message);
+        }
+
+        #[test]
+        fn request_respawn_deserializes_properly() {
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&request_respawn_expected_json())
+                                                  .unwrap())
+                           .unwrap(),
+                       Message::RequestRespawn);
+        }
+
+        #[test]
+        fn request_respawn_with_empty_data_deserializes_properly() {
+            assert_eq!(str::parse::<Message>(r#"{"type":"request_respawn","data":{}}"#).unwrap(),
+                       Message::RequestRespawn);
+        }
+
+        #[test]
+        fn request_stats_deserializes_properly() {
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&request_stats_expected_json())
+                                                  .unwrap())
+                           .unwrap(),
+                       Message::RequestStats);
+        }
+
+        #[test]
+        fn shot_blocked_deserializes_properly() {
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&shot_blocked_expected_json())
+                                                  .unwrap())
+                           .unwrap(),
+                       Message::ShotBlocked);
+        }
+
+        #[test]
+        fn request_player_stats_deserializes_properly() {
+            let mut rng = thread_rng();
+            let id: u32 = rng.gen();
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&request_player_stats_expected_json(id))
+                                                  .unwrap())
+                           .unwrap(),
+                       Message::RequestPlayerStats { id: id });
+        }
+
+        #[test]
+        fn player_stats_deserializes_properly() {
+            let mut rng = thread_rng();
+            let id: u32 = rng.gen();
+            let kills: u32 = rng.gen();
+            let deaths: u32 = rng.gen();
+            let health = gen_f32(&mut rng);
+
+            let expected_message = Message::PlayerStats {
+                id: id,
+                kills: kills,
+                deaths: deaths,
+                health: health,
+            };
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&player_stats_expected_json(id, kills, deaths, health))
+                                                  .unwrap())
+                           .unwrap(),
+                       expected_message);
+        }
+
+        #[test]
+        fn input_ack_deserializes_properly() {
+            let mut rng = thread_rng();
+            let seq: u64 = rng.gen();
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&input_ack_expected_json(seq))
+                                                  .unwrap())
+                           .unwrap(),
+                       Message::InputAck { seq: seq });
+        }
+
+        #[test]
+        fn kill_streak_deserializes_properly() {
+            let mut rng = thread_rng();
+            let id: u32 = rng.gen();
+            let streak: u32 = rng.gen();
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&kill_streak_expected_json(id, streak))
+                                                  .unwrap())
+                           .unwrap(),
+                       Message::KillStreak {
+                           id: id,
+                           streak: streak,
+                       });
+        }
+
+        #[test]
+        fn stats_deserializes_properly() {
+            let mut rng = thread_rng();
+            let per_type = vec![("go_away".to_string(), rng.gen(), rng.gen()), ("welcome".to_string(), rng.gen(), rng.gen())];
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&stats_expected_json(&per_type))
+                                                  .unwrap())
+                           .unwrap(),
+                       Message::Stats { per_type: per_type });
+        }
+
+        #[test]
+        fn ready_deserializes_properly() {
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&ready_expected_json())
+                                                  .unwrap())
+                           .unwrap(),
+                       Message::Ready);
+        }
+
+        #[test]
+        fn emote_deserializes_properly() {
+            let mut rng = thread_rng();
+            let id: u32 = rng.gen();
+            let emote = rng.gen_range(0, EMOTE_COUNT);
+
+            let expected_message = Message::Emote {
+                id: Some(id),
+                emote: emote,
+            };
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&emote_expected_json(Some(id), emote as u64))
+                                                  .unwrap())
+                           .unwrap(),
+                       expected_message);
+        }
+
+        #[test]
+        fn emote_without_id_deserializes_properly() {
+            let mut rng = thread_rng();
+            let emote = rng.gen_range(0, EMOTE_COUNT);
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&emote_expected_json(None, emote as u64))
+                                                  .unwrap())
+                           .unwrap(),
+                       Message::Emote {
+                           id: None,
+                           emote: emote,
+                       });
+        }
+
+        #[test]
+        fn emote_out_of_range_is_left_to_the_server() {
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&emote_expected_json(None, EMOTE_COUNT as u64))
+                                                  .unwrap())
+                           .unwrap(),
+                       Message::Emote {
+                           id: None,
+                           emote: EMOTE_COUNT,
+                       });
+        }
+
+        #[test]
+        fn nack_deserializes_properly() {
+            let mut rng = thread_rng();
+            let reason: String = {
+                let len = rng.gen_range(1, 100);
+                rng.gen_ascii_chars().take(len).collect()
+            };
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&nack_expected_json(reason.clone())).unwrap())
+                           .unwrap(),
+                       Message::Nack { reason: reason });
+        }
+
+        #[test]
+        fn set_paused_deserializes_properly() {
+            for &paused in &[true, false] {
+                assert_eq!(str::parse::<Message>(&serde_json::to_string(&set_paused_expected_json(paused, "hunter2"))
+                                                      .unwrap())
+                               .unwrap(),
+                           Message::SetPaused {
+                               paused: paused,
+                               password: "hunter2".to_string(),
+                           });
+            }
+        }
+
+        #[test]
+        fn game_paused_deserializes_properly() {
+            for &paused in &[true, false] {
+                assert_eq!(str::parse::<Message>(&serde_json::to_string(&game_paused_expected_json(paused)).unwrap())
+                               .unwrap(),
+                           Message::GamePaused { paused: paused });
+            }
+        }
+
+        #[test]
+        fn server_shutdown_deserializes_properly() {
+            let seconds_remaining: u32 = thread_rng().gen();
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&server_shutdown_expected_json(seconds_remaining)).unwrap())
+                           .unwrap(),
+                       Message::ServerShutdown { seconds_remaining: seconds_remaining });
+        }
+
+        #[test]
+        fn player_facing_deserializes_properly() {
+            let mut rng = thread_rng();
+            let id: u32 = rng.gen();
+            let facing_x = gen_f32(&mut rng);
+            let facing_y = gen_f32(&mut rng);
+
+            let expected_message = Message::PlayerFacing {
+                id: id,
+                facing_x: facing_x,
+                facing_y: facing_y,
+            };
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&player_facing_expected_json(id, facing_x, facing_y))
+                                                  .unwrap())
+                           .unwrap(),
+                       expected_message);
+        }
+
+        #[test]
+        fn player_appearance_deserializes_properly() {
+            let mut rng = thread_rng();
+            let id: u32 = rng.gen();
+            let color = gen_color(&mut rng);
+
+            let expected_message = Message::PlayerAppearance {
+                id: id,
+                color: color,
+            };
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&player_appearance_expected_json(id, color))
+                                                  .unwrap())
+                           .unwrap(),
+                       expected_message);
+        }
+
+        #[test]
+        fn position_report_deserializes_properly() {
             let mut rng = thread_rng();
-            let id: u32 = rng.gen();
             let x = gen_f32(&mut rng);
             let y = gen_f32(&mut rng);
 
-            let expected_message = Message::PlayerStopped {
-                id: id,
-                x: x,
-                y: y,
-            };
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&position_report_expected_json(x, y))
+                                                  .unwrap())
+                           .unwrap(),
+                       Message::PositionReport { x: x, y: y });
+        }
 
-            assert_eq!(str::parse::<Message>(&serde_json::to_string(&player_stopped_expected_json(id, x, y))
-                                                        .unwrap())
-                               .unwrap(),
-                           expected_message);
+        #[test]
+        fn set_appearance_deserializes_properly() {
+            let mut rng = thread_rng();
+            let color = gen_color(&mut rng);
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&set_appearance_expected_json(color))
+                                                  .unwrap())
+                           .unwrap(),
+                       Message::SetAppearance { color: color });
         }
 
         #[test]
-        fn world_state_deserializes_properly() {
+        fn player_count_deserializes_properly() {
             let mut rng = thread_rng();
-            let player_count: u32 = rng.gen_range(1, 100);
-            let alive_players = gen_bullets_or_players(&mut rng,
-                                                       Player::moving,
-                                                       Player::not_moving,
-                                                       player_count);
-            let alive_bullets = {
-                let bullet_count = rng.gen_range(player_count, player_count * 3);
-                gen_bullets_or_players(&mut rng, Bullet::moving, Bullet::not_moving, bullet_count)
-            };
+            let count: u32 = rng.gen();
 
-            let expected_json = world_state_expected_json(player_count,
-                                                          &alive_players,
-                                                          &alive_bullets);
-            let expected_message = Message::WorldState {
-                player_count: player_count,
-                alive_players: alive_players,
-                alive_bullets: alive_bullets,
-            };
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&player_count_expected_json(count))
+                                                  .unwrap())
+                           .unwrap(),
+                       Message::PlayerCount { count: count });
+        }
 
-            assert_eq!(str::parse::<Message>(&serde_json::to_string(&expected_json).unwrap())
+        #[test]
+        fn fire_cooldown_deserializes_properly() {
+            let mut rng = thread_rng();
+            let ready_in_ticks: u32 = rng.gen();
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&fire_cooldown_expected_json(ready_in_ticks))
+                                                  .unwrap())
                            .unwrap(),
-                       expected_message);
+                       Message::FireCooldown { ready_in_ticks: ready_in_ticks });
         }
 
         #[test]
-        fn start_moving_deserializes_properly() {
+        fn round_start_deserializes_properly() {
             let mut rng = thread_rng();
-            let move_x = gen_f32(&mut rng);
-            let move_y = gen_f32(&mut rng);
+            let round: u32 = rng.gen();
+            let duration_ticks: u32 = rng.gen();
 
-            let expected_message = Message::StartMoving {
-                move_x: move_x,
-                move_y: move_y,
-            };
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&round_start_expected_json(round, duration_ticks))
+                                                  .unwrap())
+                           .unwrap(),
+                       Message::RoundStart {
+                           round: round,
+                           duration_ticks: duration_ticks,
+                       });
+        }
 
-            assert_eq!(str::parse::<Message>(&serde_json::to_string(&start_moving_expected_json(move_x, move_y))
-                                                        .unwrap())
-                               .unwrap(),
-                           expected_message);
+        #[test]
+        fn round_end_deserializes_properly() {
+            let mut rng = thread_rng();
+            let round: u32 = rng.gen();
+            let scores = vec![Score::new(rng.gen(), rng.gen()), Score::new(rng.gen(), rng.gen())];
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&round_end_expected_json(round, &scores))
+                                                  .unwrap())
+                           .unwrap(),
+                       Message::RoundEnd {
+                           round: round,
+                           scores: scores,
+                       });
         }
 
         #[test]
-        fn stop_moving_deserializes_properly() {
-            assert_eq!(str::parse::<Message>(&serde_json::to_string(&stop_moving_expected_json())
+        fn bullet_destroyed_deserializes_properly() {
+            let mut rng = thread_rng();
+            let id: u32 = rng.gen();
+
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&bullet_destroyed_expected_json(id))
                                                   .unwrap())
                            .unwrap(),
-                       Message::StopMoving);
+                       Message::BulletDestroyed { id: id });
         }
 
         #[test]
-        fn fire_deserializes_properly() {
+        fn time_sync_request_deserializes_properly() {
             let mut rng = thread_rng();
-            let move_x = gen_f32(&mut rng);
-            let move_y = gen_f32(&mut rng);
+            let client_time = gen_f64(&mut rng);
 
-            let expected_message = Message::Fire {
-                move_x: move_x,
-                move_y: move_y,
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&time_sync_request_expected_json(client_time))
+                                                  .unwrap())
+                           .unwrap(),
+                       Message::TimeSyncRequest { client_time: client_time });
+        }
+
+        #[test]
+        fn time_sync_response_deserializes_properly() {
+            let mut rng = thread_rng();
+            let client_time = gen_f64(&mut rng);
+            let server_time = gen_f64(&mut rng);
+
+            let expected_message = Message::TimeSyncResponse {
+                client_time: client_time,
+                server_time: server_time,
             };
 
-            assert_eq!(str::parse::<Message>(&serde_json::to_string(&fire_expected_json(move_x,
-                                                                                        move_y))
+            assert_eq!(str::parse::<Message>(&serde_json::to_string(&time_sync_response_expected_json(client_time,
+                                                                                                      server_time))
                                                   .unwrap())
                            .unwrap(),
                        expected_message);
         }
+
+        #[test]
+        fn integer_floats_deserialize_properly() {
+            let cases = vec![(r#"{"type":"start_moving","data":{"move_x":1,"move_y":-1}}"#,
+                              Message::StartMoving {
+                                  move_x: 1.0,
+                                  move_y: -1.0,
+                              }),
+                             (r#"{"type":"fire","data":{"move_x":0,"move_y":3}}"#,
+                              Message::Fire {
+                                  move_x: 0.0,
+                                  move_y: 3.0,
+                              }),
+                             (r#"{"type":"shots_fired","data":{"id":1,"bullet_id":2,"x":10,"y":20,"aim_x":-1,"aim_y":0,"tick":3}}"#,
+                              Message::ShotsFired {
+                                  id: 1,
+                                  bullet_id: 2,
+                                  x: 10.0,
+                                  y: 20.0,
+                                  aim_x: -1.0,
+                                  aim_y: 0.0,
+                                  tick: 3,
+                              })];
+
+            for (json_txt, expected_message) in cases {
+                // Both through the fast path, where it applies, and through the general parser
+                assert_eq!(str::parse::<Message>(json_txt).unwrap(), expected_message);
+                assert_eq!(Message::parse(json_txt, usize::MAX).unwrap(), expected_message);
+            }
+        }
+
+        #[test]
+        fn movement_along_one_axis_round_trips() {
+            let cases = vec![(r#"{"type":"start_moving","data":{"move_y":-1.0}}"#,
+                              Message::StartMoving {
+                                  move_x: 0.0,
+                                  move_y: -1.0,
+                              }),
+                             (r#"{"type":"start_moving","data":{"move_x":0.5}}"#,
+                              Message::StartMoving {
+                                  move_x: 0.5,
+                                  move_y: 0.0,
+                              }),
+                             (r#"{"type":"player_moving","data":{"id":1,"x":10.0,"y":20.0,"move_x":1.0}}"#,
+                              Message::PlayerMoving {
+                                  id: 1,
+                                  x: 10.0,
+                                  y: 20.0,
+                                  move_x: 1.0,
+                                  move_y: 0.0,
+                              }),
+                             (r#"{"type":"player_moving","data":{"id":1,"x":10.0,"y":20.0,"move_y":1.0}}"#,
+                              Message::PlayerMoving {
+                                  id: 1,
+                                  x: 10.0,
+                                  y: 20.0,
+                                  move_x: 0.0,
+                                  move_y: 1.0,
+                              })];
+
+            for (json_txt, expected_message) in cases {
+                let message = str::parse::<Message>(json_txt).unwrap();
+                assert_eq!(message, expected_message);
+
+                // The missing component is sent as an explicit 0, which parses back the same
+                assert_eq!(str::parse::<Message>(&message.to_string()).unwrap(), message);
+            }
+        }
     }
 
     mod incorrect {
         use std::collections::BTreeMap;
         use self::super::super::*;
-        use self::super::super::super::{Message, MessageError};
+        use std::iter;
+        use self::super::super::super::{Message, MessageError, Player, EMOTE_COUNT, MAX_REASON_LENGTH};
         use rand::{Rng, thread_rng};
         use serde_json;
 
+        #[test]
+        fn go_away_with_overlong_reason_fails() {
+            let reason: String = iter::repeat('a').take(MAX_REASON_LENGTH + 1).collect();
+
+            match str::parse::<Message>(&serde_json::to_string(&go_away_expected_json(reason)).unwrap()).unwrap_err() {
+                MessageError::InvalidValue(_) => {}
+                me => panic!(format!("Incorrect error kind: {:?}, should be InvalidValue", me)),
+            }
+        }
+
+        #[test]
+        fn set_appearance_with_out_of_range_color_fails() {
+            match str::parse::<Message>(&serde_json::to_string(&set_appearance_expected_json(0x1000000)).unwrap())
+                      .unwrap_err() {
+                MessageError::BadType(_) => {}
+                _ => panic!("Incorrect error kind"),
+            }
+        }
+
+        #[test]
+        fn emote_past_u8_fails() {
+            match str::parse::<Message>(&serde_json::to_string(&emote_expected_json(Some(0), 256)).unwrap()).unwrap_err() {
+                MessageError::BadType(_) => {}
+                me => panic!(format!("Incorrect error kind: {:?}, should be BadType", me)),
+            }
+        }
+
+        #[test]
+        fn world_state_with_too_many_bullets_fails() {
+            let alive_bullets: Vec<_> = (0..5).map(|id| Bullet::not_moving(id, 1.0, 2.0)).collect();
+            let json_txt = serde_json::to_string(&world_state_expected_json(5, &vec![], &alive_bullets)).unwrap();
+
+            match Message::from_str_with_max_array_length(&json_txt, 4).unwrap_err() {
+                MessageError::ExtraneousProperty(_) => {}
+                me => panic!(format!("Incorrect error kind: {:?}, should be ExtraneousProperty", me)),
+            }
+            assert!(Message::from_str_with_max_array_length(&json_txt, 5).is_ok());
+        }
+
+        #[test]
+        fn negative_id_fails() {
+            match str::parse::<Message>(r#"{"type":"player_joined","data":{"id":-1}}"#).unwrap_err() {
+                MessageError::BadType(_) => {}
+                me => panic!(format!("Incorrect error kind: {:?}, should be BadType", me)),
+            }
+        }
+
+        #[test]
+        fn id_past_u32_fails() {
+            match str::parse::<Message>(r#"{"type":"player_joined","data":{"id":5000000000}}"#).unwrap_err() {
+                MessageError::BadType(_) => {}
+                me => panic!(format!("Incorrect error kind: {:?}, should be BadType", me)),
+            }
+        }
+
+        #[test]
+        fn player_with_negative_id_fails() {
+            let mut player_json = Player::not_moving(0, 1.0, 2.0).to_json();
+            let _ = player_json.as_object_mut().unwrap().insert("id".to_string(), serde_json::Value::I64(-1));
+
+            match Player::from_json(&player_json).unwrap_err() {
+                MessageError::BadType(_) => {}
+                me => panic!(format!("Incorrect error kind: {:?}, should be BadType", me)),
+            }
+        }
+
+        #[test]
+        fn set_appearance_with_negative_color_fails() {
+            match str::parse::<Message>(r#"{"type":"set_appearance","data":{"color":-1}}"#).unwrap_err() {
+                MessageError::BadType(_) => {}
+                _ => panic!("Incorrect error kind"),
+            }
+        }
+
         #[test]
         fn player_destroyed_with_killer_no_bullet_fails() {
             let mut rng = thread_rng();
@@ -599,6 +1602,52 @@ mod de {
             }
         }
 
+        #[test]
+        fn players_moving_with_player_not_moving_fails() {
+            let unexpected_json = players_moving_expected_json(&vec![Player::moving(0, 10.5, 10.5, 1.0, 0.0),
+                                                                     Player::not_moving(1, 20.5, 20.5)]);
+
+            match str::parse::<Message>(&serde_json::to_string(&unexpected_json).unwrap())
+                      .unwrap_err() {
+                MessageError::InvalidValue(_) => {}
+                me => panic!(format!("Incorrect error kind: {:?}, should be InvalidValue", me)),
+            }
+        }
+
+        #[test]
+        fn world_state_with_more_alive_than_connected_players_fails() {
+            let alive_players = vec![Player::not_moving(0, 10.5, 10.5), Player::not_moving(1, 20.5, 20.5)];
+            let unexpected_json = world_state_expected_json(1, &alive_players, &vec![]);
+
+            match str::parse::<Message>(&serde_json::to_string(&unexpected_json).unwrap())
+                      .unwrap_err() {
+                MessageError::InvalidValue(_) => {}
+                me => panic!(format!("Incorrect error kind: {:?}, should be InvalidValue", me)),
+            }
+        }
+
+        #[test]
+        fn start_moving_with_infinite_vector_fails() {
+            match str::parse::<Message>(r#"{"type":"start_moving","data":{"move_x":1e39,"move_y":0.0}}"#).unwrap_err() {
+                MessageError::InvalidValue(_) => {}
+                me => panic!(format!("Incorrect error kind: {:?}, should be InvalidValue", me)),
+            }
+        }
+
+        #[test]
+        fn player_destroyed_with_killer_no_bullet_is_invalid() {
+            let message = Message::PlayerDestroyed {
+                id: 0,
+                killer_id: Some(1),
+                bullet_id: None,
+            };
+
+            match message.validate().unwrap_err() {
+                MessageError::InvalidValue(_) => {}
+                me => panic!(format!("Incorrect error kind: {:?}, should be InvalidValue", me)),
+            }
+        }
+
         #[test]
         fn missing_type_fails() {
             let mut unexpected_json = player_joined_expected_json(0);
@@ -622,50 +1671,357 @@ mod de {
                                    .remove("data")
                                    .unwrap();
 
-            match str::parse::<Message>(&serde_json::to_string(&unexpected_json).unwrap())
-                      .unwrap_err() {
-                MessageError::PropertyMissing(_) => {}
-                me => panic!(format!("Incorrect error kind: {:?}, should be PropertyMissing", me)),
-            }
+            match str::parse::<Message>(&serde_json::to_string(&unexpected_json).unwrap())
+                      .unwrap_err() {
+                MessageError::PropertyMissing(_) => {}
+                me => panic!(format!("Incorrect error kind: {:?}, should be PropertyMissing", me)),
+            }
+        }
+
+        #[test]
+        fn missing_data_subkey_fails() {
+            let mut unexpected_json = player_joined_expected_json(0);
+            let _ = unexpected_json.as_object_mut()
+                                   .unwrap()
+                                   .get_mut("data")
+                                   .unwrap()
+                                   .as_object_mut()
+                                   .unwrap()
+                                   .clear();
+
+            match str::parse::<Message>(&serde_json::to_string(&unexpected_json).unwrap())
+                      .unwrap_err() {
+                MessageError::PropertyMissing(_) => {}
+                me => panic!(format!("Incorrect error kind: {:?}, should be PropertyMissing", me)),
+            }
+        }
+
+        #[test]
+        fn empty_toplevel_object_fails() {
+            let unexpected_json = serde_json::Value::Object(BTreeMap::new());
+
+            match str::parse::<Message>(&serde_json::to_string(&unexpected_json).unwrap())
+                      .unwrap_err() {
+                MessageError::PropertyMissing(_) => {}
+                me => panic!(format!("Incorrect error kind: {:?}, should be PropertyMissing", me)),
+            }
+        }
+
+        #[test]
+        fn incorrect_toplevel_type_fails() {
+            let unexpected_json = serde_json::Value::Null;
+
+            match str::parse::<Message>(&serde_json::to_string(&unexpected_json).unwrap())
+                      .unwrap_err() {
+                MessageError::BadType(_) => {}
+                me => panic!(format!("Incorrect error kind: {:?}, should be BadType", me)),
+            }
+        }
+    }
+}
+
+mod schema {
+    use self::super::*;
+    use self::super::super::{Message, SCHEMA};
+    use serde_json::{self, Value};
+
+    #[test]
+    fn every_message_serializes_exactly_its_schema_keys() {
+        let messages = vec![Message::Welcome {
+                                id: 0,
+                                speed: 1.0,
+                                size: 2.0,
+                                bullet_speed: 3.0,
+                                bullet_size: 4.0,
+                                features: vec!["ready".to_string()],
+                            },
+                            Message::GoAway { reason: "reason".to_string() },
+                            Message::PlayerJoined { id: 0 },
+                            Message::PlayerLeft { id: 0 },
+                            Message::ShotsFired {
+                                id: 0,
+                                bullet_id: 1,
+                                x: 1.0,
+                                y: 2.0,
+                                aim_x: 3.0,
+                                aim_y: 4.0,
+                                tick: 5,
+                            },
+                            Message::PlayerSpawned { id: 0, x: 1.0, y: 2.0 },
+                            Message::PlayerDestroyed {
+                                id: 0,
+                                killer_id: Some(1),
+                                bullet_id: Some(2),
+                            },
+                            Message::PlayerMoving {
+                                id: 0,
+                                x: 1.0,
+                                y: 2.0,
+                                move_x: 3.0,
+                                move_y: 4.0,
+                            },
+                            Message::PlayerStopped { id: 0, x: 1.0, y: 2.0 },
+                            Message::WorldState {
+                                player_count: 1,
+                                alive_players: vec![Player::not_moving(0, 1.0, 2.0)],
+                                alive_bullets: vec![Bullet::not_moving(0, 1.0, 2.0)],
+                            },
+                            Message::StartMoving { move_x: 1.0, move_y: 2.0 },
+                            Message::StopMoving,
+                            Message::Fire { move_x: 1.0, move_y: 2.0 },
+                            Message::RequestRespawn,
+                            Message::PlayerTeleported { id: 0, x: 1.0, y: 2.0 },
+                            Message::BulletMoving {
+                                id: 0,
+                                x: 1.0,
+                                y: 2.0,
+                                move_x: 3.0,
+                                move_y: 4.0,
+                            },
+                            Message::TimeSyncRequest { client_time: 1.0 },
+                            Message::TimeSyncResponse { client_time: 1.0, server_time: 2.0 },
+                            Message::BulletDestroyed { id: 0 },
+                            Message::PlayerAppearance { id: 0, color: 0xFF0000 },
+                            Message::SetAppearance { color: 0xFF0000 },
+                            Message::PlayerCount { count: 1 },
+                            Message::FireCooldown { ready_in_ticks: 1 },
+                            Message::RoundStart { round: 1, duration_ticks: 2 },
+                            Message::RoundEnd { round: 1, scores: vec![Score::new(0, 1)] },
+                            Message::PositionReport { x: 1.0, y: 2.0 },
+                            Message::Ready,
+                            Message::Emote {
+                                id: Some(0),
+                                emote: 1,
+                            },
+                            Message::GamePaused { paused: true },
+                            Message::ServerShutdown { seconds_remaining: 5 },
+                            Message::PlayersMoving { updates: vec![Player::moving(0, 1.0, 2.0, 0.0, 1.0)] },
+                            Message::PlayerFacing {
+                                id: 0,
+                                facing_x: 1.0,
+                                facing_y: 0.0,
+                            },
+                            Message::RequestStats,
+                            Message::Stats { per_type: vec![("welcome".to_string(), 1, 2)] },
+                            Message::ShotBlocked,
+                            Message::RequestPlayerStats { id: 0 },
+                            Message::PlayerStats {
+                                id: 0,
+                                kills: 1,
+                                deaths: 2,
+                                health: 3.0,
+                            },
+                            Message::InputAck { seq: 1 },
+                            Message::KillStreak {
+                                id: 1,
+                                streak: 3,
+                            },
+                            Message::Nack { reason: "reason".to_string() },
+                            Message::SetPaused {
+                                paused: true,
+                                password: "password".to_string(),
+                            }];
+
+        let mut seen_types = Vec::new();
+        for message in messages {
+            let json = serde_json::from_str::<Value>(&message.to_string()).unwrap();
+            let json = json.as_object().unwrap();
+            let msg_type = match json.get("type") {
+                Some(&Value::String(ref msg_type)) => msg_type.clone(),
+                msg_type => panic!("Message type not String: {:?}", msg_type),
+            };
+            let keys = match json.get("data") {
+                Some(data) => data.as_object().unwrap().keys().cloned().collect::<Vec<_>>(),
+                None => vec![],
+            };
+
+            let &(_, fields) = SCHEMA.iter().find(|&&(name, _)| name == msg_type).unwrap();
+            let mut expected_keys = fields.iter().map(|&(name, _)| name.to_string()).collect::<Vec<_>>();
+            expected_keys.sort();
+            assert_eq!(keys, expected_keys);
+
+            seen_types.push(msg_type);
+        }
+
+        let mut schema_types = SCHEMA.iter().map(|&(name, _)| name.to_string()).collect::<Vec<_>>();
+        schema_types.sort();
+        seen_types.sort();
+        assert_eq!(seen_types, schema_types);
+    }
+}
+
+mod fast {
+    use std::usize;
+    use self::super::*;
+    use self::super::super::Message;
+    use self::super::super::fast;
+    use rand::thread_rng;
+    use serde_json;
+
+    #[test]
+    fn fixed_shape_client_messages_take_fast_path() {
+        let mut rng = thread_rng();
+        let move_x = gen_f32(&mut rng);
+        let move_y = gen_f32(&mut rng);
+
+        for json in vec![start_moving_expected_json(move_x, move_y),
+                         stop_moving_expected_json(),
+                         fire_expected_json(move_x, move_y)] {
+            let json_txt = serde_json::to_string(&json).unwrap();
+
+            assert_eq!(fast::parse(&json_txt), Some(Message::parse(&json_txt, usize::MAX).unwrap()));
+        }
+        assert_eq!(fast::parse(r#"{"data":{},"type":"stop_moving"}"#), Some(Message::StopMoving));
+        assert_eq!(fast::parse(r#"{"data":{"move_y":-1,"move_x":0},"type":"fire"}"#),
+                   Some(Message::Fire {
+                       move_x: 0.0,
+                       move_y: -1.0,
+                   }));
+    }
+
+    #[test]
+    fn malformed_fast_path_messages_are_left_to_general_parser() {
+        for json_txt in &[r#"{"type":"stop_moving","data":{"move_x":1.0}}"#,
+                          r#"{"type":"stop_moving","extra":1}"#,
+                          r#"{"type":"start_moving","data":{"move_x":1.0}}"#,
+                          r#"{"type":"start_moving","data":{"move_x":1.0,"move_y":0.0,"move_z":0.0}}"#,
+                          r#"{"type":"start_moving","data":{"move_x":"1.0","move_y":0.0}}"#,
+                          r#"{"type":"fire","data":{"move_x":1.0,"move_y":0.0},"extra":{}}"#,
+                          r#"{"type":"fire","data":{"move_x":1.0,"move_x":2.0,"move_y":0.0}}"#,
+                          r#"{"type":"fire"}"#,
+                          r#"{"type":"player_joined","data":{"id":1}}"#,
+                          r#"{"type":1}"#,
+                          r#"[]"#] {
+            assert_eq!(fast::parse(json_txt), None);
+        }
+
+        assert!(str::parse::<Message>(r#"{"type":"start_moving","data":{"move_x":1.0,"move_y":0.0,"move_z":0.0}}"#)
+                    .is_err());
+    }
+}
+
+mod prefix {
+    use self::super::super::{Message, MessageError};
+
+    #[test]
+    fn concatenated_messages_are_parsed_one_at_a_time() {
+        let go_away = Message::GoAway { reason: r#"}{"[\"#.to_string() };
+        let fire = Message::Fire {
+            move_x: 1.0,
+            move_y: -1.0,
+        };
+        let buffer = format!("{}\n{}", go_away.to_string(), fire.to_string());
+
+        let (first, rest) = Message::parse_prefix(&buffer).unwrap();
+        assert_eq!(first, go_away);
+        let (second, rest) = Message::parse_prefix(rest).unwrap();
+        assert_eq!(second, fire);
+        assert_eq!(rest, "");
+    }
+
+    #[test]
+    fn incomplete_message_fails() {
+        let buffer = Message::StopMoving.to_string();
+
+        match Message::parse_prefix(&buffer[..buffer.len() - 1]).unwrap_err() {
+            MessageError::JsonError(_) => {}
+            me => panic!(format!("Incorrect error kind: {:?}, should be JsonError", me)),
         }
+        match Message::parse_prefix("[]").unwrap_err() {
+            MessageError::BadType(_) => {}
+            me => panic!(format!("Incorrect error kind: {:?}, should be BadType", me)),
+        }
+    }
+}
 
-        #[test]
-        fn missing_data_subkey_fails() {
-            let mut unexpected_json = player_joined_expected_json(0);
-            let _ = unexpected_json.as_object_mut()
-                                   .unwrap()
-                                   .get_mut("data")
-                                   .unwrap()
-                                   .as_object_mut()
-                                   .unwrap()
-                                   .clear();
+mod seq {
+    use std::usize;
+    use self::super::super::{with_seq, Message, MessageError};
 
-            match str::parse::<Message>(&serde_json::to_string(&unexpected_json).unwrap())
-                      .unwrap_err() {
-                MessageError::PropertyMissing(_) => {}
-                me => panic!(format!("Incorrect error kind: {:?}, should be PropertyMissing", me)),
-            }
+    #[test]
+    fn sequenced_messages_parse_like_plain_ones() {
+        let messages = vec![Message::StopMoving,
+                            Message::Fire {
+                                move_x: 1.0,
+                                move_y: -1.0,
+                            }];
+
+        for (seq, message) in messages.into_iter().enumerate() {
+            let sequenced = with_seq(&message.to_string(), seq as u64);
+            assert!(sequenced.starts_with(&format!(r#"{{"seq":{},"#, seq)), "{}", sequenced);
+            assert_eq!(str::parse::<Message>(&sequenced).unwrap(), message);
         }
+    }
 
-        #[test]
-        fn empty_toplevel_object_fails() {
-            let unexpected_json = serde_json::Value::Object(BTreeMap::new());
+    #[test]
+    fn seq_is_parsed_alongside_message() {
+        let fire = Message::Fire {
+            move_x: 1.0,
+            move_y: -1.0,
+        };
 
-            match str::parse::<Message>(&serde_json::to_string(&unexpected_json).unwrap())
-                      .unwrap_err() {
-                MessageError::PropertyMissing(_) => {}
-                me => panic!(format!("Incorrect error kind: {:?}, should be PropertyMissing", me)),
-            }
+        assert_eq!(Message::from_str_with_seq(&with_seq(&fire.to_string(), 7), usize::MAX).unwrap(),
+                   (fire.clone(), Some(7)));
+        assert_eq!(Message::from_str_with_seq(&fire.to_string(), usize::MAX).unwrap(),
+                   (fire, None));
+    }
+
+    #[test]
+    fn non_integer_seq_fails() {
+        match str::parse::<Message>(r#"{"seq":"1","type":"stop_moving"}"#).unwrap_err() {
+            MessageError::BadType(_) => {}
+            me => panic!(format!("Incorrect error kind: {:?}, should be BadType", me)),
         }
+    }
+}
 
-        #[test]
-        fn incorrect_toplevel_type_fails() {
-            let unexpected_json = serde_json::Value::Null;
+mod normalised {
+    use self::super::super::{Bullet, Message, MessageError, Player};
 
-            match str::parse::<Message>(&serde_json::to_string(&unexpected_json).unwrap())
-                      .unwrap_err() {
-                MessageError::BadType(_) => {}
-                me => panic!(format!("Incorrect error kind: {:?}, should be BadType", me)),
+    #[test]
+    fn normalised_vectors_pass() {
+        let messages = vec![Message::PlayerMoving {
+                                id: 0,
+                                x: 1.0,
+                                y: 2.0,
+                                move_x: 0.6,
+                                move_y: -0.8,
+                            },
+                            Message::PlayersMoving { updates: vec![Player::moving(0, 1.0, 2.0, 0.0, 1.0)] },
+                            // Players in world_state may move at any speed, and bullets may have been slowed down
+                            Message::world_state(1,
+                                                 vec![Player::moving(0, 1.0, 2.0, 0.3, 0.0)],
+                                                 vec![Bullet::moving(0, 1.0, 2.0, 0.5, 0.0), Bullet::not_moving(1, 1.0, 2.0)]),
+                            Message::StartMoving {
+                                move_x: 3.0,
+                                move_y: 0.0,
+                            }];
+
+        for message in messages {
+            assert!(message.check_normalised().is_ok(), "{:?}", message);
+        }
+    }
+
+    #[test]
+    fn denormalised_vectors_fail() {
+        let messages = vec![Message::ShotsFired {
+                                id: 0,
+                                bullet_id: 0,
+                                x: 1.0,
+                                y: 2.0,
+                                aim_x: 0.5,
+                                aim_y: 0.0,
+                                tick: 0,
+                            },
+                            Message::PlayersMoving {
+                                updates: vec![Player::moving(0, 1.0, 2.0, 0.0, 1.0), Player::moving(1, 1.0, 2.0, 1.0, 1.0)],
+                            },
+                            Message::world_state(0, vec![], vec![Bullet::moving(0, 1.0, 2.0, 1.5, 0.0)])];
+
+        for message in messages {
+            match message.check_normalised() {
+                Err(MessageError::InvalidValue(_)) => {}
+                other => panic!("Expected InvalidValue for {:?}, got {:?}", message, other),
             }
         }
     }
@@ -676,7 +2032,8 @@ pub fn welcome_expected_json(id: u32,
                              speed: f32,
                              size: f32,
                              bullet_speed: f32,
-                             bullet_size: f32)
+                             bullet_size: f32,
+                             features: Vec<String>)
                              -> Value {
     Value::Object(BTreeMap::from_iter(vec![
         ("type".to_string(), Value::String("welcome".to_string())),
@@ -687,6 +2044,7 @@ pub fn welcome_expected_json(id: u32,
                 ("size".to_string(), Value::F64(size as f64)),
                 ("bullet_speed".to_string(), Value::F64(bullet_speed as f64)),
                 ("bullet_size".to_string(), Value::F64(bullet_size as f64)),
+                ("features".to_string(), Value::Array(features.into_iter().map(Value::String).collect())),
             ]
         ))),
     ]))
@@ -716,7 +2074,8 @@ pub fn shots_fired_expected_json(id: u32,
                                  x: f32,
                                  y: f32,
                                  aim_x: f32,
-                                 aim_y: f32)
+                                 aim_y: f32,
+                                 tick: u64)
                                  -> Value {
     Value::Object(BTreeMap::from_iter(vec![
         ("type".to_string(), Value::String("shots_fired".to_string())),
@@ -728,6 +2087,7 @@ pub fn shots_fired_expected_json(id: u32,
                 ("y".to_string(), Value::F64(y as f64)),
                 ("aim_x".to_string(), Value::F64(aim_x as f64)),
                 ("aim_y".to_string(), Value::F64(aim_y as f64)),
+                ("tick".to_string(), Value::U64(tick)),
             ]
         ))),
     ]))
@@ -765,8 +2125,16 @@ pub fn player_destroyed_with_killer_expected_json(id: u32,
 }
 
 pub fn player_moving_expected_json(id: u32, x: f32, y: f32, move_x: f32, move_y: f32) -> Value {
+    id_pos_moves_expected_json(id, x, y, move_x, move_y, "player_moving")
+}
+
+pub fn bullet_moving_expected_json(id: u32, x: f32, y: f32, move_x: f32, move_y: f32) -> Value {
+    id_pos_moves_expected_json(id, x, y, move_x, move_y, "bullet_moving")
+}
+
+fn id_pos_moves_expected_json(id: u32, x: f32, y: f32, move_x: f32, move_y: f32, msg_type: &str) -> Value {
     Value::Object(BTreeMap::from_iter(vec![
-        ("type".to_string(), Value::String("player_moving".to_string())),
+        ("type".to_string(), Value::String(msg_type.to_string())),
         ("data".to_string(), Value::Object(
             BTreeMap::from_iter(vec![
                 ("id".to_string(), Value::U64(id as u64)),
@@ -799,6 +2167,21 @@ pub fn world_state_expected_json(player_count: u32,
     ]))
 }
 
+pub fn players_moving_expected_json(updates: &Vec<Player>) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("players_moving".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("updates".to_string(), Value::Array(updates.iter().map(Player::to_json).collect::<Vec<_>>())),
+            ]
+        ))),
+    ]))
+}
+
+pub fn player_teleported_expected_json(id: u32, x: f32, y: f32) -> Value {
+    id_pos_expected_json(id, x, y, "player_teleported")
+}
+
 pub fn start_moving_expected_json(move_x: f32, move_y: f32) -> Value {
     movement_expected_json(move_x, move_y, "start_moving")
 }
@@ -813,6 +2196,280 @@ pub fn fire_expected_json(move_x: f32, move_y: f32) -> Value {
     movement_expected_json(move_x, move_y, "fire")
 }
 
+pub fn request_respawn_expected_json() -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("request_respawn".to_string())),
+    ]))
+}
+
+pub fn ready_expected_json() -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("ready".to_string())),
+    ]))
+}
+
+pub fn request_stats_expected_json() -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("request_stats".to_string())),
+    ]))
+}
+
+pub fn shot_blocked_expected_json() -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("shot_blocked".to_string())),
+    ]))
+}
+
+pub fn request_player_stats_expected_json(id: u32) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("request_player_stats".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("id".to_string(), Value::U64(id as u64)),
+            ]
+        ))),
+    ]))
+}
+
+pub fn player_stats_expected_json(id: u32, kills: u32, deaths: u32, health: f32) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("player_stats".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("id".to_string(), Value::U64(id as u64)),
+                ("kills".to_string(), Value::U64(kills as u64)),
+                ("deaths".to_string(), Value::U64(deaths as u64)),
+                ("health".to_string(), Value::F64(health as f64)),
+            ]
+        ))),
+    ]))
+}
+
+pub fn input_ack_expected_json(seq: u64) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("input_ack".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("seq".to_string(), Value::U64(seq)),
+            ]
+        ))),
+    ]))
+}
+
+pub fn kill_streak_expected_json(id: u32, streak: u32) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("kill_streak".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("id".to_string(), Value::U64(id as u64)),
+                ("streak".to_string(), Value::U64(streak as u64)),
+            ]
+        ))),
+    ]))
+}
+
+pub fn stats_expected_json(per_type: &Vec<(String, u64, u64)>) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("stats".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("per_type".to_string(), Value::Array(per_type.iter().map(|&(ref msg_type, count, bytes)| {
+                    Value::Object(BTreeMap::from_iter(vec![
+                        ("type".to_string(), Value::String(msg_type.clone())),
+                        ("count".to_string(), Value::U64(count)),
+                        ("bytes".to_string(), Value::U64(bytes)),
+                    ]))
+                }).collect::<Vec<_>>())),
+            ]
+        ))),
+    ]))
+}
+
+pub fn emote_expected_json(id: Option<u32>, emote: u64) -> Value {
+    let mut data = BTreeMap::from_iter(vec![
+        ("emote".to_string(), Value::U64(emote)),
+    ]);
+    if let Some(id) = id {
+        let _ = data.insert("id".to_string(), Value::U64(id as u64));
+    }
+
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("emote".to_string())),
+        ("data".to_string(), Value::Object(data)),
+    ]))
+}
+
+pub fn nack_expected_json(reason: String) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("nack".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("reason".to_string(), Value::String(reason)),
+            ]
+        ))),
+    ]))
+}
+
+pub fn set_paused_expected_json(paused: bool, password: &str) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("set_paused".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("paused".to_string(), Value::Bool(paused)),
+                ("password".to_string(), Value::String(password.to_string())),
+            ]
+        ))),
+    ]))
+}
+
+pub fn game_paused_expected_json(paused: bool) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("game_paused".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("paused".to_string(), Value::Bool(paused)),
+            ]
+        ))),
+    ]))
+}
+
+pub fn server_shutdown_expected_json(seconds_remaining: u32) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("server_shutdown".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("seconds_remaining".to_string(), Value::U64(seconds_remaining as u64)),
+            ]
+        ))),
+    ]))
+}
+
+pub fn player_facing_expected_json(id: u32, facing_x: f32, facing_y: f32) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("player_facing".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("id".to_string(), Value::U64(id as u64)),
+                ("facing_x".to_string(), Value::F64(facing_x as f64)),
+                ("facing_y".to_string(), Value::F64(facing_y as f64)),
+            ]
+        ))),
+    ]))
+}
+
+pub fn player_appearance_expected_json(id: u32, color: u32) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("player_appearance".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("id".to_string(), Value::U64(id as u64)),
+                ("color".to_string(), Value::U64(color as u64)),
+            ]
+        ))),
+    ]))
+}
+
+pub fn set_appearance_expected_json(color: u32) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("set_appearance".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("color".to_string(), Value::U64(color as u64)),
+            ]
+        ))),
+    ]))
+}
+
+pub fn position_report_expected_json(x: f32, y: f32) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("position_report".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("x".to_string(), Value::F64(x as f64)),
+                ("y".to_string(), Value::F64(y as f64)),
+            ]
+        ))),
+    ]))
+}
+
+pub fn player_count_expected_json(count: u32) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("player_count".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("count".to_string(), Value::U64(count as u64)),
+            ]
+        ))),
+    ]))
+}
+
+pub fn fire_cooldown_expected_json(ready_in_ticks: u32) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("fire_cooldown".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("ready_in_ticks".to_string(), Value::U64(ready_in_ticks as u64)),
+            ]
+        ))),
+    ]))
+}
+
+pub fn round_start_expected_json(round: u32, duration_ticks: u32) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("round_start".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("round".to_string(), Value::U64(round as u64)),
+                ("duration_ticks".to_string(), Value::U64(duration_ticks as u64)),
+            ]
+        ))),
+    ]))
+}
+
+pub fn round_end_expected_json(round: u32, scores: &[Score]) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("round_end".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("round".to_string(), Value::U64(round as u64)),
+                ("scores".to_string(), Value::Array(scores.iter().map(|score| Value::Object(
+                    BTreeMap::from_iter(vec![
+                        ("id".to_string(), Value::U64(score.id as u64)),
+                        ("kills".to_string(), Value::U64(score.kills as u64)),
+                    ]
+                ))).collect())),
+            ]
+        ))),
+    ]))
+}
+
+pub fn bullet_destroyed_expected_json(id: u32) -> Value {
+    id_only_expected_json(id, "bullet_destroyed")
+}
+
+pub fn time_sync_request_expected_json(client_time: f64) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("time_sync_request".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("client_time".to_string(), Value::F64(client_time)),
+            ]
+        ))),
+    ]))
+}
+
+pub fn time_sync_response_expected_json(client_time: f64, server_time: f64) -> Value {
+    Value::Object(BTreeMap::from_iter(vec![
+        ("type".to_string(), Value::String("time_sync_response".to_string())),
+        ("data".to_string(), Value::Object(
+            BTreeMap::from_iter(vec![
+                ("client_time".to_string(), Value::F64(client_time)),
+                ("server_time".to_string(), Value::F64(server_time)),
+            ]
+        ))),
+    ]))
+}
+
 fn id_only_expected_json(id: u32, msg_type: &str) -> Value {
     Value::Object(BTreeMap::from_iter(vec![
         ("type".to_string(), Value::String(msg_type.to_string())),
@@ -880,3 +2537,11 @@ pub fn gen_f32<R: Rng>(rng: &mut R) -> f32 {
     // Randoming actual floats hits us when widening them to f64
     (rng.gen_range(0u32, 99u32) as f32) + 0.5f32
 }
+
+pub fn gen_color<R: Rng>(rng: &mut R) -> u32 {
+    rng.gen_range(0u32, 0x1000000u32)
+}
+
+pub fn gen_f64<R: Rng>(rng: &mut R) -> f64 {
+    (rng.gen_range(0u32, 99999u32) as f64) + 0.5f64
+}
@@ -1,35 +1,408 @@
-use clap::{App, Arg, AppSettings};
+use clap::{App, Arg, ArgMatches, AppSettings, ErrorKind};
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::str::FromStr;
+
+/// What happens to entities reaching the edge of the map
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoundaryMode {
+    /// Players stop at the wall, bullets are destroyed
+    Clamp,
+    /// Players and bullets reappear at the opposite edge
+    Wrap,
+    /// Players stop at the wall, bullets bounce off it
+    Bounce,
+}
+
+impl FromStr for BoundaryMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<BoundaryMode, String> {
+        match s {
+            "clamp" => Ok(BoundaryMode::Clamp),
+            "wrap" => Ok(BoundaryMode::Wrap),
+            "bounce" => Ok(BoundaryMode::Bounce),
+            _ => Err(format!("{:?} is not one of clamp, wrap or bounce", s)),
+        }
+    }
+}
+
+/// What happens to players running into a wall of the map
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WallResponse {
+    /// Players stop entirely
+    Stop,
+    /// Players keep moving along the wall, only losing the blocked part of their movement
+    Slide,
+}
+
+impl FromStr for WallResponse {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<WallResponse, String> {
+        match s {
+            "stop" => Ok(WallResponse::Stop),
+            "slide" => Ok(WallResponse::Slide),
+            _ => Err(format!("{:?} is not one of stop or slide", s)),
+        }
+    }
+}
+
+/// Shape of players' hitboxes, sized by `player_size`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CollisionShape {
+    /// Circles with a radius of `player_size`
+    Circle,
+    /// Axis-aligned squares with sides of twice `player_size`
+    Square,
+}
+
+impl FromStr for CollisionShape {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<CollisionShape, String> {
+        match s {
+            "circle" => Ok(CollisionShape::Circle),
+            "square" => Ok(CollisionShape::Square),
+            _ => Err(format!("{:?} is not one of circle or square", s)),
+        }
+    }
+}
+
+/// What happens when a player fires while the bullet cap is reached
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BulletCapPolicy {
+    /// The oldest bullet is destroyed to make room for the new one
+    EvictOldest,
+    /// The new bullet isn't fired
+    Reject,
+}
+
+impl FromStr for BulletCapPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<BulletCapPolicy, String> {
+        match s {
+            "evict" => Ok(BulletCapPolicy::EvictOldest),
+            "reject" => Ok(BulletCapPolicy::Reject),
+            _ => Err(format!("{:?} is not one of evict or reject", s)),
+        }
+    }
+}
 
 /// Representation of the application's all configurable values
-#[derive(Debug, Clone, Hash)]
+#[derive(Debug, Clone)]
 pub struct Options {
     /// Host to connect to. Default: `"localhost"`
     pub host: String,
     /// Port on the host to connect to. Default: `8080`
     pub port: u16,
+    /// Radius of players, used for collisions and advertised to clients. Default: `10`
+    pub player_size: f32,
+    /// Radius of bullets, used for collisions and advertised to clients. Default: `5`
+    pub bullet_size: f32,
+    /// Shape of players' hitboxes. Default: `CollisionShape::Circle`
+    pub collision_shape: CollisionShape,
+    /// Maximum distance a player can move per tick, movement exceeding it gets clamped and corrected. Default: `None`
+    pub max_move_distance: Option<f32>,
+    /// Fraction of velocity bullets lose every tick. Default: `None`
+    pub bullet_drag: Option<f32>,
+    /// Multiplier of the distance everything moves per tick, below 1 for slow motion. Default: `1`
+    pub time_scale: f32,
+    /// Fraction of damage bullets lose per unit of distance travelled. Default: `None`
+    pub damage_falloff: Option<f32>,
+    /// Distance from their player within which clients are sent other entities in full updates. Default: `None`, i.e. any
+    pub view_radius: Option<f32>,
+    /// What happens to entities reaching the edge of the map. Default: `BoundaryMode::Clamp`
+    pub boundary_mode: BoundaryMode,
+    /// What happens to players running into a wall, unless they wrap around. Default: `WallResponse::Stop`
+    pub wall_response: WallResponse,
+    /// Maximum amount of bullets alive at once. Default: `None`
+    pub max_bullets: Option<u32>,
+    /// What happens when a player fires while `max_bullets` are alive. Default: `BulletCapPolicy::EvictOldest`
+    pub bullet_cap_policy: BulletCapPolicy,
+    /// Whether to not send clients the movement updates caused by their own input, which they predict locally. Default: `false`
+    pub suppress_movement_echo: bool,
+    /// Amount of improbable inputs after which a client is kicked. Default: `None`
+    pub kick_threshold: Option<u32>,
+    /// Minimum distance between a spawning player and other players. Default: `None`, i.e. twice the player size, so just not overlapping
+    pub spawn_clearance: Option<f32>,
+    /// Distance around a spawning player within which bullets are destroyed, destroyed players' bullets being destroyed too.
+    /// Default: `None`, i.e. bullets are left alone
+    pub respawn_bullet_clearance: Option<f32>,
+    /// Ticks players take to accelerate to full speed or slow down to a stop. Default: `None`, i.e. instantly
+    pub acceleration_ticks: Option<u32>,
+    /// Minimum amount of ticks between a player's shots. Default: `None`, i.e. unlimited fire rate
+    pub fire_cooldown: Option<u32>,
+    /// Whether to apply only the latest movement each client requested between ticks, once per tick. Default: `false`
+    pub merge_movement: bool,
+    /// Whether to spawn players only once their clients send **ready**. Default: `false`, i.e. on connection
+    pub require_ready: bool,
+    /// Whether to accept positions clients report for their players, if they could have moved there. Default: `false`
+    pub client_positions: bool,
+    /// Whether to count the messages sent by type and size, and let clients request the counts. Default: `false`
+    pub debug_stats: bool,
+    /// Maximum amount of every client's inputs processed per tick, the rest being queued. Default: `None`, i.e. all of them
+    pub max_inputs_per_tick: Option<u32>,
+    /// Whether to periodically send everyone the whole world state, besides sending it to joining clients. Default: `true`
+    pub full_updates: bool,
+    /// Whether bullets hit players, otherwise they fly through them and nobody ever dies. Default: `true`
+    pub combat: bool,
+    /// Whether joining clients wait for the next full update instead of being sent the whole world state at once. Default: `false`
+    pub defer_join_state: bool,
+    /// Minimum and maximum ticks between full updates, which are sent less often the more clients, players and bullets there are.
+    /// Default: `None`, i.e. every 600 ticks
+    pub adaptive_full_updates: Option<(u32, u32)>,
+    /// Amount of players above which joins and leaves aren't broadcast, leaving membership to full updates. Default: `None`
+    pub quiet_membership_above: Option<u32>,
+    /// Ticks every round lasts, after which scores are reset and everyone is respawned. Default: `None`, i.e. one endless game
+    pub round_length: Option<u32>,
+    /// Ticks within which a player's kills count towards the same streak, streaks of 3, 5 and 10 being broadcast.
+    /// Default: `None`, i.e. streaks aren't announced
+    pub kill_streak_window: Option<u32>,
+    /// Maximum amount of ticks players are rewound by, according to the shooter's latency, when checking whether bullets hit them. Default: `None`
+    pub lag_compensation: Option<u32>,
+    /// Maximum amount of rooms running at once, each with its own game; with only one, every path joins the default room. Default: `1`
+    pub max_rooms: u32,
+    /// Decimal places floats in messages are rounded to. Default: `None`, i.e. full precision
+    pub float_precision: Option<u32>,
+    /// Reason sent to clients when the server shuts down. Default: `"Server termination"`
+    pub shutdown_reason: String,
+    /// Amount of seconds the game goes on for after the server starts shutting down, so clients can count down. Default: `None`
+    pub shutdown_grace_period: Option<u32>,
+    /// Milliseconds a client can stay silent before it's considered disconnected. Default: `None`
+    pub client_timeout: Option<u64>,
+    /// Origins browser clients may connect from. Default: `None`, i.e. any
+    pub allowed_origins: Option<Vec<String>>,
+    /// Maximum amount of elements in any array in a message sent by a client. Default: `1024`
+    pub max_array_length: u32,
+    /// Maximum amount of connections in the middle of their handshake at once. Default: `None`
+    pub max_handshakes: Option<u32>,
+    /// Port on the host to stream the game to read-only spectators on, if any. Default: `None`
+    pub observer_port: Option<u16>,
+    /// Whether to report vectors sent to clients which the protocol requires to be normalised, but aren't. Default: `false`
+    pub strict: bool,
+    /// Password clients can send along with **set_paused** to pause and resume their room's game. Default: `None`, i.e. nobody can
+    pub admin_password: Option<String>,
+    /// Whether to only validate the configuration and exit without listening. Default: `false`
+    pub check: bool,
 }
 
 impl Options {
     /// Parse `env`-wide command-line arguments into an `Options` instance
-    pub fn parse() -> Options {
+    ///
+    /// Exits after printing the help or version, if they were asked for.
+    pub fn try_parse() -> Result<Options, String> {
+        Options::try_parse_from(env::args_os())
+    }
+
+    /// Parse the specified command-line arguments, starting with the program name, into an `Options` instance
+    ///
+    /// Exits after printing the help or version, if they were asked for.
+    pub fn try_parse_from<I, T>(args: I) -> Result<Options, String>
+        where I: IntoIterator<Item = T>,
+              T: Into<OsString>
+    {
+        let matches = match Options::app().get_matches_from_safe(args) {
+            Ok(matches) => matches,
+            Err(error) => {
+                if error.kind == ErrorKind::HelpDisplayed || error.kind == ErrorKind::VersionDisplayed {
+                    error.exit();
+                }
+                return Err(error.message);
+            }
+        };
+        Options::from_matches(&matches)
+    }
+
+    fn app() -> App<'static, 'static> {
         static USAGE: &'static str = "[host] 'Host to connect to. Default: localhost'";
 
-        let matches = App::new("tatsoryk-server")
-                          .setting(AppSettings::ColoredHelp)
-                          .version(env!("CARGO_PKG_VERSION"))
-                          .author("nabijaczleweli <nabijaczleweli@gmail.com>,\n\
-                                   Cat Plus Plus <piotrlegnica@piotrl.pl>,\n\
-                                   Lalaland <ethan.steinberg@gmail.com>")
-                          .about("Implementation of the server for Tatsoryk")
-                          .args_from_usage(USAGE)
-                          .arg(Arg::from_usage("[port] 'Port on the host to connect to. \
-                                                  Default: 8080'")
-                                   .validator(Options::verify_u16))
-                          .get_matches();
+        App::new("tatsoryk-server")
+            .setting(AppSettings::ColoredHelp)
+            .version(env!("CARGO_PKG_VERSION"))
+            .author("nabijaczleweli <nabijaczleweli@gmail.com>,\n\
+                     Cat Plus Plus <piotrlegnica@piotrl.pl>,\n\
+                     Lalaland <ethan.steinberg@gmail.com>")
+            .about("Implementation of the server for Tatsoryk")
+            .args_from_usage(USAGE)
+            .arg(Arg::from_usage("[port] 'Port on the host to connect to. \
+                                    Default: 8080'")
+                     .validator(Options::verify_u16))
+            .arg(Arg::from_usage("--player-size=[RADIUS] 'Radius of players. \
+                                    Default: 10'")
+                     .validator(Options::verify_positive_f32))
+            .arg(Arg::from_usage("--bullet-size=[RADIUS] 'Radius of bullets. \
+                                    Default: 5'")
+                     .validator(Options::verify_positive_f32))
+            .arg(Arg::from_usage("--collision-shape=[SHAPE] 'Shape of player hitboxes, squares span the player size either way. \
+                                    Default: circle'")
+                     .possible_values(&["circle", "square"]))
+            .arg(Arg::from_usage("--max-move-distance=[DISTANCE] 'Maximum distance a player can move per tick. \
+                                    Default: unlimited'")
+                     .validator(Options::verify_positive_f32))
+            .arg(Arg::from_usage("--time-scale=[FACTOR] 'Multiplier of the distance everything moves per tick, \
+                                    below 1 for slow motion, above for fast forward. Default: 1'")
+                     .validator(Options::verify_positive_f32))
+            .arg(Arg::from_usage("--bullet-drag=[FRACTION] 'Fraction of velocity bullets lose every tick. \
+                                    Default: none'")
+                     .validator(Options::verify_fraction))
+            .arg(Arg::from_usage("--damage-falloff=[FRACTION] 'Fraction of damage bullets lose per unit of distance travelled. \
+                                    Default: none'")
+                     .validator(Options::verify_fraction))
+            .arg(Arg::from_usage("--view-radius=[DISTANCE] 'Distance from their player within which clients are sent \
+                                    other entities in full updates. Default: unlimited'")
+                     .validator(Options::verify_positive_f32))
+            .arg(Arg::from_usage("--boundary=[MODE] 'What happens to entities reaching the map edge. \
+                                    Default: clamp'")
+                     .possible_values(&["clamp", "wrap", "bounce"]))
+            .arg(Arg::from_usage("--wall-response=[RESPONSE] 'What happens to players running into the map edge. \
+                                    Default: stop'")
+                     .possible_values(&["stop", "slide"]))
+            .arg(Arg::from_usage("--max-bullets=[COUNT] 'Maximum amount of bullets alive at once. \
+                                    Default: unlimited'")
+                     .validator(Options::verify_positive_u32))
+            .arg(Arg::from_usage("--bullet-cap-policy=[POLICY] 'What happens when firing past the bullet cap. \
+                                    Default: evict'")
+                     .possible_values(&["evict", "reject"]))
+            .arg(Arg::from_usage("--suppress-movement-echo 'Skip sending clients movement updates caused by their own input'"))
+            .arg(Arg::from_usage("--kick-threshold=[COUNT] 'Amount of improbable inputs after which a client is kicked. \
+                                    Default: never'")
+                     .validator(Options::verify_positive_u32))
+            .arg(Arg::from_usage("--spawn-clearance=[DISTANCE] 'Minimum distance between a spawning player and other players. \
+                                    Default: twice the player size'")
+                     .validator(Options::verify_positive_f32))
+            .arg(Arg::from_usage("--respawn-bullet-clearance=[DISTANCE] 'Distance around a spawning player within which bullets are destroyed, \
+                                    along with all bullets of destroyed players. Default: bullets are left alone'")
+                     .validator(Options::verify_positive_f32))
+            .arg(Arg::from_usage("--acceleration-ticks=[TICKS] 'Ticks players take to reach full speed or stop. \
+                                    Default: instant'")
+                     .validator(Options::verify_positive_u32))
+            .arg(Arg::from_usage("--fire-cooldown=[TICKS] 'Minimum ticks between shots of a player. \
+                                    Default: unlimited'")
+                     .validator(Options::verify_positive_u32))
+            .arg(Arg::from_usage("--merge-movement 'Apply only the latest movement each client requested between ticks'"))
+            .arg(Arg::from_usage("--require-ready 'Spawn players only once their clients say they are ready'"))
+            .arg(Arg::from_usage("--client-positions 'Accept positions reported by clients, if they could have moved there'"))
+            .arg(Arg::from_usage("--debug-stats 'Count messages sent by type and size, and let clients request the counts'"))
+            .arg(Arg::from_usage("--max-inputs-per-tick=[COUNT] 'Maximum inputs processed per client per tick, \
+                                    the rest are queued, dropping the oldest if too many. Default: all'")
+                     .validator(Options::verify_positive_u32))
+            .arg(Arg::from_usage("--no-full-updates 'Send the whole world state only to joining clients, never periodically'"))
+            .arg(Arg::from_usage("--no-combat 'Let bullets fly through players, so that they can practice moving around without dying'"))
+            .arg(Arg::from_usage("--defer-join-state 'Let joining clients wait for the next full update instead of sending them the world state at once'"))
+            .arg(Arg::from_usage("--adaptive-full-updates=[RANGE] 'Send full updates less often the more clients, players and bullets \
+                                    there are, every MIN,MAX ticks at the most and least. Default: every 600 ticks'")
+                     .validator(Options::verify_tick_range))
+            .arg(Arg::from_usage("--quiet-membership-above=[PLAYERS] 'Stop broadcasting joins and leaves while more players \
+                                    than this are connected, full updates keep clients up to date. Default: never'")
+                     .validator(Options::verify_positive_u32))
+            .arg(Arg::from_usage("--round-length=[TICKS] 'Ticks every round lasts, scores are reset and everyone respawns between rounds. \
+                                    Default: no rounds'")
+                     .validator(Options::verify_positive_u32))
+            .arg(Arg::from_usage("--kill-streak-window=[TICKS] 'Ticks within which kills count towards the same streak, \
+                                    notable streaks are announced. Default: no streaks'")
+                     .validator(Options::verify_positive_u32))
+            .arg(Arg::from_usage("--lag-compensation=[TICKS] 'Check hits against where players were as many ticks ago as the shooter lags, \
+                                    up to this many. Default: no compensation'")
+                     .validator(Options::verify_positive_u32))
+            .arg(Arg::from_usage("--max-rooms=[COUNT] 'Maximum amount of rooms running at once, clients join the room named by their URL path. \
+                                    Default: 1'")
+                     .validator(Options::verify_positive_u32))
+            .arg(Arg::from_usage("--float-precision=[DECIMALS] 'Decimal places floats in messages are rounded to. \
+                                    Default: full precision'")
+                     .validator(Options::verify_precision))
+            .arg(Arg::from_usage("--shutdown-reason=[REASON] 'Reason sent to clients when the server shuts down. \
+                                    Default: Server termination'"))
+            .arg(Arg::from_usage("--shutdown-grace-period=[SECONDS] 'Seconds the game goes on for after clients are warned \
+                                    about the server shutting down. Default: 0'")
+                     .validator(Options::verify_positive_u32))
+            .arg(Arg::from_usage("--client-timeout=[MS] 'Milliseconds a client can stay silent before being disconnected. \
+                                    Default: never'")
+                     .validator(Options::verify_positive_u64))
+            .arg(Arg::from_usage("--allowed-origins=[ORIGINS] 'Comma-separated origins browser clients may connect from, \
+                                    e.g. https://example.com. Default: any'"))
+            .arg(Arg::from_usage("--max-array-length=[LENGTH] 'Maximum amount of elements in any array in a client message. \
+                                    Default: 1024'")
+                     .validator(Options::verify_positive_u32))
+            .arg(Arg::from_usage("--max-handshakes=[COUNT] 'Maximum amount of connections mid-handshake at once, \
+                                    more are closed immediately. Default: unlimited'")
+                     .validator(Options::verify_positive_u32))
+            .arg(Arg::from_usage("--observer-port=[PORT] 'Port on the host to stream the game to read-only spectators on, \
+                                    they never become players. Default: none'")
+                     .validator(Options::verify_u16))
+            .arg(Arg::from_usage("--strict 'Report vectors sent to clients which should be normalised, but are not'"))
+            .arg(Arg::from_usage("--admin-password=[PASSWORD] 'Password clients can pause and resume the game in their room with. \
+                                    Default: nobody can'"))
+            .arg(Arg::from_usage("--check 'Validate the configuration and exit without listening'"))
+    }
 
-        Options {
+    /// Convert arguments already checked by the validators into an `Options` instance
+    ///
+    /// Values which still don't parse mean a flag is missing its validator, and are reported rather than panicked on.
+    fn from_matches(matches: &ArgMatches) -> Result<Options, String> {
+        Ok(Options {
             host: matches.value_of("host").unwrap_or("127.0.0.1").to_string(),
-            port: matches.value_of("port").unwrap_or("8080").parse::<u16>().unwrap(), /* Verified earlier */
+            port: try!(Options::parsed_value(matches, "port")).unwrap_or(8080),
+            player_size: try!(Options::parsed_value(matches, "player-size")).unwrap_or(10.0),
+            bullet_size: try!(Options::parsed_value(matches, "bullet-size")).unwrap_or(5.0),
+            collision_shape: try!(Options::parsed_value(matches, "collision-shape")).unwrap_or(CollisionShape::Circle),
+            max_move_distance: try!(Options::parsed_value(matches, "max-move-distance")),
+            bullet_drag: try!(Options::parsed_value(matches, "bullet-drag")),
+            time_scale: try!(Options::parsed_value(matches, "time-scale")).unwrap_or(1.0),
+            damage_falloff: try!(Options::parsed_value(matches, "damage-falloff")),
+            view_radius: try!(Options::parsed_value(matches, "view-radius")),
+            boundary_mode: try!(Options::parsed_value(matches, "boundary")).unwrap_or(BoundaryMode::Clamp),
+            wall_response: try!(Options::parsed_value(matches, "wall-response")).unwrap_or(WallResponse::Stop),
+            max_bullets: try!(Options::parsed_value(matches, "max-bullets")),
+            bullet_cap_policy: try!(Options::parsed_value(matches, "bullet-cap-policy")).unwrap_or(BulletCapPolicy::EvictOldest),
+            suppress_movement_echo: matches.is_present("suppress-movement-echo"),
+            kick_threshold: try!(Options::parsed_value(matches, "kick-threshold")),
+            spawn_clearance: try!(Options::parsed_value(matches, "spawn-clearance")),
+            respawn_bullet_clearance: try!(Options::parsed_value(matches, "respawn-bullet-clearance")),
+            acceleration_ticks: try!(Options::parsed_value(matches, "acceleration-ticks")),
+            fire_cooldown: try!(Options::parsed_value(matches, "fire-cooldown")),
+            merge_movement: matches.is_present("merge-movement"),
+            require_ready: matches.is_present("require-ready"),
+            client_positions: matches.is_present("client-positions"),
+            debug_stats: matches.is_present("debug-stats"),
+            max_inputs_per_tick: try!(Options::parsed_value(matches, "max-inputs-per-tick")),
+            full_updates: !matches.is_present("no-full-updates"),
+            combat: !matches.is_present("no-combat"),
+            defer_join_state: matches.is_present("defer-join-state"),
+            adaptive_full_updates: match matches.value_of("adaptive-full-updates") {
+                Some(range) => Some(try!(Options::parse_tick_range(range))),
+                None => None,
+            },
+            quiet_membership_above: try!(Options::parsed_value(matches, "quiet-membership-above")),
+            round_length: try!(Options::parsed_value(matches, "round-length")),
+            kill_streak_window: try!(Options::parsed_value(matches, "kill-streak-window")),
+            lag_compensation: try!(Options::parsed_value(matches, "lag-compensation")),
+            max_rooms: try!(Options::parsed_value(matches, "max-rooms")).unwrap_or(1),
+            float_precision: try!(Options::parsed_value(matches, "float-precision")),
+            shutdown_reason: matches.value_of("shutdown-reason").unwrap_or("Server termination").to_string(),
+            shutdown_grace_period: try!(Options::parsed_value(matches, "shutdown-grace-period")),
+            client_timeout: try!(Options::parsed_value(matches, "client-timeout")),
+            allowed_origins: matches.value_of("allowed-origins")
+                                    .map(|o| o.split(',').map(|origin| origin.trim().to_string()).collect()),
+            max_array_length: try!(Options::parsed_value(matches, "max-array-length")).unwrap_or(1024),
+            max_handshakes: try!(Options::parsed_value(matches, "max-handshakes")),
+            observer_port: try!(Options::parsed_value(matches, "observer-port")),
+            strict: matches.is_present("strict"),
+            admin_password: matches.value_of("admin-password").map(str::to_string),
+            check: matches.is_present("check"),
+        })
+    }
+
+    /// Parse the value of the specified flag, if it was specified.
+    fn parsed_value<T>(matches: &ArgMatches, name: &str) -> Result<Option<T>, String>
+        where T: FromStr,
+              T::Err: fmt::Display
+    {
+        match matches.value_of(name) {
+            Some(value) => value.parse().map(Some).map_err(|err| format!("Invalid value {:?} for {}: {}", value, name, err)),
+            None => Ok(None),
         }
     }
 
@@ -39,4 +412,201 @@ impl Options {
             Err(err) => Err(format!("{:?} is not a 16-bit unsigned integer: {}", arg, err)),
         }
     }
+
+    fn verify_positive_u32(arg: String) -> Result<(), String> {
+        match arg[..].parse::<u32>() {
+            Ok(0) => Err(format!("{:?} is not positive", arg)),
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("{:?} is not a 32-bit unsigned integer: {}", arg, err)),
+        }
+    }
+
+    fn verify_positive_u64(arg: String) -> Result<(), String> {
+        match arg[..].parse::<u64>() {
+            Ok(0) => Err(format!("{:?} is not positive", arg)),
+            Ok(_) => Ok(()),
+            Err(err) => Err(format!("{:?} is not a 64-bit unsigned integer: {}", arg, err)),
+        }
+    }
+
+    fn verify_tick_range(arg: String) -> Result<(), String> {
+        Options::parse_tick_range(&arg).map(|_| ())
+    }
+
+    fn parse_tick_range(arg: &str) -> Result<(u32, u32), String> {
+        let mut bounds = arg.split(',');
+        match (bounds.next(), bounds.next(), bounds.next()) {
+            (Some(min), Some(max), None) => {
+                match (min.trim().parse::<u32>(), max.trim().parse::<u32>()) {
+                    (Ok(min), Ok(max)) if min > 0 && min <= max => Ok((min, max)),
+                    (Ok(min), Ok(max)) => Err(format!("{:?} is not a range of positive tick counts: {} to {}", arg, min, max)),
+                    (Err(err), _) | (_, Err(err)) => Err(format!("{:?} has a bound which is not a 32-bit unsigned integer: {}", arg, err)),
+                }
+            }
+            _ => Err(format!("{:?} is not two comma-separated bounds", arg)),
+        }
+    }
+
+    fn verify_precision(arg: String) -> Result<(), String> {
+        static MAX_PRECISION: u32 = 15; // f64 can't represent more significant decimal digits
+
+        match arg[..].parse::<u32>() {
+            Ok(p) if p <= MAX_PRECISION => Ok(()),
+            Ok(p) => Err(format!("{:?} is more than {} decimal places: {}", arg, MAX_PRECISION, p)),
+            Err(err) => Err(format!("{:?} is not a 32-bit unsigned integer: {}", arg, err)),
+        }
+    }
+
+    fn verify_positive_f32(arg: String) -> Result<(), String> {
+        match arg[..].parse::<f32>() {
+            Ok(f) if f > 0.0 => Ok(()),
+            Ok(f) => Err(format!("{:?} is not positive: {}", arg, f)),
+            Err(err) => Err(format!("{:?} is not a floating-point number: {}", arg, err)),
+        }
+    }
+
+    fn verify_fraction(arg: String) -> Result<(), String> {
+        match arg[..].parse::<f32>() {
+            Ok(f) if f > 0.0 && f < 1.0 => Ok(()),
+            Ok(f) => Err(format!("{:?} is not between 0 and 1: {}", arg, f)),
+            Err(err) => Err(format!("{:?} is not a floating-point number: {}", arg, err)),
+        }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            player_size: 10.0,
+            bullet_size: 5.0,
+            collision_shape: CollisionShape::Circle,
+            max_move_distance: None,
+            bullet_drag: None,
+            time_scale: 1.0,
+            damage_falloff: None,
+            view_radius: None,
+            boundary_mode: BoundaryMode::Clamp,
+            wall_response: WallResponse::Stop,
+            max_bullets: None,
+            bullet_cap_policy: BulletCapPolicy::EvictOldest,
+            suppress_movement_echo: false,
+            kick_threshold: None,
+            spawn_clearance: None,
+            respawn_bullet_clearance: None,
+            acceleration_ticks: None,
+            fire_cooldown: None,
+            merge_movement: false,
+            require_ready: false,
+            client_positions: false,
+            debug_stats: false,
+            max_inputs_per_tick: None,
+            full_updates: true,
+            combat: true,
+            defer_join_state: false,
+            adaptive_full_updates: None,
+            quiet_membership_above: None,
+            round_length: None,
+            kill_streak_window: None,
+            lag_compensation: None,
+            max_rooms: 1,
+            float_precision: None,
+            shutdown_reason: "Server termination".to_string(),
+            shutdown_grace_period: None,
+            client_timeout: None,
+            allowed_origins: None,
+            max_array_length: 1024,
+            max_handshakes: None,
+            observer_port: None,
+            strict: false,
+            admin_password: None,
+            check: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use self::super::{BoundaryMode, Options};
+
+    #[test]
+    fn no_arguments_parse_to_defaults() {
+        let options = Options::try_parse_from(vec!["tatsoryk-server"]).unwrap();
+        let defaults = Options::default();
+
+        assert_eq!(options.host, defaults.host);
+        assert_eq!(options.port, defaults.port);
+        assert_eq!(options.player_size, defaults.player_size);
+        assert_eq!(options.boundary_mode, defaults.boundary_mode);
+        assert_eq!(options.max_rooms, defaults.max_rooms);
+        assert_eq!(options.max_array_length, defaults.max_array_length);
+        assert_eq!(options.adaptive_full_updates, defaults.adaptive_full_updates);
+    }
+
+    #[test]
+    fn valid_arguments_parse() {
+        let options = Options::try_parse_from(vec!["tatsoryk-server",
+                                                   "0.0.0.0",
+                                                   "9000",
+                                                   "--player-size=12.5",
+                                                   "--boundary=wrap",
+                                                   "--fire-cooldown=30",
+                                                   "--adaptive-full-updates=60,1200"])
+                          .unwrap();
+
+        assert_eq!(options.host, "0.0.0.0");
+        assert_eq!(options.port, 9000);
+        assert_eq!(options.player_size, 12.5);
+        assert_eq!(options.boundary_mode, BoundaryMode::Wrap);
+        assert_eq!(options.fire_cooldown, Some(30));
+        assert_eq!(options.adaptive_full_updates, Some((60, 1200)));
+    }
+
+    #[test]
+    fn invalid_port_fails() {
+        assert!(Options::try_parse_from(vec!["tatsoryk-server", "localhost", "65536"]).is_err());
+    }
+
+    #[test]
+    fn invalid_flag_values_fail() {
+        let invalid = [("player-size", "0"),
+                       ("bullet-size", "-1"),
+                       ("collision-shape", "triangle"),
+                       ("max-move-distance", "far"),
+                       ("time-scale", "0"),
+                       ("bullet-drag", "1"),
+                       ("damage-falloff", "0"),
+                       ("view-radius", "-5"),
+                       ("boundary", "teleport"),
+                       ("wall-response", "bounce"),
+                       ("max-bullets", "0"),
+                       ("bullet-cap-policy", "ignore"),
+                       ("kick-threshold", "-1"),
+                       ("spawn-clearance", "x"),
+                       ("respawn-bullet-clearance", "0"),
+                       ("acceleration-ticks", "0"),
+                       ("fire-cooldown", "1.5"),
+                       ("max-inputs-per-tick", "0"),
+                       ("adaptive-full-updates", "10"),
+                       ("adaptive-full-updates", "20,10"),
+                       ("quiet-membership-above", "0"),
+                       ("round-length", "0"),
+                       ("kill-streak-window", "x"),
+                       ("lag-compensation", "0"),
+                       ("max-rooms", "0"),
+                       ("float-precision", "16"),
+                       ("shutdown-grace-period", "0"),
+                       ("client-timeout", "0"),
+                       ("max-array-length", "0"),
+                       ("max-handshakes", "x"),
+                       ("observer-port", "70000")];
+
+        for &(flag, value) in &invalid {
+            let argument = format!("--{}={}", flag, value);
+            assert!(Options::try_parse_from(vec!["tatsoryk-server", &argument[..]]).is_err(),
+                    "{} accepted",
+                    argument);
+        }
+    }
 }
@@ -5,6 +5,7 @@
 //! This file defines the common data formats for those channels.
 
 use std::sync::mpsc::{Sender, SendError};
+use std::sync::Arc;
 use std::fmt;
 
 use message;
@@ -18,12 +19,14 @@ pub struct Client {
     /// Channel which allows you to send messages to the corresponding websocket.
     ///
     /// Send a None to close the websocket. (Some(data) for a normal message).
-    sender: Sender<Option<String>>,
+    ///
+    /// The data is shared, so that a broadcast message is serialised only once regardless of client count.
+    sender: Sender<Option<Arc<String>>>,
 }
 
 impl Client {
     /// Create a new client from a given id and sender channel.
-    pub fn new(id: u32, sender: Sender<Option<String>>) -> Client {
+    pub fn new(id: u32, sender: Sender<Option<Arc<String>>>) -> Client {
         Client {
             id: id,
             sender: sender,
@@ -31,12 +34,17 @@ impl Client {
     }
 
     /// Send a message to the websocket.
-    pub fn send(&self, message: String) -> Result<(), SendError<Option<String>>> {
+    pub fn send(&self, message: String) -> Result<(), SendError<Option<Arc<String>>>> {
+        self.send_shared(Arc::new(message))
+    }
+
+    /// Send a message shared with other clients to the websocket.
+    pub fn send_shared(&self, message: Arc<String>) -> Result<(), SendError<Option<Arc<String>>>> {
         self.sender.send(Some(message))
     }
 
     /// Close the websocket.
-    pub fn close(&self) -> Result<(), SendError<Option<String>>> {
+    pub fn close(&self) -> Result<(), SendError<Option<Arc<String>>>> {
         self.sender.send(None)
     }
 }
@@ -49,7 +57,8 @@ impl fmt::Debug for Client {
 
 /// A WebSocketEvent is any websocket message which might be sent to the main game loop.
 ///
-/// Right now, we have clients connecting, disconnecting, and sending messages.
+/// Right now, we have clients connecting, disconnecting, sending messages, and having their latency measured,
+/// read-only spectators connecting and disconnecting, and the game being paused or resumed or slowed down by an administrator.
 /// This is the place where we would add additional stuff like say, unix signals.
 #[derive(Debug, Clone)]
 pub enum WebSocketEvent {
@@ -59,8 +68,25 @@ pub enum WebSocketEvent {
     ClientClosed {
         client_id: u32,
     },
+    SpectatorCreated {
+        client: Client,
+    },
+    SpectatorClosed {
+        client_id: u32,
+    },
+    SetPaused {
+        paused: bool,
+    },
+    SetTimeScale {
+        time_scale: f32,
+    },
     ClientMessage {
         client_id: u32,
         message: message::Message,
+        seq: Option<u64>,
+    },
+    ClientLatency {
+        client_id: u32,
+        latency_ms: u32,
     },
 }
@@ -2,22 +2,40 @@
 
 use message;
 
-use std::collections::HashMap;
-use std::sync::mpsc;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::cmp;
+use std::fmt;
+use std::mem;
+use std::sync::{mpsc, Arc};
 
-use math::distance_between;
+use math::{aabb_circle_hit, rect_contains, Vec2};
+use options::{BoundaryMode, BulletCapPolicy, CollisionShape, Options, WallResponse};
 use rand::{thread_rng, Rng};
+use time;
 
 use self::super::Client;
+use self::super::GameObserver;
 use self::super::WebSocketEvent;
 
-static BULLET_RADIUS: f32 = 5.0;
-static PLAYER_RADIUS: f32 = 10.0;
 static BULLET_SPEED: f32 = 3.0;
 static PLAYER_SPEED: f32 = 2.0;
 static MAP_HEIGHT: f32 = 500.0;
 static MAP_WIDTH: f32 = 500.0;
-static TICKS_BETWEEN_FULL_UPDATES: u32 = 600; // 10s @ 60FPS
+static TICKS_BETWEEN_FULL_UPDATES: u64 = 600; // 10s @ 60FPS
+static ADAPTIVE_UPDATE_BUDGET: u64 = 2; // Players and bullets per tick sent in adaptive full updates, counted for every recipient
+static RESPAWN_DELAY: u32 = 120; // 2s @ 60FPS
+static MIN_BULLET_VELOCITY: f32 = 0.1; // Relative to BULLET_SPEED, slower bullets are destroyed
+static MAX_BULLET_RANGE: f32 = 1500.0; // Bullets are destroyed after travelling this far, so wrapping and bouncing ones don't pile up
+static MAX_HEALTH: f32 = 100.0;
+static BULLET_DAMAGE: f32 = 100.0;
+static MIN_DAMAGE_FRACTION: f32 = 0.2; // Bullets never deal less than this fraction of BULLET_DAMAGE
+static MIN_VELOCITY_UPDATE: f32 = 0.2; // Accelerating players' and dragged bullets' velocity changes smaller than this aren't broadcast
+static MAX_INPUT_MAGNITUDE: f32 = 1.5; // Input vectors SHOULD be normalised, larger ones are improbable from honest clients
+static MAX_QUEUED_INPUTS: usize = 64; // Per client, the oldest inputs are dropped past this
+static SEPARATION_SPEED: f32 = 1.0; // Overlapping players are pushed apart by at most this much each per tick
+static KILL_STREAK_THRESHOLDS: &'static [u32] = &[3, 5, 10]; // Kills in a row announced, if kill streaks are enabled
+static TICK_LENGTH: f32 = 1000.0 / 60.0; // ms
 
 /// The `GameState` contains the whole state of the game.
 ///
@@ -25,55 +43,335 @@ static TICKS_BETWEEN_FULL_UPDATES: u32 = 600; // 10s @ 60FPS
 #[derive(Debug)]
 pub struct GameState {
     players: HashMap<u32, message::Player>,
+    /// Players waiting to respawn, mapped to the amount of ticks since they died.
+    dead_players: HashMap<u32, u32>,
+    /// Clients which were welcomed, but didn't say they're ready to spawn yet, if that's required.
+    awaiting_ready: HashSet<u32>,
+    /// Remaining health of every alive player.
+    health: HashMap<u32, f32>,
+    /// Latest movement requested by every client since the last tick, `None` meaning stopping.
+    pending_movement: HashMap<u32, Option<Vec2>>,
+    /// Movement every player requested, which it accelerates towards, if acceleration is enabled.
+    movement_inputs: HashMap<u32, Option<Vec2>>,
+    /// Movement last broadcast for every accelerating player.
+    broadcast_velocities: HashMap<u32, Vec2>,
+    /// Amount and total size in bytes of every type of message sent to clients and spectators, if debug stats are enabled.
+    sent_stats: RefCell<BTreeMap<&'static str, (u64, u64)>>,
+    /// Whole world state as last encoded, reused until the state changes.
+    encoded_state: RefCell<Option<Arc<String>>>,
+    /// Colors chosen by clients, packed as `0xRRGGBB`.
+    appearances: HashMap<u32, u32>,
+    /// Amount of improbable inputs every client has sent.
+    violations: HashMap<u32, u32>,
+    /// Tick at which every player last fired, tracked if fire rate is limited.
+    last_fire_tick: HashMap<u32, u64>,
+    /// Tick and position of every player's last accepted position report, or spawn, if client positions are accepted.
+    last_position_reports: HashMap<u32, (u64, Vec2)>,
+    /// Inputs every client sent which weren't processed yet, if inputs are limited per tick.
+    input_queues: HashMap<u32, VecDeque<(message::Message, Option<u64>)>>,
+    /// Highest seq of every client's inputs processed since the last **input_ack** sent to it, if it numbers them.
+    unacknowledged_inputs: HashMap<u32, u64>,
+    /// Amount of players every player destroyed in the round in progress.
+    scores: HashMap<u32, u32>,
+    /// Amount of times every player was destroyed in the round in progress.
+    deaths: HashMap<u32, u32>,
+    /// Amount of players every player destroyed in a row without dying, and the tick of the latest, if kill streaks are enabled.
+    kill_streaks: HashMap<u32, (u32, u64)>,
+    /// Latest measured latency of every client, in ticks.
+    latencies: HashMap<u32, u32>,
+    /// Positions of all players at the end of the latest ticks, most recent first, if lag is compensated for.
+    position_history: VecDeque<HashMap<u32, Vec2>>,
     bullets: HashMap<u32, message::OwnedBullet>,
+    /// Buffers reused by every game update, so that steady ticking doesn't allocate them over and over.
+    force_stopped_buffer: Vec<u32>,
+    hit_players_buffer: Vec<(u32, u32)>,
+    destroyed_bullets_buffer: Vec<u32>,
     clients: HashMap<u32, Client>,
+    /// Read-only clients which receive everything broadcast, but never become players.
+    spectators: HashMap<u32, Client>,
     next_bullet_id: u32,
-    ticks_since_last_full_update: u32,
+    /// Amount of game updates processed so far.
+    tick: u64,
+    /// Tick at which the last full state update was sent.
+    last_full_update_tick: u64,
+    /// Number of the round in progress, if the game is played in rounds.
+    round: u32,
+    /// Tick at which the round in progress started.
+    round_start_tick: u64,
+    /// Value of the monotonic clock when the game started, in nanoseconds.
+    start_time: u64,
+    /// Whether the simulation is paused, in which case game updates are skipped entirely.
+    paused: bool,
+    /// Multiplier of the distance players and bullets move per tick.
+    time_scale: f32,
+    options: Options,
+    observer: Option<Box<GameObserver>>,
 }
 
 impl GameState {
-    /// Create a new game state.
-    pub fn new() -> GameState {
+    /// Create a new game state configured according to the specified options.
+    pub fn new(options: Options) -> GameState {
         GameState {
             players: HashMap::new(),
-            bullets: HashMap::new(),
+            dead_players: HashMap::new(),
+            awaiting_ready: HashSet::new(),
+            health: HashMap::new(),
+            pending_movement: HashMap::new(),
+            movement_inputs: HashMap::new(),
+            broadcast_velocities: HashMap::new(),
+            sent_stats: RefCell::new(BTreeMap::new()),
+            encoded_state: RefCell::new(None),
+            appearances: HashMap::new(),
+            violations: HashMap::new(),
+            last_fire_tick: HashMap::new(),
+            last_position_reports: HashMap::new(),
+            input_queues: HashMap::new(),
+            unacknowledged_inputs: HashMap::new(),
+            scores: HashMap::new(),
+            deaths: HashMap::new(),
+            kill_streaks: HashMap::new(),
+            latencies: HashMap::new(),
+            position_history: VecDeque::new(),
+            bullets: HashMap::with_capacity(options.max_bullets.map_or(0, |max_bullets| max_bullets as usize)),
+            force_stopped_buffer: Vec::new(),
+            hit_players_buffer: Vec::new(),
+            destroyed_bullets_buffer: Vec::new(),
             clients: HashMap::new(),
+            spectators: HashMap::new(),
             next_bullet_id: 0,
-            ticks_since_last_full_update: 0,
+            tick: 0,
+            last_full_update_tick: 0,
+            round: 1,
+            round_start_tick: 0,
+            start_time: time::precise_time_ns(),
+            paused: false,
+            time_scale: options.time_scale,
+            options: options,
+            observer: None,
         }
     }
 
+    /// Check whether the game's configuration is sane.
+    pub fn validate(&self) -> Result<(), String> {
+        let player_radius = self.options.player_size;
+        if player_radius <= 0.0 || self.options.bullet_size <= 0.0 {
+            return Err(format!("Sizes must be positive, player: {}, bullet: {}", player_radius, self.options.bullet_size));
+        }
+        if MAP_WIDTH < 2.0 * player_radius || MAP_HEIGHT < 2.0 * player_radius {
+            return Err(format!("{}x{} map can't fit a player of radius {}", MAP_WIDTH, MAP_HEIGHT, player_radius));
+        }
+        if PLAYER_SPEED <= 0.0 || BULLET_SPEED <= 0.0 {
+            return Err(format!("Speeds must be positive, player: {}, bullet: {}", PLAYER_SPEED, BULLET_SPEED));
+        }
+        if let Some(max_move_distance) = self.options.max_move_distance {
+            if max_move_distance <= 0.0 {
+                return Err(format!("Maximum move distance must be positive, got {}", max_move_distance));
+            }
+        }
+        if self.options.time_scale <= 0.0 {
+            return Err(format!("Time scale must be positive, got {}", self.options.time_scale));
+        }
+        if let Some(bullet_drag) = self.options.bullet_drag {
+            if bullet_drag <= 0.0 || bullet_drag >= 1.0 {
+                return Err(format!("Bullet drag must be between 0 and 1, got {}", bullet_drag));
+            }
+        }
+        if let Some(damage_falloff) = self.options.damage_falloff {
+            if damage_falloff <= 0.0 || damage_falloff >= 1.0 {
+                return Err(format!("Damage falloff must be between 0 and 1, got {}", damage_falloff));
+            }
+        }
+        if let Some(view_radius) = self.options.view_radius {
+            if view_radius <= 0.0 {
+                return Err(format!("View radius must be positive, got {}", view_radius));
+            }
+        }
+        if let Some(spawn_clearance) = self.options.spawn_clearance {
+            if spawn_clearance < 2.0 * player_radius {
+                return Err(format!("Spawn clearance must be at least {} to keep players from overlapping, got {}",
+                                   2.0 * player_radius,
+                                   spawn_clearance));
+            }
+        }
+        if self.options.kick_threshold == Some(0) {
+            return Err("Kick threshold must be positive".to_string());
+        }
+        if self.options.max_bullets == Some(0) {
+            return Err("Maximum bullet count must be positive".to_string());
+        }
+        if self.options.max_inputs_per_tick == Some(0) {
+            return Err("Maximum inputs per tick must be positive".to_string());
+        }
+        if self.options.round_length == Some(0) {
+            return Err("Round length must be positive".to_string());
+        }
+        if self.options.lag_compensation == Some(0) {
+            return Err("Lag compensation must be positive".to_string());
+        }
+        if self.options.shutdown_reason.chars().count() > message::MAX_REASON_LENGTH {
+            return Err(format!("Shutdown reason must be at most {} characters long", message::MAX_REASON_LENGTH));
+        }
+        if self.options.defer_join_state && !self.options.full_updates {
+            return Err("Joining clients can only wait for the next full update if full updates are enabled".to_string());
+        }
+        if let Some((min_interval, max_interval)) = self.options.adaptive_full_updates {
+            if !self.options.full_updates {
+                return Err("Full updates can only be adaptive if they're enabled".to_string());
+            }
+            if min_interval == 0 || min_interval > max_interval {
+                return Err(format!("Adaptive full update interval must be a positive range, got {} to {}",
+                                   min_interval,
+                                   max_interval));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Attach an observer to be notified about the game's lifecycle events, replacing the previous one, if any.
+    pub fn set_observer(&mut self, observer: Box<GameObserver>) {
+        self.observer = Some(observer);
+    }
+
     /// Tries to process every available websocket event without blocking.
-    pub fn process_websocket_events(&mut self, game_messages: &mpsc::Receiver<WebSocketEvent>) {
+    ///
+    /// If inputs are limited per tick, clients' inputs are queued instead,
+    /// and up to the limit of every client's queued inputs are processed in a round-robin fashion,
+    /// so that a client flooding the server can't delay the others' inputs.
+    ///
+    /// Returns whether any more events can arrive, i.e. `false` once every sender is gone along with the server threads.
+    pub fn process_websocket_events(&mut self, game_messages: &mpsc::Receiver<WebSocketEvent>) -> bool {
+        let mut connected = true;
         loop {
             match game_messages.try_recv() {
                 Ok(message) => self.process_websocket_event(message),
-                Err(mpsc::TryRecvError::Empty) => return,
-                Err(mpsc::TryRecvError::Disconnected) => return, // Server thread died
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    connected = false;
+                    break;
+                }
             }
         }
+
+        if let Some(max_inputs_per_tick) = self.options.max_inputs_per_tick {
+            self.process_queued_inputs(max_inputs_per_tick);
+        }
+        connected
+    }
+
+    /// The amount of game updates processed so far.
+    pub fn current_tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// The amount of players currently alive.
+    pub fn alive_player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    /// The amount of bullets currently in flight.
+    pub fn bullet_count(&self) -> usize {
+        self.bullets.len()
+    }
+
+    /// Whether there's nothing to update, i.e. nobody is connected and there are no players or bullets left.
+    pub fn is_idle(&self) -> bool {
+        self.clients.is_empty() && self.spectators.is_empty() && self.players.is_empty() && self.bullets.is_empty()
+    }
+
+    /// A one-line summary of the game for logs, without the details `Debug` dumps.
+    pub fn summary(&self) -> String {
+        self.to_string()
+    }
+
+    /// Pause or resume the simulation, notifying everybody if that changes anything.
+    ///
+    /// Clients can still connect and disconnect while the game is paused, but nothing moves and the ticks stop.
+    pub fn set_paused(&mut self, paused: bool) {
+        if self.paused != paused {
+            self.paused = paused;
+            self.send_to_everybody(message::Message::GamePaused { paused: paused });
+        }
+    }
+
+    /// Whether the simulation is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Make players and bullets move the specified multiple of their usual distance per tick from now on,
+    /// for slow motion or fast forward without changing the tick rate.
+    ///
+    /// Only clients welcomed afterwards are told the scaled speeds. Non-positive scales are ignored.
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        if time_scale > 0.0 {
+            self.time_scale = time_scale;
+        }
+    }
+
+    /// Warn everybody the server is shutting down after the configured grace period, if any.
+    pub fn announce_shutdown(&self) {
+        self.send_to_everybody(message::Message::ServerShutdown {
+            seconds_remaining: self.options.shutdown_grace_period.unwrap_or(0),
+        });
     }
 
-    /// Updates the game state in one tick.
+    /// Updates the game state in one tick, unless it's paused.
+    ///
+    /// Players and bullets are processed in order of their IDs at every stage,
+    /// so that the same state always results in the same messages, in the same order.
     pub fn process_game_update(&mut self) {
+        if self.paused {
+            return;
+        }
+        self.tick += 1;
+        self.invalidate_encoded_state();
+
+        let mut pending_movement: Vec<_> = self.pending_movement.drain().collect();
+        pending_movement.sort_by(|&(lhs, _), &(rhs, _)| lhs.cmp(&rhs));
+        let mut started_moving = Vec::new();
+        for (id, movement) in pending_movement {
+            match self.apply_movement(id, movement) {
+                Some(message::Message::PlayerMoving { id, x, y, move_x, move_y }) => {
+                    started_moving.push(message::Player::moving(id, x, y, move_x, move_y));
+                }
+                Some(update) => self.send_movement_update(id, update),
+                None => {}
+            }
+        }
+        self.send_moving_updates(started_moving);
+
+        if let Some(acceleration_ticks) = self.options.acceleration_ticks {
+            self.accelerate_players(1.0 / acceleration_ticks as f32);
+        }
+
+        if let Some(max_distance) = self.options.max_move_distance {
+            self.reconcile_movement(max_distance);
+        }
+
+        let boundary_mode = self.options.boundary_mode;
+        let wall_response = self.options.wall_response;
+        let player_radius = self.options.player_size;
+        let player_speed = PLAYER_SPEED * self.time_scale;
+
         // Do a normal position update
-        let player_ids: Vec<_> = self.players.keys().map(|i| *i).collect();
-        let mut force_stopped_player_ids = Vec::new();
+        let mut player_ids: Vec<_> = self.players.keys().map(|i| *i).collect();
+        player_ids.sort();
+        let mut force_stopped_player_ids = mem::replace(&mut self.force_stopped_buffer, Vec::new());
+        let mut wrapped_player_ids = Vec::new();
+        let mut slid_player_ids = Vec::new();
         for cur_player_id in &player_ids {
             let collides_with_player = {
                 let cur_player = self.players.get(cur_player_id).unwrap();
-                match (cur_player.move_x, cur_player.move_y) {
-                    (None, None) => continue,
-                    (Some(move_x), Some(move_y)) => {
+                match cur_player.movement() {
+                    None => continue,
+                    Some(movement) => {
+                        let destination = Vec2::new(cur_player.x, cur_player.y) + Vec2::from(movement);
                         let mut collides = false;
                         for cmp_player_id in &player_ids {
                             if cmp_player_id != cur_player_id {
                                 let cmp_player = self.players.get(cmp_player_id).unwrap();
-                                if distance_between(cur_player.x + move_x,
-                                                    cur_player.y + move_y,
-                                                    cmp_player.x,
-                                                    cmp_player.y) <
-                                   2.0 * PLAYER_RADIUS {
+                                if destination.distance(Vec2::new(cmp_player.x, cmp_player.y)) < 2.0 * player_radius {
                                     collides = true;
                                     break;
                                 }
@@ -81,7 +379,6 @@ impl GameState {
                         }
                         collides
                     }
-                    _ => panic!("Invariant not met: player moves only in one direction"),
                 }
             };
 
@@ -89,293 +386,3948 @@ impl GameState {
             let mut collides_with_map = false;
 
             if !collides_with_player {
-                collides_with_map = Self::move_player(&mut player.x, player.move_x) ||
-                                    Self::move_player(&mut player.y, player.move_y);
+                match boundary_mode {
+                    BoundaryMode::Wrap => {
+                        let wrapped_x =
+                            Self::wrap_player(&mut player.x, player.move_x, player_speed, MAP_WIDTH, player_radius);
+                        let wrapped_y =
+                            Self::wrap_player(&mut player.y, player.move_y, player_speed, MAP_HEIGHT, player_radius);
+                        if wrapped_x || wrapped_y {
+                            wrapped_player_ids.push(*cur_player_id);
+                        }
+                    }
+                    BoundaryMode::Clamp | BoundaryMode::Bounce => {
+                        match wall_response {
+                            WallResponse::Stop => {
+                                collides_with_map =
+                                    Self::move_player(&mut player.x, player.move_x, player_speed, MAP_WIDTH, player_radius) ||
+                                    Self::move_player(&mut player.y, player.move_y, player_speed, MAP_HEIGHT, player_radius);
+                            }
+                            WallResponse::Slide => {
+                                let blocked_x =
+                                    Self::move_player(&mut player.x, player.move_x, player_speed, MAP_WIDTH, player_radius);
+                                let blocked_y =
+                                    Self::move_player(&mut player.y, player.move_y, player_speed, MAP_HEIGHT, player_radius);
+                                if blocked_x {
+                                    player.move_x = None;
+                                }
+                                if blocked_y {
+                                    player.move_y = None;
+                                }
+
+                                if blocked_x || blocked_y {
+                                    if player.movement().map_or(true, |movement| movement == (0.0, 0.0)) {
+                                        collides_with_map = true;
+                                    } else {
+                                        slid_player_ids.push(*cur_player_id);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
             }
 
             if collides_with_player || collides_with_map {
-                force_stopped_player_ids.push(cur_player_id);
+                force_stopped_player_ids.push(*cur_player_id);
+            }
+        }
+
+        for wrapped_player_id in wrapped_player_ids {
+            let (x, y) = {
+                let player = self.players.get(&wrapped_player_id).unwrap();
+                (player.x, player.y)
+            };
+            self.send_to_everybody(message::Message::PlayerTeleported {
+                id: wrapped_player_id,
+                x: x,
+                y: y,
+            });
+        }
+
+        for slid_player_id in slid_player_ids {
+            let player = *self.players.get(&slid_player_id).unwrap();
+            let (move_x, move_y) = player.movement().unwrap();
+
+            // Make accelerating players keep sliding, instead of running into the wall again
+            if let Some(&Some(input)) = self.movement_inputs.get(&slid_player_id) {
+                let input = Vec2::new(if player.move_x.is_none() { 0.0 } else { input.x },
+                                      if player.move_y.is_none() { 0.0 } else { input.y });
+                let _ = self.movement_inputs.insert(slid_player_id, Some(input));
+                let _ = self.broadcast_velocities.insert(slid_player_id, Vec2::new(move_x, move_y));
             }
+
+            self.send_to_everybody(message::Message::PlayerMoving {
+                id: slid_player_id,
+                x: player.x,
+                y: player.y,
+                move_x: move_x,
+                move_y: move_y,
+            });
         }
 
-        for force_stopped_player_id in force_stopped_player_ids {
+        for force_stopped_player_id in force_stopped_player_ids.drain(..) {
+            // Make accelerating players stop for good, like the others, until they request to move again
+            let _ = self.movement_inputs.remove(&force_stopped_player_id);
+            let _ = self.broadcast_velocities.remove(&force_stopped_player_id);
+
             let (x, y) = {
-                let mut player = self.players.get_mut(force_stopped_player_id).unwrap();
+                let mut player = self.players.get_mut(&force_stopped_player_id).unwrap();
                 player.move_x = None;
                 player.move_y = None;
                 (player.x, player.y)
             };
             self.send_to_everybody(message::Message::PlayerStopped {
-                id: *force_stopped_player_id,
+                id: force_stopped_player_id,
                 x: x,
                 y: y,
             });
         }
+        self.force_stopped_buffer = force_stopped_player_ids;
 
-        let mut destroyed_bullets = Vec::new();
-        let mut destroyed_players = Vec::new();
+        self.separate_players();
 
-        for (_, bullet) in &mut self.bullets {
-            bullet.bullet.x += bullet.bullet.move_x.unwrap_or(0.0) * BULLET_SPEED;
-            bullet.bullet.y += bullet.bullet.move_y.unwrap_or(0.0) * BULLET_SPEED;
+        let mut destroyed_bullets = mem::replace(&mut self.destroyed_bullets_buffer, Vec::new());
+        let mut hit_players = mem::replace(&mut self.hit_players_buffer, Vec::new());
+        let mut changed_bullets = Vec::new();
+        let mut expired_bullets = Vec::new();
 
-            if bullet.bullet.x < 0.0 || bullet.bullet.x > MAP_WIDTH || bullet.bullet.y < 0.0 ||
-               bullet.bullet.y > MAP_HEIGHT {
-                destroyed_bullets.push(bullet.bullet.id);
+        let bullet_drag = self.options.bullet_drag;
+        let bullet_radius = self.options.bullet_size;
+        let bullet_speed = BULLET_SPEED * self.time_scale;
+        for (_, owned_bullet) in &mut self.bullets {
+            let velocity = owned_bullet.bullet.movement().map_or(0.0, |movement| Vec2::from(movement).length());
+            owned_bullet.distance_travelled += velocity * bullet_speed;
+
+            let bullet = &mut owned_bullet.bullet;
+            if owned_bullet.distance_travelled > MAX_BULLET_RANGE {
+                destroyed_bullets.push(bullet.id);
+                expired_bullets.push(bullet.id);
+                continue;
+            }
+
+            bullet.x += bullet.move_x.unwrap_or(0.0) * bullet_speed;
+            bullet.y += bullet.move_y.unwrap_or(0.0) * bullet_speed;
+
+            let mut changed = false;
+            match boundary_mode {
+                BoundaryMode::Clamp => {
+                    if !Self::map_contains(bullet.x, bullet.y) {
+                        destroyed_bullets.push(bullet.id);
+                        continue;
+                    }
+                }
+                BoundaryMode::Wrap => {
+                    let (x, y) = (Self::wrap(bullet.x, MAP_WIDTH, bullet_radius),
+                                  Self::wrap(bullet.y, MAP_HEIGHT, bullet_radius));
+                    changed = (x, y) != (bullet.x, bullet.y);
+                    bullet.x = x;
+                    bullet.y = y;
+                }
+                BoundaryMode::Bounce => {
+                    if !Self::map_contains(bullet.x, bullet.y) {
+                        Self::bounce_bullet(&mut bullet.x, &mut bullet.move_x, MAP_WIDTH);
+                        Self::bounce_bullet(&mut bullet.y, &mut bullet.move_y, MAP_HEIGHT);
+                        changed = true;
+                    }
+                }
+            }
+
+            if let (Some(drag), Some(movement)) = (bullet_drag, bullet.movement()) {
+                let movement = Vec2::from(movement) * (1.0 - drag);
+                bullet.move_x = Some(movement.x);
+                bullet.move_y = Some(movement.y);
+
+                if movement.length() < MIN_BULLET_VELOCITY {
+                    destroyed_bullets.push(bullet.id);
+                    expired_bullets.push(bullet.id);
+                    continue;
+                }
+                if owned_bullet.broadcast_velocity - movement.length() >= MIN_VELOCITY_UPDATE {
+                    changed = true;
+                }
+            }
+
+            if changed {
+                owned_bullet.broadcast_velocity = bullet.movement().map_or(0.0, |movement| Vec2::from(movement).length());
+                changed_bullets.push(*bullet);
             }
         }
 
-        // Check for collisions
-        for (_, bullet) in &self.bullets {
-            for (_, player) in &self.players {
-                if bullet.owner_id != player.id &&
-                   distance_between(bullet.bullet.x, bullet.bullet.y, player.x, player.y) <
-                   BULLET_RADIUS + PLAYER_RADIUS {
-                    destroyed_bullets.push(bullet.bullet.id);
-                    destroyed_players.push((player.id, bullet.bullet.id));
+        expired_bullets.sort();
+        for bullet_id in expired_bullets {
+            self.send_to_everybody(message::Message::BulletDestroyed { id: bullet_id });
+        }
+
+        changed_bullets.sort_by(|lhs, rhs| lhs.id.cmp(&rhs.id));
+        for changed_bullet in changed_bullets {
+            self.send_to_everybody(message::Message::BulletMoving {
+                id: changed_bullet.id,
+                x: changed_bullet.x,
+                y: changed_bullet.y,
+                move_x: changed_bullet.move_x.unwrap_or(0.0),
+                move_y: changed_bullet.move_y.unwrap_or(0.0),
+            });
+        }
+
+        // Check for collisions, without combat bullets fly through players until they leave the map
+        if self.options.combat {
+            for (_, bullet) in &self.bullets {
+                for (_, player) in &self.players {
+                    let player = self.rewound(player, bullet.owner_id);
+                    if bullet.owner_id != player.id && self.bullet_hits_player(&bullet.bullet, &player) {
+                        destroyed_bullets.push(bullet.bullet.id);
+                        hit_players.push((player.id, bullet.bullet.id));
+                    }
                 }
             }
         }
 
-        // Process destroy requests
-        let mut rng = thread_rng();
-        for (player_id, bullet_id) in destroyed_players {
-            let bullet = self.bullets.get(&bullet_id).unwrap();
+        for (_, ticks_dead) in &mut self.dead_players {
+            *ticks_dead += 1;
+        }
+
+        // Process hits, the destroyed players stay dead until they request a respawn.
+        // Bullets hitting the same player are processed in the order they were fired, so the oldest one gets the kill.
+        hit_players.sort();
+        for (player_id, bullet_id) in hit_players.drain(..) {
+            if !self.players.contains_key(&player_id) {
+                continue; // Already killed by another bullet this tick
+            }
+
+            let (killer_id, distance_travelled) = {
+                let bullet = self.bullets.get(&bullet_id).unwrap();
+                (bullet.owner_id, bullet.distance_travelled)
+            };
+            let damage = self.bullet_damage(distance_travelled);
+            let health = {
+                let health = self.health.get_mut(&player_id).unwrap();
+                *health -= damage;
+                *health
+            };
+            if health > 0.0 {
+                continue;
+            }
+
+            let _ = self.players.remove(&player_id);
+            let _ = self.health.remove(&player_id);
+            let _ = self.dead_players.insert(player_id, 0);
+            *self.scores.entry(killer_id).or_insert(0) += 1;
+            *self.deaths.entry(player_id).or_insert(0) += 1;
+
             self.send_to_everybody(message::Message::PlayerDestroyed {
                 id: player_id,
-                killer_id: Some(bullet.owner_id),
+                killer_id: Some(killer_id),
                 bullet_id: Some(bullet_id),
             });
 
-            let (new_x, new_y) = self.random_free_spot(&mut rng);
+            let _ = self.kill_streaks.remove(&player_id);
+            if let Some(window) = self.options.kill_streak_window {
+                self.extend_kill_streak(killer_id, window);
+            }
 
-            {
-                let dead_player = self.players.get_mut(&player_id).unwrap();
-                dead_player.x = new_x;
-                dead_player.y = new_y;
+            if let Some(ref mut observer) = self.observer {
+                observer.on_kill(player_id, killer_id);
             }
 
-            self.send_to_everybody(message::Message::PlayerSpawned {
-                id: player_id,
-                x: new_x,
-                y: new_y,
-            });
+            if self.options.respawn_bullet_clearance.is_some() {
+                // The bullets which hit someone this tick are removed below
+                self.destroy_bullets(|bullet| bullet.owner_id == player_id && !destroyed_bullets.contains(&bullet.bullet.id));
+            }
         }
 
-        for bullet_id in destroyed_bullets {
+        self.hit_players_buffer = hit_players;
+
+        for bullet_id in destroyed_bullets.drain(..) {
             let _ = self.bullets.remove(&bullet_id);
         }
-    }
+        self.destroyed_bullets_buffer = destroyed_bullets;
 
-    /// Send the current state to each client.
-    pub fn send_state_updates(&mut self) {
-        if self.ticks_since_last_full_update == TICKS_BETWEEN_FULL_UPDATES {
-            self.ticks_since_last_full_update = 0;
-            self.send_to_everybody(self.serialize());
-        } else {
-            self.ticks_since_last_full_update += 1;
+        if let Some(round_length) = self.options.round_length {
+            if self.tick - self.round_start_tick >= round_length as u64 {
+                self.start_next_round(round_length);
+            }
         }
-    }
-
-    /// Process a web socket event.
-    fn process_websocket_event(&mut self, message: WebSocketEvent) {
-        match message {
-            WebSocketEvent::ClientCreated { client } => {
-                let welcome_message = message::Message::Welcome {
-                    id: client.id,
-                    speed: PLAYER_SPEED,
-                    size: PLAYER_RADIUS,
-                    bullet_speed: BULLET_SPEED,
-                    bullet_size: BULLET_RADIUS,
-                };
 
-                let _ = client.send(welcome_message.to_string());
-                self.send_to_everybody(message::Message::PlayerJoined { id: client.id });
+        if let Some(lag_compensation) = self.options.lag_compensation {
+            let positions = self.players.iter().map(|(&id, player)| (id, Vec2::new(player.x, player.y))).collect();
+            self.position_history.push_front(positions);
+            self.position_history.truncate(lag_compensation as usize);
+        }
 
-                let (x, y) = self.random_free_spot(&mut thread_rng());
-                let _ = self.players
-                            .insert(client.id, message::Player::not_moving(client.id, x, y));
-                self.send_to_everybody(message::Message::PlayerSpawned {
-                    id: client.id,
-                    x: x,
-                    y: y,
-                });
+        self.acknowledge_inputs();
 
-                let _ = client.send(self.serialize().to_string());
+        if let Some(mut observer) = self.observer.take() {
+            observer.on_tick(self);
+            self.observer = Some(observer);
+        }
+    }
 
-                let _ = self.clients.insert(client.id, client);
+    /// Get the specified player as the specified shooter saw it, i.e. as many ticks ago as the shooter lags.
+    ///
+    /// Players are left where they are if lag isn't compensated for, or they weren't alive back then.
+    fn rewound(&self, player: &message::Player, shooter_id: u32) -> message::Player {
+        let mut player = *player;
+        let ticks = self.latencies.get(&shooter_id).map_or(0, |&ticks| ticks as usize);
+        if ticks > 0 {
+            let past_positions = self.position_history.get(ticks - 1).or(self.position_history.back());
+            if let Some(&position) = past_positions.and_then(|positions| positions.get(&player.id)) {
+                player.x = position.x;
+                player.y = position.y;
             }
-            WebSocketEvent::ClientClosed { client_id } => {
-                let _ = self.players.remove(&client_id);
-                let _ = self.clients.remove(&client_id);
-
-                self.send_to_everybody(message::Message::PlayerLeft { id: client_id });
+        }
+        player
+    }
 
-                let mut destroyed_bullets = Vec::new();
-                for (_, bullet) in &self.bullets {
-                    if bullet.owner_id == client_id {
-                        destroyed_bullets.push(bullet.bullet.id);
-                    }
-                }
-                for bullet_id in destroyed_bullets {
-                    let _ = self.bullets.remove(&bullet_id);
-                }
+    /// Check whether the specified bullet overlaps the specified player's hitbox of the configured shape.
+    ///
+    /// Only hits against bullets depend on the shape, as walls are axis-aligned, so they stop both shapes at the same distance.
+    fn bullet_hits_player(&self, bullet: &message::Bullet, player: &message::Player) -> bool {
+        let player_radius = self.options.player_size;
+        match self.options.collision_shape {
+            CollisionShape::Circle => {
+                Vec2::new(bullet.x, bullet.y).distance(Vec2::new(player.x, player.y)) <
+                self.options.bullet_size + player_radius
             }
-            WebSocketEvent::ClientMessage { client_id, message } => {
-                self.process_client_message(client_id, message);
+            CollisionShape::Square => {
+                aabb_circle_hit(player.x - player_radius,
+                                player.y - player_radius,
+                                player.x + player_radius,
+                                player.y + player_radius,
+                                bullet.x,
+                                bullet.y,
+                                self.options.bullet_size)
             }
         }
     }
 
-    /// Serialize the entire game state into one json string.
-    fn serialize(&self) -> message::Message {
-        let players: Vec<_> = self.players
-                                  .values()
-                                  .cloned()
-                                  .collect();
-        let bullets: Vec<_> = self.bullets
-                                  .values()
-                                  .map(|b| &b.bullet)
-                                  .cloned()
-                                  .collect();
-        message::Message::WorldState {
-            player_count: self.clients.len() as u32,
-            alive_players: players,
-            alive_bullets: bullets,
+    /// Count a kill the specified player just scored towards its streak, if it's within the specified amount of ticks
+    /// of its previous kill, starting a new streak otherwise, and announce the streak if it reached a threshold.
+    fn extend_kill_streak(&mut self, killer_id: u32, window: u32) {
+        let streak = match self.kill_streaks.get(&killer_id) {
+            Some(&(streak, last_kill_tick)) if self.tick - last_kill_tick <= window as u64 => streak + 1,
+            _ => 1,
+        };
+        let _ = self.kill_streaks.insert(killer_id, (streak, self.tick));
+
+        if KILL_STREAK_THRESHOLDS.contains(&streak) {
+            self.send_to_everybody(message::Message::KillStreak {
+                id: killer_id,
+                streak: streak,
+            });
         }
     }
 
-    /// Process a simple string message from the client.
-    fn process_client_message(&mut self, client_id: u32, message: message::Message) {
-        match message {
-            message::Message::StartMoving { move_x, move_y } => {
-                let resp = {
-                    let player = self.players.get_mut(&client_id).unwrap();
-                    player.move_x = Some(move_x);
-                    player.move_y = Some(move_y);
+    /// End the round in progress, sending everyone its scores,
+    /// and start the next one with no bullets, scores reset and everyone who was spawned before respawned.
+    fn start_next_round(&mut self, round_length: u32) {
+        let mut client_ids: Vec<_> = self.clients.keys().map(|i| *i).collect();
+        client_ids.sort();
 
-                    message::Message::PlayerMoving {
-                        id: player.id,
-                        x: player.x,
-                        y: player.y,
-                        move_x: move_x,
-                        move_y: move_y,
-                    }
-                };
-                self.send_to_everybody(resp);
-            }
-            message::Message::StopMoving => {
-                let resp = {
-                    let player = self.players.get_mut(&client_id).unwrap();
-                    player.move_x = None;
-                    player.move_y = None;
+        let scores = client_ids.iter()
+                               .map(|id| message::Score::new(*id, self.scores.get(id).cloned().unwrap_or(0)))
+                               .collect();
+        self.send_to_everybody(message::Message::RoundEnd {
+            round: self.round,
+            scores: scores,
+        });
 
-                    message::Message::PlayerStopped {
-                        id: player.id,
-                        x: player.x,
-                        y: player.y,
-                    }
-                };
-                self.send_to_everybody(resp);
-            }
-            message::Message::Fire { move_x, move_y } => {
-                let player = self.players.get(&client_id).unwrap();
+        self.round += 1;
+        self.round_start_tick = self.tick;
+        self.scores.clear();
+        self.deaths.clear();
+        self.kill_streaks.clear();
 
-                // Have to move the bullet out of the way of the player to avoid an instant collision.
-                let start_x = player.x + move_x * (BULLET_RADIUS + PLAYER_RADIUS + 1.0);
-                let start_y = player.y + move_y * (BULLET_RADIUS + PLAYER_RADIUS + 1.0);
+        let mut bullet_ids: Vec<_> = self.bullets.keys().map(|i| *i).collect();
+        bullet_ids.sort();
+        for bullet_id in bullet_ids {
+            let _ = self.bullets.remove(&bullet_id);
+            self.send_to_everybody(message::Message::BulletDestroyed { id: bullet_id });
+        }
 
-                let new_bullet = message::Bullet::moving(self.next_bullet_id,
-                                                         start_x,
-                                                         start_y,
-                                                         move_x,
-                                                         move_y);
-                let _ = self.bullets
-                            .insert(self.next_bullet_id,
-                                    message::OwnedBullet::new(new_bullet, player.id));
+        // Clear the map first, so that everyone can pick from all the free spots
+        let respawned_ids: Vec<_> = client_ids.into_iter().filter(|id| !self.awaiting_ready.contains(id)).collect();
+        for id in &respawned_ids {
+            let _ = self.players.remove(id);
+            let _ = self.pending_movement.remove(id);
+        }
 
-                let resp = message::Message::ShotsFired {
-                    id: player.id,
-                    bullet_id: self.next_bullet_id,
-                    x: start_x,
-                    y: start_y,
-                    aim_x: move_x,
-                    aim_y: move_y,
-                };
-                self.send_to_everybody(resp);
+        self.send_to_everybody(message::Message::RoundStart {
+            round: self.round,
+            duration_ticks: round_length,
+        });
+        for id in respawned_ids {
+            self.spawn_player(id, true);
+        }
+    }
 
-                self.next_bullet_id += 1;
-            }
-            _ => panic!("Unprocessed message! {}", message.to_string()),
+    /// Damage dealt by a bullet which travelled the specified distance, according to the configured falloff.
+    fn bullet_damage(&self, distance_travelled: f32) -> f32 {
+        match self.options.damage_falloff {
+            Some(falloff) => BULLET_DAMAGE * (1.0 - falloff * distance_travelled).max(MIN_DAMAGE_FRACTION),
+            None => BULLET_DAMAGE,
         }
     }
 
-    /// Find a valid unoccupied spot for a player to spawn.
-    fn random_free_spot<R: Rng>(&self, rng: &mut R) -> (f32, f32) {
-        static MAX_ITERATIONS: u32 = 100;
+    /// Clamp every player's movement to the specified distance per tick,
+    /// sending corrected `PlayerMoving`s so that clients don't drift from the server.
+    fn reconcile_movement(&mut self, max_distance: f32) {
+        let mut reconciled_player_ids = Vec::new();
+        for (id, player) in &mut self.players {
+            if Self::clamp_movement(player, max_distance) {
+                reconciled_player_ids.push(*id);
+            }
+        }
 
-        let min_vial_x = PLAYER_RADIUS;
-        let min_vial_y = PLAYER_RADIUS;
-        let max_vial_x = MAP_WIDTH - PLAYER_RADIUS;
-        let max_vial_y = MAP_HEIGHT - PLAYER_RADIUS;
+        reconciled_player_ids.sort();
+        for reconciled_player_id in reconciled_player_ids {
+            let resp = {
+                let player = self.players.get(&reconciled_player_id).unwrap();
+                let (move_x, move_y) = player.movement().unwrap();
+                message::Message::PlayerMoving {
+                    id: player.id,
+                    x: player.x,
+                    y: player.y,
+                    move_x: move_x,
+                    move_y: move_y,
+                }
+            };
+            self.send_to_everybody(resp);
+        }
+    }
 
-        for _ in 1..MAX_ITERATIONS {
-            let x: f32 = rng.gen_range(min_vial_x, max_vial_x);
-            let y: f32 = rng.gen_range(min_vial_y, max_vial_y);
+    /// Push overlapping players a bit apart along the line between their centers,
+    /// sending the new positions of the pushed ones to everyone.
+    fn separate_players(&mut self) {
+        let player_radius = self.options.player_size;
+        let mut player_ids: Vec<_> = self.players.keys().map(|i| *i).collect();
+        player_ids.sort();
 
-            let mut collides = false;
+        let mut pushes: HashMap<u32, Vec2> = HashMap::new();
+        for (i, cur_player_id) in player_ids.iter().enumerate() {
+            for cmp_player_id in &player_ids[i + 1..] {
+                let cur_player = self.players.get(cur_player_id).unwrap();
+                let cmp_player = self.players.get(cmp_player_id).unwrap();
+                let cur_position = Vec2::new(cur_player.x, cur_player.y);
+                let cmp_position = Vec2::new(cmp_player.x, cmp_player.y);
 
-            for (_, player) in &self.players {
-                if distance_between(x, y, player.x, player.y) < 2.0 * PLAYER_RADIUS {
-                    collides = true;
-                    break;
+                let distance = cur_position.distance(cmp_position);
+                let overlap = 2.0 * player_radius - distance;
+                if overlap <= 0.0 {
+                    continue;
                 }
-            }
 
-            for (_, bullet) in &self.bullets {
-                if distance_between(x, y, bullet.bullet.x, bullet.bullet.y) <
-                   PLAYER_RADIUS + BULLET_RADIUS {
-                    collides = true;
-                    break;
+                let direction = if distance > 0.0 {
+                    (cur_position - cmp_position).normalize()
+                } else {
+                    Vec2::new(1.0, 0.0) // Exactly on top of each other, split them horizontally
+                };
+                let push = direction * (overlap / 2.0).min(SEPARATION_SPEED);
+                for &(id, push) in &[(*cur_player_id, push), (*cmp_player_id, -push)] {
+                    let total = pushes.entry(id).or_insert(Vec2::default());
+                    *total = *total + push;
                 }
             }
-
-            if !collides {
-                return (x, y);
-            }
         }
-        println!("Failed to find a random empty spot for player after {} iterations",
-                 MAX_ITERATIONS);
 
-        (rng.gen_range(0.0, MAP_WIDTH), rng.gen_range(0.0, MAP_HEIGHT))
-    }
+        let boundary_mode = self.options.boundary_mode;
+        let mut pushes: Vec<_> = pushes.into_iter().collect();
+        pushes.sort_by(|&(lhs, _), &(rhs, _)| lhs.cmp(&rhs));
+        for (id, push) in pushes {
+            let player = {
+                let player = self.players.get_mut(&id).unwrap();
+                let position = Vec2::new(player.x, player.y) + push;
+                match boundary_mode {
+                    BoundaryMode::Wrap => {
+                        player.x = Self::wrap(position.x, MAP_WIDTH, player_radius);
+                        player.y = Self::wrap(position.y, MAP_HEIGHT, player_radius);
+                    }
+                    BoundaryMode::Clamp | BoundaryMode::Bounce => {
+                        player.x = position.x.max(player_radius).min(MAP_WIDTH - player_radius);
+                        player.y = position.y.max(player_radius).min(MAP_HEIGHT - player_radius);
+                    }
+                }
+                *player
+            };
 
-    /// Send a specified message to all connected clients.
-    fn send_to_everybody(&self, what: message::Message) {
-        let value = what.to_string();
-        for (_, client) in &self.clients {
+            self.send_to_everybody(match player.movement() {
+                Some((move_x, move_y)) => {
+                    message::Message::PlayerMoving {
+                        id: id,
+                        x: player.x,
+                        y: player.y,
+                        move_x: move_x,
+                        move_y: move_y,
+                    }
+                }
+                None => {
+                    message::Message::PlayerStopped {
+                        id: id,
+                        x: player.x,
+                        y: player.y,
+                    }
+                }
+            });
+        }
+    }
+
+    /// Check whether the specified position is inside the playable area, edges included.
+    pub fn is_in_bounds(&self, x: f32, y: f32) -> bool {
+        Self::map_contains(x, y)
+    }
+
+    /// Relocate the specified player to the specified position (clamped to the map), notifying everyone about it.
+    ///
+    /// Returns whether the player was alive to be teleported.
+    pub fn teleport(&mut self, id: u32, x: f32, y: f32) -> bool {
+        let player_radius = self.options.player_size;
+        let (x, y) = match self.players.get_mut(&id) {
+            Some(player) => {
+                player.x = x.max(player_radius).min(MAP_WIDTH - player_radius);
+                player.y = y.max(player_radius).min(MAP_HEIGHT - player_radius);
+                (player.x, player.y)
+            }
+            None => return false,
+        };
+        self.invalidate_encoded_state();
+
+        self.send_to_everybody(message::Message::PlayerTeleported {
+            id: id,
+            x: x,
+            y: y,
+        });
+        true
+    }
+
+    /// Send the current state to each client, if periodic full updates are enabled and it's time for one,
+    /// see `full_update_interval()`.
+    ///
+    /// If the view radius is limited, every client is sent its own state, culled around its player.
+    pub fn send_state_updates(&mut self) {
+        if self.options.full_updates && self.tick - self.last_full_update_tick >= self.full_update_interval() {
+            self.last_full_update_tick = self.tick;
+            if self.options.view_radius.is_none() {
+                let state = self.encoded_state();
+                self.count_sent("world_state", state.len(), self.clients.len() + self.spectators.len());
+                for (_, client) in self.clients.iter().chain(&self.spectators) {
+                    // Ignore failures for the same reason as in send_to_everybody()
+                    let _ = client.send_shared(state.clone());
+                }
+                return;
+            }
+
+            let client_ids: Vec<_> = self.clients.keys().cloned().collect();
+            for id in client_ids {
+                self.send_state_to(id);
+            }
+            let spectator_ids: Vec<_> = self.spectators.keys().cloned().collect();
+            for id in spectator_ids {
+                self.send_state_to_spectator(id);
+            }
+        }
+    }
+
+    /// Amount of ticks between full updates.
+    ///
+    /// If they're adaptive, it's proportional to the amount of recipients times the amount of players and bullets,
+    /// so that about `ADAPTIVE_UPDATE_BUDGET` of them are sent per tick, within the configured bounds.
+    fn full_update_interval(&self) -> u64 {
+        match self.options.adaptive_full_updates {
+            Some((min_interval, max_interval)) => {
+                let recipients = (self.clients.len() + self.spectators.len()) as u64;
+                let entities = (self.players.len() + self.bullets.len()) as u64;
+                cmp::max(min_interval as u64,
+                         cmp::min(max_interval as u64, recipients * entities / ADAPTIVE_UPDATE_BUDGET))
+            }
+            None => TICKS_BETWEEN_FULL_UPDATES,
+        }
+    }
+
+    /// Process a web socket event.
+    fn process_websocket_event(&mut self, message: WebSocketEvent) {
+        match message {
+            WebSocketEvent::ClientCreated { client } => {
+                self.invalidate_encoded_state();
+                let welcome_message = message::Message::Welcome {
+                    id: client.id,
+                    speed: PLAYER_SPEED * self.time_scale,
+                    size: self.options.player_size,
+                    bullet_speed: BULLET_SPEED * self.time_scale,
+                    bullet_size: self.options.bullet_size,
+                    features: self.features(),
+                };
+
+                let id = client.id;
+                let quiet = self.membership_quiet(self.player_count() + 1);
+                if !quiet {
+                    self.send_to_everybody(message::Message::PlayerJoined { id: id });
+                }
+                if self.options.require_ready {
+                    let _ = self.awaiting_ready.insert(id);
+                } else {
+                    self.spawn_player(id, !quiet);
+                }
+
+                // Only start targeting the client once the others were notified, it learns about itself from the state
+                let _ = self.clients.insert(id, client);
+                self.send_to(id, welcome_message);
+                // With deferred join state, the next periodic full update reaches the joiner along with everyone else
+                if !self.options.defer_join_state {
+                    self.send_state_to(id);
+                }
+                if self.paused {
+                    self.send_to(id, message::Message::GamePaused { paused: true });
+                }
+                for (&player_id, &color) in &self.appearances {
+                    self.send_to(id,
+                                 message::Message::PlayerAppearance {
+                                     id: player_id,
+                                     color: color,
+                                 });
+                }
+                if !quiet {
+                    self.send_to_everybody(message::Message::PlayerCount { count: self.player_count() });
+                }
+                if let Some(round_length) = self.options.round_length {
+                    let duration_ticks = round_length - (self.tick - self.round_start_tick) as u32;
+                    self.send_to(id,
+                                 message::Message::RoundStart {
+                                     round: self.round,
+                                     duration_ticks: duration_ticks,
+                                 });
+                }
+
+                if let Some(ref mut observer) = self.observer {
+                    observer.on_join(id);
+                }
+            }
+            WebSocketEvent::ClientClosed { client_id } => {
+                self.invalidate_encoded_state();
+                let quiet = self.membership_quiet(self.player_count());
+                let _ = self.players.remove(&client_id);
+                let _ = self.dead_players.remove(&client_id);
+                let _ = self.awaiting_ready.remove(&client_id);
+                let _ = self.health.remove(&client_id);
+                let _ = self.pending_movement.remove(&client_id);
+                let _ = self.movement_inputs.remove(&client_id);
+                let _ = self.broadcast_velocities.remove(&client_id);
+                let _ = self.appearances.remove(&client_id);
+                let _ = self.violations.remove(&client_id);
+                let _ = self.last_fire_tick.remove(&client_id);
+                let _ = self.last_position_reports.remove(&client_id);
+                let _ = self.input_queues.remove(&client_id);
+                let _ = self.unacknowledged_inputs.remove(&client_id);
+                let _ = self.scores.remove(&client_id);
+                let _ = self.deaths.remove(&client_id);
+                let _ = self.kill_streaks.remove(&client_id);
+                let _ = self.latencies.remove(&client_id);
+                let _ = self.clients.remove(&client_id);
+
+                if !quiet {
+                    self.send_to_everybody(message::Message::PlayerLeft { id: client_id });
+                    self.send_to_everybody(message::Message::PlayerCount { count: self.player_count() });
+                }
+
+                let mut destroyed_bullets = Vec::new();
+                for (_, bullet) in &self.bullets {
+                    if bullet.owner_id == client_id {
+                        destroyed_bullets.push(bullet.bullet.id);
+                    }
+                }
+                for bullet_id in destroyed_bullets {
+                    let _ = self.bullets.remove(&bullet_id);
+                }
+
+                if let Some(ref mut observer) = self.observer {
+                    observer.on_leave(client_id);
+                }
+            }
+            WebSocketEvent::SpectatorCreated { client } => {
+                let id = client.id;
+                let _ = self.spectators.insert(id, client);
+                self.send_state_to_spectator(id);
+                if self.paused {
+                    self.send_to_spectator(id, message::Message::GamePaused { paused: true });
+                }
+                for (&player_id, &color) in &self.appearances {
+                    self.send_to_spectator(id,
+                                           message::Message::PlayerAppearance {
+                                               id: player_id,
+                                               color: color,
+                                           });
+                }
+            }
+            WebSocketEvent::SpectatorClosed { client_id } => {
+                let _ = self.spectators.remove(&client_id);
+            }
+            WebSocketEvent::SetPaused { paused } => self.set_paused(paused),
+            WebSocketEvent::SetTimeScale { time_scale } => self.set_time_scale(time_scale),
+            WebSocketEvent::ClientLatency { client_id, latency_ms } => {
+                if self.clients.contains_key(&client_id) {
+                    let _ = self.latencies.insert(client_id, (latency_ms as f32 / TICK_LENGTH).round() as u32);
+                }
+            }
+            WebSocketEvent::ClientMessage { client_id, message, seq } => {
+                if self.options.max_inputs_per_tick.is_some() {
+                    self.queue_input(client_id, message, seq);
+                } else {
+                    self.process_input(client_id, message, seq);
+                }
+            }
+        }
+    }
+
+    /// Queue the specified client's input to be processed later, dropping its oldest input if its queue is full.
+    fn queue_input(&mut self, client_id: u32, message: message::Message, seq: Option<u64>) {
+        let queue = self.input_queues.entry(client_id).or_insert_with(VecDeque::new);
+        if queue.len() >= MAX_QUEUED_INPUTS {
+            let _ = queue.pop_front();
+        }
+        queue.push_back((message, seq));
+    }
+
+    /// Process up to the specified amount of every client's queued inputs, taking one input from every client in turn.
+    fn process_queued_inputs(&mut self, max_inputs: u32) {
+        let mut client_ids: Vec<_> = self.input_queues.keys().cloned().collect();
+        client_ids.sort();
+
+        for _ in 0..max_inputs {
+            let mut processed_any = false;
+            for &client_id in &client_ids {
+                let input = match self.input_queues.get_mut(&client_id) {
+                    Some(queue) => queue.pop_front(),
+                    None => None, // Client went away
+                };
+                if let Some((input, seq)) = input {
+                    self.process_input(client_id, input, seq);
+                    processed_any = true;
+                }
+            }
+
+            if !processed_any {
+                break;
+            }
+        }
+
+        for client_id in client_ids {
+            let drained = match self.input_queues.get(&client_id) {
+                Some(queue) => queue.is_empty(),
+                None => false,
+            };
+            if drained {
+                let _ = self.input_queues.remove(&client_id);
+            }
+        }
+    }
+
+    /// Process the specified client's input, noting its seq, if any, to be acknowledged at the end of the next tick.
+    fn process_input(&mut self, client_id: u32, message: message::Message, seq: Option<u64>) {
+        self.process_client_message(client_id, message);
+        if let Some(seq) = seq {
+            let highest = self.unacknowledged_inputs.entry(client_id).or_insert(seq);
+            *highest = cmp::max(*highest, seq);
+        }
+    }
+
+    /// Send every client whose numbered inputs were processed since the last tick the highest seq among them.
+    fn acknowledge_inputs(&mut self) {
+        let mut acknowledged: Vec<_> = self.unacknowledged_inputs.drain().collect();
+        acknowledged.sort();
+        for (client_id, seq) in acknowledged {
+            self.send_to(client_id, message::Message::InputAck { seq: seq });
+        }
+    }
+
+    /// Names of the optional features enabled by the options, advertised to clients in **welcome**.
+    fn features(&self) -> Vec<String> {
+        let mut features = Vec::new();
+        if self.options.require_ready {
+            features.push("ready");
+        }
+        if self.options.client_positions {
+            features.push("position_report");
+        }
+        if self.options.fire_cooldown.is_some() {
+            features.push("fire_cooldown");
+        }
+        if self.options.round_length.is_some() {
+            features.push("rounds");
+        }
+        if self.options.kill_streak_window.is_some() {
+            features.push("kill_streaks");
+        }
+        if self.options.debug_stats {
+            features.push("stats");
+        }
+        if self.options.admin_password.is_some() {
+            features.push("admin_pause");
+        }
+        features.into_iter().map(str::to_string).collect()
+    }
+
+    /// Serialize the entire game state into one json string.
+    ///
+    /// Players and bullets are sorted by ID, so that the same state always serializes the same.
+    fn serialize(&self) -> message::Message {
+        self.serialize_around(None)
+    }
+
+    /// Serialize the game state as seen by the specified client,
+    /// i.e. only with the entities within the view radius of its player, if it's limited and the player is alive.
+    fn serialize_for(&self, id: u32) -> message::Message {
+        let view = match (self.options.view_radius, self.players.get(&id)) {
+            (Some(view_radius), Some(player)) => Some((Vec2::new(player.x, player.y), view_radius)),
+            _ => None,
+        };
+        self.serialize_around(view)
+    }
+
+    /// Serialize the game state, with only the entities within the specified `(center, radius)` circle, if any.
+    ///
+    /// Every connected player is counted either way.
+    fn serialize_around(&self, view: Option<(Vec2, f32)>) -> message::Message {
+        let visible = |x: f32, y: f32| view.map_or(true, |(center, radius)| center.distance(Vec2::new(x, y)) <= radius);
+
+        let mut players: Vec<_> = self.players
+                                      .values()
+                                      .filter(|p| visible(p.x, p.y))
+                                      .cloned()
+                                      .collect();
+        let mut bullets: Vec<_> = self.bullets
+                                      .values()
+                                      .map(|b| &b.bullet)
+                                      .filter(|b| visible(b.x, b.y))
+                                      .cloned()
+                                      .collect();
+        players.sort_by(|lhs, rhs| lhs.id.cmp(&rhs.id));
+        bullets.sort_by(|lhs, rhs| lhs.id.cmp(&rhs.id));
+
+        // Dead and not yet spawned players are connected too
+        message::Message::world_state(self.player_count(), players, bullets)
+    }
+
+    /// Amount of players currently connected, whether alive or not.
+    fn player_count(&self) -> u32 {
+        self.clients.len() as u32
+    }
+
+    /// Check whether joins and leaves go unannounced with the specified amount of players, joining or leaving one included,
+    /// in which case clients learn about them from full updates.
+    fn membership_quiet(&self, player_count: u32) -> bool {
+        self.options.quiet_membership_above.map_or(false, |threshold| player_count > threshold)
+    }
+
+    /// Process a simple string message from the client.
+    ///
+    /// Messages requiring the player to be alive are ignored if it isn't (i.e. it's dead or hasn't spawned yet).
+    /// While the game is paused, **start_moving** and **fire** are refused with **nack**, as they'd only take effect once it's resumed.
+    ///
+    /// Improbable inputs are recorded as violations, see `record_violation()`.
+    fn process_client_message(&mut self, client_id: u32, message: message::Message) {
+        self.invalidate_encoded_state();
+        if let Some(violation) = Self::input_violation(&message) {
+            self.record_violation(client_id, &violation);
+        }
+
+        if self.paused {
+            match message {
+                message::Message::StartMoving { .. } |
+                message::Message::Fire { .. } => {
+                    self.send_to(client_id, message::Message::Nack { reason: "Game paused".to_string() });
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        match message {
+            message::Message::StartMoving { move_x, move_y } => {
+                // Moving players are seen facing where they move, so there's no need to announce it
+                let _ = self.face(client_id, move_x, move_y);
+                self.request_movement(client_id, Some(Vec2::new(move_x, move_y)))
+            }
+            message::Message::StopMoving => self.request_movement(client_id, None),
+            message::Message::Fire { move_x, move_y } => {
+                if self.face(client_id, move_x, move_y) && !self.players[&client_id].is_moving() {
+                    self.send_to_everybody(message::Message::PlayerFacing {
+                        id: client_id,
+                        facing_x: move_x,
+                        facing_y: move_y,
+                    });
+                }
+
+                let player = match self.players.get(&client_id) {
+                    Some(player) => *player,
+                    None => return,
+                };
+
+                if let Some(cooldown) = self.options.fire_cooldown {
+                    if let Some(ready_in_ticks) = self.fire_ready_in(client_id, cooldown) {
+                        self.send_to(client_id, message::Message::FireCooldown { ready_in_ticks: ready_in_ticks });
+                        return;
+                    }
+                }
+
+                let offset = Self::bullet_spawn_offset(self.options.player_size, self.options.bullet_size);
+                let start_x = player.x + move_x * offset;
+                let start_y = player.y + move_y * offset;
+
+                // Near an edge, aiming outward can put the spawn point past it, so it's clamped into the map first.
+                // Under Clamp the clamped bullet is then checked instead, and if it'd still leave the map on its first move
+                // it'd be destroyed before ever being seen moving, so the shot is blocked
+                let start_x = start_x.max(0.0).min(MAP_WIDTH);
+                let start_y = start_y.max(0.0).min(MAP_HEIGHT);
+                let bullet_speed = BULLET_SPEED * self.time_scale;
+                if self.options.boundary_mode == BoundaryMode::Clamp &&
+                   !Self::map_contains(start_x + move_x * bullet_speed, start_y + move_y * bullet_speed) {
+                    self.send_to(client_id, message::Message::ShotBlocked);
+                    return;
+                }
+
+                if let Some(max_bullets) = self.options.max_bullets {
+                    if self.bullets.len() >= max_bullets as usize {
+                        match self.options.bullet_cap_policy {
+                            BulletCapPolicy::Reject => return,
+                            BulletCapPolicy::EvictOldest => self.evict_oldest_bullet(),
+                        }
+                    }
+                }
+
+                let new_bullet = message::Bullet::moving(self.next_bullet_id,
+                                                         start_x,
+                                                         start_y,
+                                                         move_x,
+                                                         move_y);
+                let _ = self.bullets
+                            .insert(self.next_bullet_id,
+                                    message::OwnedBullet::new(new_bullet, player.id));
+
+                let resp = message::Message::ShotsFired {
+                    id: player.id,
+                    bullet_id: self.next_bullet_id,
+                    x: start_x,
+                    y: start_y,
+                    aim_x: move_x,
+                    aim_y: move_y,
+                    tick: self.tick,
+                };
+                self.send_to_everybody(resp);
+
+                self.next_bullet_id += 1;
+
+                if let Some(cooldown) = self.options.fire_cooldown {
+                    let _ = self.last_fire_tick.insert(client_id, self.tick);
+                    self.send_to(client_id, message::Message::FireCooldown { ready_in_ticks: cooldown });
+                }
+            }
+            message::Message::RequestRespawn => {
+                let can_respawn = match self.dead_players.get(&client_id) {
+                    Some(ticks_dead) => *ticks_dead >= RESPAWN_DELAY,
+                    None => false,
+                };
+
+                if can_respawn {
+                    self.spawn_player(client_id, true);
+                }
+            }
+            message::Message::TimeSyncRequest { client_time } => {
+                let server_time = self.server_time();
+                self.send_to(client_id,
+                             message::Message::TimeSyncResponse {
+                                 client_time: client_time,
+                                 server_time: server_time,
+                             });
+            }
+            message::Message::SetAppearance { color } => {
+                let _ = self.appearances.insert(client_id, color);
+                self.send_to_everybody(message::Message::PlayerAppearance {
+                    id: client_id,
+                    color: color,
+                });
+            }
+            message::Message::PositionReport { x, y } => {
+                if self.options.client_positions {
+                    self.process_position_report(client_id, x, y);
+                }
+            }
+            message::Message::Ready => {
+                if self.awaiting_ready.remove(&client_id) {
+                    self.spawn_player(client_id, true);
+                }
+            }
+            message::Message::SetPaused { paused, password } => {
+                if self.options.admin_password.as_ref() == Some(&password) {
+                    println!("Client {} {} the game", client_id, if paused { "paused" } else { "resumed" });
+                    self.set_paused(paused);
+                } else {
+                    self.send_to(client_id, message::Message::Nack { reason: "Not allowed to pause".to_string() });
+                }
+            }
+            message::Message::Emote { emote, .. } => {
+                if emote < message::EMOTE_COUNT {
+                    self.send_to_everybody(message::Message::Emote {
+                        id: Some(client_id),
+                        emote: emote,
+                    });
+                } else {
+                    // Likely a client which knows more emotes than this server, so it's refused rather than sent away
+                    self.send_to(client_id,
+                                 message::Message::Nack { reason: format!("emote {} out of {}", emote, message::EMOTE_COUNT) });
+                }
+            }
+            message::Message::RequestStats => {
+                if self.options.debug_stats {
+                    let per_type = self.sent_stats
+                                       .borrow()
+                                       .iter()
+                                       .map(|(msg_type, &(count, bytes))| (msg_type.to_string(), count, bytes))
+                                       .collect();
+                    self.send_to(client_id, message::Message::Stats { per_type: per_type });
+                }
+            }
+            message::Message::RequestPlayerStats { id } => {
+                if self.clients.contains_key(&id) {
+                    let stats = message::Message::PlayerStats {
+                        id: id,
+                        kills: self.scores.get(&id).cloned().unwrap_or(0),
+                        deaths: self.deaths.get(&id).cloned().unwrap_or(0),
+                        health: self.health.get(&id).cloned().unwrap_or(0.0),
+                    };
+                    self.send_to(client_id, stats);
+                } else {
+                    self.send_to(client_id, message::Message::Nack { reason: format!("player {} not connected", id) });
+                }
+            }
+            _ => {} // Server-only messages, recorded as violations above
+        }
+    }
+
+    /// Accept the position the specified player reported if it could have moved there since its last accepted report,
+    /// snapping it back to where the server has it otherwise.
+    fn process_position_report(&mut self, id: u32, x: f32, y: f32) {
+        let player_position = match self.players.get(&id) {
+            Some(player) => Vec2::new(player.x, player.y),
+            None => return,
+        };
+        let (last_tick, last_position) = match self.last_position_reports.get(&id) {
+            Some(last_report) => *last_report,
+            None => (self.tick, player_position),
+        };
+
+        let max_distance = PLAYER_SPEED * self.time_scale * (self.tick - last_tick) as f32;
+        let player_radius = self.options.player_size;
+        let in_bounds = x >= player_radius && x <= MAP_WIDTH - player_radius && y >= player_radius &&
+                        y <= MAP_HEIGHT - player_radius;
+        if in_bounds && last_position.distance(Vec2::new(x, y)) <= max_distance {
+            if let Some(player) = self.players.get_mut(&id) {
+                player.x = x;
+                player.y = y;
+            }
+            let _ = self.last_position_reports.insert(id, (self.tick, Vec2::new(x, y)));
+        } else {
+            println!("Rejecting position ({}, {}) reported by client {}", x, y, id);
+            let _ = self.last_position_reports.insert(id, (self.tick, player_position));
+            self.send_to(id,
+                         message::Message::PlayerTeleported {
+                             id: id,
+                             x: player_position.x,
+                             y: player_position.y,
+                         });
+        }
+    }
+
+    /// Turn the specified player to face along the specified vector, unless it's zero.
+    ///
+    /// Returns whether the player is alive and now faces elsewhere than before.
+    fn face(&mut self, client_id: u32, facing_x: f32, facing_y: f32) -> bool {
+        if facing_x == 0.0 && facing_y == 0.0 {
+            return false;
+        }
+
+        match self.players.get_mut(&client_id) {
+            Some(player) if player.facing_x != Some(facing_x) || player.facing_y != Some(facing_y) => {
+                player.facing_x = Some(facing_x);
+                player.facing_y = Some(facing_y);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Change the specified player's movement, buffering it until the next tick if configured to merge movement.
+    fn request_movement(&mut self, client_id: u32, movement: Option<Vec2>) {
+        if self.options.merge_movement {
+            if self.players.contains_key(&client_id) {
+                let _ = self.pending_movement.insert(client_id, movement);
+            }
+        } else if let Some(update) = self.apply_movement(client_id, movement) {
+            self.send_movement_update(client_id, update);
+        }
+    }
+
+    /// Set the specified player's movement, returning the update to notify everyone about.
+    ///
+    /// If acceleration is enabled, the player only starts accelerating towards the specified movement instead,
+    /// so there's nothing to notify about yet.
+    fn apply_movement(&mut self, client_id: u32, movement: Option<Vec2>) -> Option<message::Message> {
+        if self.options.acceleration_ticks.is_some() {
+            if self.players.contains_key(&client_id) {
+                let _ = self.movement_inputs.insert(client_id, movement);
+            }
+            return None;
+        }
+
+        let player = match self.players.get_mut(&client_id) {
+            Some(player) => player,
+            None => return None,
+        };
+
+        Some(match movement {
+            Some(movement) => {
+                player.move_x = Some(movement.x);
+                player.move_y = Some(movement.y);
+
+                message::Message::PlayerMoving {
+                    id: player.id,
+                    x: player.x,
+                    y: player.y,
+                    move_x: movement.x,
+                    move_y: movement.y,
+                }
+            }
+            None => {
+                player.move_x = None;
+                player.move_y = None;
+
+                message::Message::PlayerStopped {
+                    id: player.id,
+                    x: player.x,
+                    y: player.y,
+                }
+            }
+        })
+    }
+
+    /// Change every player's movement towards the one it requested by at most the specified amount,
+    /// broadcasting the changes once they're big enough or the requested movement is reached.
+    fn accelerate_players(&mut self, max_change: f32) {
+        let mut changed_player_ids = Vec::new();
+        for (id, input) in &self.movement_inputs {
+            let player = match self.players.get_mut(id) {
+                Some(player) => player,
+                None => continue,
+            };
+
+            let current = player.movement().map_or(Vec2::default(), Vec2::from);
+            let target = input.unwrap_or(Vec2::default());
+            if current == target {
+                continue;
+            }
+
+            let velocity = Self::accelerate(current, target, max_change);
+            if velocity == Vec2::default() {
+                player.move_x = None;
+                player.move_y = None;
+            } else {
+                player.move_x = Some(velocity.x);
+                player.move_y = Some(velocity.y);
+            }
+
+            let last_velocity = self.broadcast_velocities.get(id).cloned().unwrap_or(Vec2::default());
+            if velocity == target || last_velocity.distance(velocity) >= MIN_VELOCITY_UPDATE {
+                let _ = self.broadcast_velocities.insert(*id, velocity);
+                changed_player_ids.push(*id);
+            }
+        }
+
+        changed_player_ids.sort();
+        for changed_player_id in changed_player_ids {
+            let player = *self.players.get(&changed_player_id).unwrap();
+            self.send_to_everybody(match player.movement() {
+                Some((move_x, move_y)) => {
+                    message::Message::PlayerMoving {
+                        id: player.id,
+                        x: player.x,
+                        y: player.y,
+                        move_x: move_x,
+                        move_y: move_y,
+                    }
+                }
+                None => {
+                    message::Message::PlayerStopped {
+                        id: player.id,
+                        x: player.x,
+                        y: player.y,
+                    }
+                }
+            });
+        }
+    }
+
+    /// Move the current movement vector towards the target one by at most the specified distance.
+    fn accelerate(current: Vec2, target: Vec2, max_change: f32) -> Vec2 {
+        static EPSILON: f32 = 0.0001; // So that accumulated rounding errors don't add a tick
+
+        let delta = target - current;
+        let distance = delta.length();
+        if distance <= max_change + EPSILON {
+            target
+        } else {
+            current + delta * (max_change / distance)
+        }
+    }
+
+    /// Describe why the specified message is improbable to come from an honest client, if it is.
+    fn input_violation(message: &message::Message) -> Option<String> {
+        match message {
+            &message::Message::StartMoving { move_x, move_y } |
+            &message::Message::Fire { move_x, move_y } => {
+                let magnitude = Vec2::new(move_x, move_y).length();
+                if magnitude > MAX_INPUT_MAGNITUDE {
+                    Some(format!("vector of magnitude {}", magnitude))
+                } else {
+                    None
+                }
+            }
+            &message::Message::StopMoving |
+            &message::Message::RequestRespawn |
+            &message::Message::TimeSyncRequest { .. } |
+            &message::Message::SetAppearance { .. } |
+            &message::Message::PositionReport { .. } |
+            &message::Message::RequestStats |
+            &message::Message::RequestPlayerStats { .. } |
+            &message::Message::Emote { .. } |
+            &message::Message::SetPaused { .. } |
+            &message::Message::Ready => None,
+            message => Some(format!("server-only message {}", message.to_string())),
+        }
+    }
+
+    /// Get the amount of ticks until the specified player can fire again with the specified cooldown,
+    /// or `None` if it already can.
+    fn fire_ready_in(&self, player_id: u32, cooldown: u32) -> Option<u32> {
+        let last_fire_tick = match self.last_fire_tick.get(&player_id) {
+            Some(last_fire_tick) => *last_fire_tick,
+            None => return None,
+        };
+
+        let ticks_since = self.tick - last_fire_tick;
+        if ticks_since < cooldown as u64 {
+            Some((cooldown as u64 - ticks_since) as u32)
+        } else {
+            None
+        }
+    }
+
+    /// Log an improbable input from the specified client,
+    /// sending it away if it reached the configured amount of those.
+    fn record_violation(&mut self, client_id: u32, violation: &str) {
+        println!("Client {} sent improbable input: {}", client_id, violation);
+
+        let violations = {
+            let violations = self.violations.entry(client_id).or_insert(0);
+            *violations += 1;
+            *violations
+        };
+
+        if Some(violations) == self.options.kick_threshold {
+            println!("Kicking client {} after {} improbable inputs", client_id, violations);
+            self.send_to(client_id,
+                         message::Message::GoAway { reason: "Too many improbable inputs".to_string() });
+            if let Some(client) = self.clients.get(&client_id) {
+                let _ = client.close();
+            }
+        }
+    }
+
+    /// Destroy all bullets matching the specified condition and notify everyone about them.
+    fn destroy_bullets<F: Fn(&message::OwnedBullet) -> bool>(&mut self, condition: F) {
+        let mut destroyed_ids: Vec<_> = self.bullets.values().filter(|bullet| condition(bullet)).map(|bullet| bullet.bullet.id).collect();
+        destroyed_ids.sort();
+
+        for destroyed_id in destroyed_ids {
+            let _ = self.bullets.remove(&destroyed_id);
+            self.send_to_everybody(message::Message::BulletDestroyed { id: destroyed_id });
+        }
+    }
+
+    /// Destroy the bullet which was fired first and notify everyone about it.
+    ///
+    /// Bullet IDs are assigned sequentially, so the oldest bullet is the one with the lowest ID.
+    fn evict_oldest_bullet(&mut self) {
+        let oldest_id = match self.bullets.keys().min() {
+            Some(id) => *id,
+            None => return,
+        };
+
+        let _ = self.bullets.remove(&oldest_id);
+        self.send_to_everybody(message::Message::BulletDestroyed { id: oldest_id });
+    }
+
+    /// Seconds elapsed on the monotonic clock since the game started.
+    fn server_time(&self) -> f64 {
+        (time::precise_time_ns() - self.start_time) as f64 / 1_000_000_000.0
+    }
+
+    /// Spawn the specified player at a random free spot, notifying everyone about it if `announce` is set.
+    ///
+    /// If the map is too crowded, the player is left dead instead, able to request a respawn right away.
+    fn spawn_player(&mut self, id: u32, announce: bool) {
+        let spot = match self.random_free_spot(&mut thread_rng()) {
+            Some(spot) => spot,
+            None => {
+                let _ = self.dead_players.entry(id).or_insert(RESPAWN_DELAY);
+                return;
+            }
+        };
+        let _ = self.dead_players.remove(&id);
+        let _ = self.movement_inputs.remove(&id);
+        let _ = self.broadcast_velocities.remove(&id);
+        let _ = self.players.insert(id, message::Player::not_moving(id, spot.x, spot.y));
+        let _ = self.health.insert(id, MAX_HEALTH);
+        if self.options.client_positions {
+            let _ = self.last_position_reports.insert(id, (self.tick, spot));
+        }
+        if let Some(clearance) = self.options.respawn_bullet_clearance {
+            self.destroy_bullets(|bullet| Vec2::new(bullet.bullet.x, bullet.bullet.y).distance(spot) < clearance);
+        }
+        if announce {
+            self.send_to_everybody(message::Message::PlayerSpawned {
+                id: id,
+                x: spot.x,
+                y: spot.y,
+            });
+        }
+    }
+
+    /// Find a valid unoccupied spot for a player to spawn, keeping the configured clearance from other players.
+    ///
+    /// Returns `None` if the map is too crowded to find one.
+    fn random_free_spot<R: Rng>(&self, rng: &mut R) -> Option<Vec2> {
+        static MAX_ITERATIONS: u32 = 100;
+
+        let player_radius = self.options.player_size;
+        let clearance = self.options.spawn_clearance.unwrap_or(2.0 * player_radius);
+
+        let min_vial_x = player_radius;
+        let min_vial_y = player_radius;
+        let max_vial_x = MAP_WIDTH - player_radius;
+        let max_vial_y = MAP_HEIGHT - player_radius;
+
+        for _ in 1..MAX_ITERATIONS {
+            let x: f32 = rng.gen_range(min_vial_x, max_vial_x);
+            let y: f32 = rng.gen_range(min_vial_y, max_vial_y);
+            let spot = Vec2::new(x, y);
+
+            let mut collides = false;
+
+            for (_, player) in &self.players {
+                if spot.distance(Vec2::new(player.x, player.y)) < clearance {
+                    collides = true;
+                    break;
+                }
+            }
+
+            for (_, bullet) in &self.bullets {
+                if spot.distance(Vec2::new(bullet.bullet.x, bullet.bullet.y)) < player_radius + self.options.bullet_size {
+                    collides = true;
+                    break;
+                }
+            }
+
+            if !collides {
+                return Some(spot);
+            }
+        }
+        println!("Failed to find a random empty spot for player after {} iterations, map overcrowded",
+                 MAX_ITERATIONS);
+
+        None
+    }
+
+    /// Serialise the specified message to be sent to the specified amount of clients, with floats rounded as configured,
+    /// counting it towards the stats, if they're enabled.
+    fn encode(&self, what: &message::Message, recipients: usize) -> String {
+        if let Err(error) = self.check_outgoing(what) {
+            println!("Sending a message with vectors which should be normalised, but aren't: {:?}", error);
+        }
+
+        let (msg_type, encoded) = what.to_typed_string_with_precision(self.options.float_precision);
+        self.count_sent(msg_type, encoded.len(), recipients);
+        encoded
+    }
+
+    /// Check whether the vectors in the specified message are normalised where the protocol requires them to be,
+    /// if configured to be strict about it.
+    fn check_outgoing(&self, what: &message::Message) -> Result<(), message::MessageError> {
+        if self.options.strict {
+            what.check_normalised()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Count a message of the specified type and length sent to the specified amount of clients towards the stats,
+    /// if they're enabled.
+    fn count_sent(&self, msg_type: &'static str, length: usize, recipients: usize) {
+        if self.options.debug_stats && recipients > 0 {
+            let mut sent_stats = self.sent_stats.borrow_mut();
+            let stats = sent_stats.entry(msg_type).or_insert((0, 0));
+            stats.0 += recipients as u64;
+            stats.1 += (length * recipients) as u64;
+        }
+    }
+
+    /// Get the whole world state serialised, reusing the last serialisation if the state didn't change since.
+    ///
+    /// Isn't counted towards the stats, the sending is.
+    fn encoded_state(&self) -> Arc<String> {
+        let mut encoded_state = self.encoded_state.borrow_mut();
+        if let Some(ref state) = *encoded_state {
+            return state.clone();
+        }
+        let state = Arc::new(self.encode(&self.serialize(), 0));
+        *encoded_state = Some(state.clone());
+        state
+    }
+
+    /// Forget the last serialisation of the world state, as it doesn't match the state anymore.
+    fn invalidate_encoded_state(&self) {
+        *self.encoded_state.borrow_mut() = None;
+    }
+
+    /// Send the world state to the client with the specified ID, if it's connected,
+    /// culled around its player if the view radius is limited.
+    fn send_state_to(&self, client_id: u32) {
+        if self.options.view_radius.is_some() && self.players.contains_key(&client_id) {
+            self.send_to(client_id, self.serialize_for(client_id));
+        } else if let Some(client) = self.clients.get(&client_id) {
+            let state = self.encoded_state();
+            self.count_sent("world_state", state.len(), 1);
+            // Ignore failures for the same reason as in send_to_everybody()
+            let _ = client.send_shared(state);
+        }
+    }
+
+    /// Send the whole world state to the spectator with the specified ID, if it's connected.
+    fn send_state_to_spectator(&self, spectator_id: u32) {
+        if let Some(spectator) = self.spectators.get(&spectator_id) {
+            let state = self.encoded_state();
+            self.count_sent("world_state", state.len(), 1);
+            // Ignore failures for the same reason as in send_to_everybody()
+            let _ = spectator.send_shared(state);
+        }
+    }
+
+    /// Send a specified message to the client with the specified ID, if it's connected.
+    fn send_to(&self, client_id: u32, what: message::Message) {
+        if let Some(client) = self.clients.get(&client_id) {
+            // Ignore failures for the same reason as in send_to_everybody()
+            let _ = client.send(self.encode(&what, 1));
+        }
+    }
+
+    /// Send a specified message to the spectator with the specified ID, if it's connected.
+    fn send_to_spectator(&self, spectator_id: u32, what: message::Message) {
+        if let Some(spectator) = self.spectators.get(&spectator_id) {
+            // Ignore failures for the same reason as in send_to_everybody()
+            let _ = spectator.send(self.encode(&what, 1));
+        }
+    }
+
+    /// Send a specified message to all connected clients and spectators.
+    ///
+    /// The message is serialised once and shared between the clients,
+    /// so each additional client costs a reference count bump rather than a copy of the whole payload.
+    fn send_to_everybody(&self, what: message::Message) {
+        let value = Arc::new(self.encode(&what, self.clients.len() + self.spectators.len()));
+        for (_, client) in self.clients.iter().chain(&self.spectators) {
             // Always ignore if the send fails.
             // We will eventually get a disconnect WebSocketMessage where we will cleanly do the disconnect.
-            let _ = client.send(value.clone());
+            let _ = client.send_shared(value.clone());
+        }
+    }
+
+    /// Send a specified message to all connected clients but the one with the specified ID, and all spectators.
+    fn send_to_everybody_except(&self, except_id: u32, what: message::Message) {
+        let except_count = if self.clients.contains_key(&except_id) { 1 } else { 0 };
+        let recipients = self.clients.len() - except_count + self.spectators.len();
+        let value = Arc::new(self.encode(&what, recipients));
+        for (_, client) in &self.clients {
+            if client.id != except_id {
+                // Ignore failures for the same reason as in send_to_everybody()
+                let _ = client.send_shared(value.clone());
+            }
+        }
+        for (_, spectator) in &self.spectators {
+            let _ = spectator.send_shared(value.clone());
+        }
+    }
+
+    /// Broadcast a movement update caused by the specified client's input,
+    /// skipping that client if it's configured to predict its own movement.
+    fn send_movement_update(&self, origin_id: u32, what: message::Message) {
+        if self.options.suppress_movement_echo {
+            self.send_to_everybody_except(origin_id, what);
+        } else {
+            self.send_to_everybody(what);
+        }
+    }
+
+    /// Broadcast that the specified players started moving, caused by their own input,
+    /// batched into one **players_moving** if there's several of them.
+    ///
+    /// If the players are configured to predict their own movement, each client is sent a batch without itself.
+    fn send_moving_updates(&self, mut players: Vec<message::Player>) {
+        if players.len() <= 1 {
+            for player in players {
+                self.send_movement_update(player.id,
+                                          message::Message::PlayerMoving {
+                                              id: player.id,
+                                              x: player.x,
+                                              y: player.y,
+                                              move_x: player.move_x.unwrap(),
+                                              move_y: player.move_y.unwrap(),
+                                          });
+            }
+            return;
+        }
+
+        players.sort_by(|lhs, rhs| lhs.id.cmp(&rhs.id));
+        if !self.options.suppress_movement_echo {
+            self.send_to_everybody(message::Message::PlayersMoving { updates: players });
+            return;
+        }
+
+        for &id in self.clients.keys() {
+            self.send_to(id,
+                         message::Message::PlayersMoving {
+                             updates: players.iter().filter(|player| player.id != id).cloned().collect(),
+                         });
+        }
+        for &id in self.spectators.keys() {
+            self.send_to_spectator(id, message::Message::PlayersMoving { updates: players.clone() });
+        }
+    }
+
+    /// Distance from the shooter's center at which a fired bullet spawns.
+    ///
+    /// The bullet starts just touching its owner; owners are never hit by their own bullets,
+    /// so no extra clearance is needed.
+    fn bullet_spawn_offset(player_radius: f32, bullet_radius: f32) -> f32 {
+        player_radius + bullet_radius
+    }
+
+    /// Scales the player's movement vector down so that it covers at most `max_distance` per tick.
+    ///
+    /// Returns whether the movement had to be clamped.
+    fn clamp_movement(player: &mut message::Player, max_distance: f32) -> bool {
+        if let Some(movement) = player.movement() {
+            let distance = Vec2::from(movement).length() * PLAYER_SPEED;
+            if distance > max_distance {
+                // Axes the player isn't moving along stay that way
+                let scale = max_distance / distance;
+                player.move_x = player.move_x.map(|move_x| move_x * scale);
+                player.move_y = player.move_y.map(|move_y| move_y * scale);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Moves a player of the specified radius along one axis of the specified length
+    /// based on its current position, movement vector and the specified speed.
+    ///
+    /// Returns whether the player crashed into a wall during movement.
+    fn move_player(pos: &mut f32, mov: Option<f32>, speed: f32, map_size: f32, radius: f32) -> bool {
+        let new_pos = *pos + mov.unwrap_or(0.0) * speed;
+        *pos = new_pos.max(radius)
+                      .min(map_size - radius);
+
+        // Along a single axis, the area the player's centre can reach is a rectangle of no height
+        !rect_contains(radius, 0.0, map_size - radius, 0.0, new_pos, 0.0)
+    }
+
+    /// Check whether the specified position is within the map's edges.
+    fn map_contains(x: f32, y: f32) -> bool {
+        rect_contains(0.0, 0.0, MAP_WIDTH, MAP_HEIGHT, x, y)
+    }
+
+    /// Moves a player of the specified radius at the specified speed along one axis of the specified length,
+    /// wrapping around to the opposite edge.
+    ///
+    /// Returns whether the player wrapped around during movement.
+    fn wrap_player(pos: &mut f32, mov: Option<f32>, speed: f32, map_size: f32, radius: f32) -> bool {
+        let new_pos = *pos + mov.unwrap_or(0.0) * speed;
+        *pos = Self::wrap(new_pos, map_size, radius);
+
+        *pos != new_pos
+    }
+
+    /// Wraps the position of something of the specified radius, which is partly outside an axis of the specified length,
+    /// around to the opposite edge, so that it's always entirely within the map.
+    fn wrap(pos: f32, map_size: f32, radius: f32) -> f32 {
+        let span = map_size - 2.0 * radius;
+        if pos < radius {
+            pos + span
+        } else if pos > map_size - radius {
+            pos - span
+        } else {
+            pos
+        }
+    }
+
+    /// Reflects a bullet which went past a wall along one axis of the specified length, reversing its movement.
+    fn bounce_bullet(pos: &mut f32, mov: &mut Option<f32>, map_size: f32) {
+        if *pos < 0.0 {
+            *pos = -*pos;
+        } else if *pos > map_size {
+            *pos = 2.0 * map_size - *pos;
+        } else {
+            return;
+        }
+
+        *mov = mov.map(|m| -m);
+    }
+}
+
+impl fmt::Display for GameState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "tick {}, clients {}, players {}, bullets {}",
+               self.tick,
+               self.clients.len(),
+               self.players.len(),
+               self.bullets.len())
+    }
+}
+
+impl Drop for GameState {
+    fn drop(&mut self) {
+        if self.clients.is_empty() && self.spectators.is_empty() {
+            return;
+        }
+
+        // Clients whose websockets already went away are skipped by send_to_everybody()
+        let reason = self.options.shutdown_reason.clone();
+        self.send_to_everybody(message::Message::GoAway { reason: reason });
+        for client in self.clients.values().chain(self.spectators.values()) {
+            let _ = client.close();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{iter, str};
+    use std::rc::Rc;
+    use std::cell::RefCell;
+    use std::sync::{mpsc, Arc};
+    use rand::thread_rng;
+    use message::{Bullet, Message, OwnedBullet, Player, Score, EMOTE_COUNT, MAX_REASON_LENGTH};
+    use math::{distance_between, Vec2};
+    use options::{BoundaryMode, BulletCapPolicy, CollisionShape, Options, WallResponse};
+    use self::super::{GameState, BULLET_SPEED, MAP_HEIGHT, MAP_WIDTH, MAX_BULLET_RANGE, MAX_HEALTH,
+                      PLAYER_SPEED, RESPAWN_DELAY, TICKS_BETWEEN_FULL_UPDATES};
+    use self::super::super::{Client, GameObserver, WebSocketEvent};
+
+    #[derive(Debug)]
+    struct RecordingObserver {
+        events: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl GameObserver for RecordingObserver {
+        fn on_join(&mut self, id: u32) {
+            self.events.borrow_mut().push(format!("join {}", id));
+        }
+
+        fn on_leave(&mut self, id: u32) {
+            self.events.borrow_mut().push(format!("leave {}", id));
+        }
+
+        fn on_kill(&mut self, victim_id: u32, killer_id: u32) {
+            self.events.borrow_mut().push(format!("kill {} {}", victim_id, killer_id));
+        }
+    }
+
+    #[test]
+    fn fired_bullet_does_not_hit_owner_on_first_tick() {
+        let mut state = GameState::new(Options::default());
+        let rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 250.0);
+
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        state.process_game_update();
+
+        assert_eq!(state.bullets.len(), 1);
+        assert!(received(&rx).iter().all(|m| match m {
+            &Message::PlayerDestroyed { .. } => false,
+            _ => true,
+        }));
+    }
+
+    #[test]
+    fn alive_player_cannot_request_respawn() {
+        let mut state = GameState::new(Options::default());
+        let rx = connect(&mut state, 0);
+        let _ = received(&rx);
+
+        state.process_client_message(0, Message::RequestRespawn);
+
+        assert!(state.players.contains_key(&0));
+        assert!(received(&rx).is_empty());
+    }
+
+    #[test]
+    fn dead_player_cannot_respawn_before_delay() {
+        let mut state = GameState::new(Options::default());
+        let rx = connect(&mut state, 0);
+        let _ = connect(&mut state, 1);
+        kill(&mut state, 0, 1);
+        let _ = received(&rx);
+
+        state.process_client_message(0, Message::RequestRespawn);
+
+        assert!(!state.players.contains_key(&0));
+        assert!(received(&rx).is_empty());
+    }
+
+    #[test]
+    fn dead_player_respawns_on_request_after_delay() {
+        let mut state = GameState::new(Options::default());
+        let rx = connect(&mut state, 0);
+        let _ = connect(&mut state, 1);
+        kill(&mut state, 0, 1);
+        for _ in 0..RESPAWN_DELAY {
+            state.process_game_update();
+        }
+        let _ = received(&rx);
+
+        state.process_client_message(0, Message::RequestRespawn);
+
+        assert!(state.players.contains_key(&0));
+        assert!(!state.dead_players.contains_key(&0));
+        assert!(received(&rx).iter().any(|m| match m {
+            &Message::PlayerSpawned { id, .. } => id == 0,
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn overspeed_movement_is_clamped_and_corrected() {
+        let mut state = GameState::new(Options { max_move_distance: Some(2.0), ..Options::default() });
+        let rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 250.0);
+
+        state.process_client_message(0,
+                                     Message::StartMoving {
+                                         move_x: 3.0,
+                                         move_y: 4.0,
+                                     });
+        let _ = received(&rx);
+        state.process_game_update();
+
+        let player = *state.players.get(&0).unwrap();
+        assert!((distance_between(250.0, 250.0, player.x, player.y) - 2.0).abs() < 0.001);
+        assert!(received(&rx).iter().any(|m| match m {
+            &Message::PlayerMoving { id, move_x, move_y, .. } => {
+                id == 0 && (distance_between(0.0, 0.0, move_x, move_y) * PLAYER_SPEED - 2.0).abs() < 0.001
+            }
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn half_time_scale_halves_movement_per_tick() {
+        let mut state = GameState::new(Options { time_scale: 0.5, ..Options::default() });
+        let _rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 250.0);
+        state.players.get_mut(&0).unwrap().move_x = Some(1.0);
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 0.0,
+                                         move_y: 1.0,
+                                     });
+        let bullet_y = state.bullets.values().next().unwrap().bullet.y;
+
+        state.process_game_update();
+
+        assert_eq!(state.players.get(&0).unwrap().x, 250.0 + PLAYER_SPEED / 2.0);
+        assert_eq!(state.bullets.values().next().unwrap().bullet.y, bullet_y + BULLET_SPEED / 2.0);
+
+        state.process_websocket_event(WebSocketEvent::SetTimeScale { time_scale: 2.0 });
+        state.process_game_update();
+        assert_eq!(state.players.get(&0).unwrap().x, 250.0 + PLAYER_SPEED / 2.0 + PLAYER_SPEED * 2.0);
+    }
+
+    #[test]
+    fn bullet_map_is_sized_for_bullet_cap() {
+        let state = GameState::new(Options { max_bullets: Some(100), ..Options::default() });
+
+        assert!(state.bullets.capacity() >= 100);
+    }
+
+    #[test]
+    fn steady_ticking_reuses_buffers() {
+        let mut state = GameState::new(Options::default());
+        let leave_map = |state: &mut GameState, id: u32| {
+            let bullet = Bullet::moving(id, MAP_WIDTH - 1.0, 250.0, 1.0, 0.0);
+            let _ = state.bullets.insert(id, OwnedBullet::new(bullet, 0));
+            state.process_game_update();
+            assert!(state.bullets.is_empty());
+        };
+
+        leave_map(&mut state, 0);
+        let buffer = (state.destroyed_bullets_buffer.as_ptr(), state.destroyed_bullets_buffer.capacity());
+        assert!(buffer.1 > 0);
+        for id in 1..10 {
+            leave_map(&mut state, id);
+            assert!(state.destroyed_bullets_buffer.is_empty());
+            assert_eq!((state.destroyed_bullets_buffer.as_ptr(), state.destroyed_bullets_buffer.capacity()),
+                       buffer);
+        }
+    }
+
+    #[test]
+    fn player_moving_along_one_axis_moves_only_along_it() {
+        let mut state = GameState::new(Options::default());
+        let _rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 250.0);
+        state.players.get_mut(&0).unwrap().move_y = Some(1.0);
+
+        state.process_game_update();
+
+        let player = *state.players.get(&0).unwrap();
+        assert_eq!((player.x, player.y), (250.0, 250.0 + PLAYER_SPEED));
+        assert_eq!((player.move_x, player.move_y), (None, Some(1.0)));
+    }
+
+    #[test]
+    fn observer_sees_join_kill_and_leave_in_order() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut state = GameState::new(Options::default());
+        state.set_observer(Box::new(RecordingObserver { events: events.clone() }));
+
+        let _ = connect(&mut state, 0);
+        let _ = connect(&mut state, 1);
+        kill(&mut state, 0, 1);
+        state.process_websocket_event(WebSocketEvent::ClientClosed { client_id: 0 });
+
+        assert_eq!(*events.borrow(),
+                   vec!["join 0".to_string(),
+                        "join 1".to_string(),
+                        "kill 0 1".to_string(),
+                        "leave 0".to_string()]);
+    }
+
+    #[test]
+    fn teleport_moves_player_and_notifies() {
+        let mut state = GameState::new(Options::default());
+        let rx = connect(&mut state, 0);
+        let _ = received(&rx);
+
+        assert!(state.teleport(0, 100.0, 1000.0));
+
+        let player = *state.players.get(&0).unwrap();
+        let player_radius = Options::default().player_size;
+        assert_eq!((player.x, player.y), (100.0, MAP_HEIGHT - player_radius));
+        assert_eq!(received(&rx),
+                   vec![Message::PlayerTeleported {
+                            id: 0,
+                            x: 100.0,
+                            y: MAP_HEIGHT - player_radius,
+                        }]);
+    }
+
+    #[test]
+    fn teleporting_unknown_player_fails() {
+        let mut state = GameState::new(Options::default());
+
+        assert!(!state.teleport(0, 100.0, 100.0));
+    }
+
+    #[test]
+    fn broadcast_payload_is_shared_between_clients() {
+        let mut state = GameState::new(Options::default());
+        let rx0 = connect(&mut state, 0);
+        let rx1 = connect(&mut state, 1);
+        let _ = received(&rx0);
+        let _ = received(&rx1);
+
+        state.send_to_everybody(state.serialize());
+
+        let payload0 = rx0.try_recv().unwrap().unwrap();
+        let payload1 = rx1.try_recv().unwrap().unwrap();
+        let address0: *const String = &*payload0;
+        let address1: *const String = &*payload1;
+        assert_eq!(payload0, payload1);
+        assert_eq!(address0, address1);
+    }
+
+    #[test]
+    fn joiners_in_the_same_tick_share_one_world_state_serialization() {
+        let mut state = GameState::new(Options::default());
+        let _rx = connect(&mut state, 0);
+        let (tx0, rx0) = mpsc::channel();
+        let (tx1, rx1) = mpsc::channel();
+        state.process_websocket_event(WebSocketEvent::SpectatorCreated { client: Client::new(0, tx0) });
+        state.process_websocket_event(WebSocketEvent::SpectatorCreated { client: Client::new(1, tx1) });
+
+        let state0 = world_state_payload(&rx0);
+        let state1 = world_state_payload(&rx1);
+        let address0: *const String = &*state0;
+        let address1: *const String = &*state1;
+        assert_eq!(address0, address1);
+
+        // A player joining changes the state, so it's serialised anew, then shared with the next joiner
+        let rx2 = connect(&mut state, 1);
+        let (tx3, rx3) = mpsc::channel();
+        state.process_websocket_event(WebSocketEvent::SpectatorCreated { client: Client::new(2, tx3) });
+
+        let state2 = world_state_payload(&rx2);
+        let state3 = world_state_payload(&rx3);
+        let address2: *const String = &*state2;
+        let address3: *const String = &*state3;
+        assert!(address2 != address0);
+        assert_eq!(address2, address3);
+        match str::parse(&state2).unwrap() {
+            Message::WorldState { player_count, .. } => assert_eq!(player_count, 2),
+            other => panic!("Expected a world_state, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn new_client_is_welcomed_with_configured_sizes_and_speeds() {
+        let mut state = GameState::new(Options {
+            player_size: 20.0,
+            bullet_size: 8.0,
+            ..Options::default()
+        });
+        let rx = connect(&mut state, 0);
+
+        assert_eq!(received(&rx)[0],
+                   Message::Welcome {
+                       id: 0,
+                       speed: PLAYER_SPEED,
+                       size: 20.0,
+                       bullet_speed: BULLET_SPEED,
+                       bullet_size: 8.0,
+                       features: vec![],
+                   });
+
+        let player = state.players[&0];
+        assert!(player.x >= 20.0 && player.x <= MAP_WIDTH - 20.0);
+        assert!(player.y >= 20.0 && player.y <= MAP_HEIGHT - 20.0);
+    }
+
+    #[test]
+    fn enabled_features_are_advertised() {
+        let mut state = GameState::new(Options {
+            require_ready: true,
+            round_length: Some(100),
+            ..Options::default()
+        });
+        let rx = connect(&mut state, 0);
+
+        match received(&rx)[0] {
+            Message::Welcome { ref features, .. } => assert_eq!(*features, vec!["ready".to_string(), "rounds".to_string()]),
+            ref message => panic!("Expected welcome, got {:?}", message),
+        }
+    }
+
+    #[test]
+    fn larger_players_are_hit_from_further_away() {
+        for &(player_size, hit) in &[(10.0, false), (20.0, true)] {
+            let mut state = GameState::new(Options { player_size: player_size, ..Options::default() });
+            let _rx = connect(&mut state, 0);
+            place_player(&mut state, 0, 250.0, 250.0);
+            let _ = state.bullets.insert(0,
+                                         OwnedBullet::new(Bullet::not_moving(0, 250.0, 250.0 + 20.0), 1));
+
+            state.process_game_update();
+
+            assert_eq!(state.bullets.is_empty(), hit);
+        }
+    }
+
+    #[test]
+    fn corner_hits_only_square_players() {
+        for &(collision_shape, hit) in &[(CollisionShape::Circle, false), (CollisionShape::Square, true)] {
+            let mut state = GameState::new(Options { collision_shape: collision_shape, ..Options::default() });
+            let _rx = connect(&mut state, 0);
+            place_player(&mut state, 0, 250.0, 250.0);
+            let _ = state.bullets.insert(0,
+                                         OwnedBullet::new(Bullet::not_moving(0, 250.0 + 12.0, 250.0 + 12.0), 1));
+
+            state.process_game_update();
+
+            assert_eq!(state.bullets.is_empty(), hit);
+        }
+    }
+
+    #[test]
+    fn firing_past_bullet_cap_evicts_oldest() {
+        let mut state = GameState::new(Options { max_bullets: Some(3), ..Options::default() });
+        let rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 250.0);
+        let _ = received(&rx);
+
+        for _ in 0..4 {
+            state.process_client_message(0,
+                                         Message::Fire {
+                                             move_x: 1.0,
+                                             move_y: 0.0,
+                                         });
+        }
+
+        let mut bullet_ids = state.bullets.keys().cloned().collect::<Vec<_>>();
+        bullet_ids.sort();
+        assert_eq!(bullet_ids, vec![1, 2, 3]);
+        assert_eq!(received(&rx)
+                       .into_iter()
+                       .filter(|message| match message {
+                           &Message::BulletDestroyed { .. } => true,
+                           _ => false,
+                       })
+                       .collect::<Vec<_>>(),
+                   vec![Message::BulletDestroyed { id: 0 }]);
+    }
+
+    #[test]
+    fn firing_during_cooldown_is_suppressed() {
+        let mut state = GameState::new(Options { fire_cooldown: Some(10), ..Options::default() });
+        let rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 250.0);
+        let _ = received(&rx);
+
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        assert!(received(&rx).contains(&Message::FireCooldown { ready_in_ticks: 10 }));
+
+        for _ in 0..3 {
+            state.process_game_update();
+        }
+        let _ = received(&rx);
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+
+        assert_eq!(state.bullets.len(), 1);
+        assert_eq!(received(&rx), vec![Message::FireCooldown { ready_in_ticks: 7 }]);
+    }
+
+    #[test]
+    fn paused_game_does_not_move_but_accepts_joiners() {
+        let mut state = GameState::new(Options::default());
+        let rx0 = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 250.0);
+        state.process_client_message(0,
+                                     Message::StartMoving {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+
+        state.process_websocket_event(WebSocketEvent::SetPaused { paused: true });
+        assert!(state.is_paused());
+        assert_eq!(received(&rx0).last(), Some(&Message::GamePaused { paused: true }));
+
+        let tick = state.current_tick();
+        let position = (state.players[&0].x, state.players[&0].y);
+        let rx1 = connect(&mut state, 1);
+        for _ in 0..10 {
+            state.process_game_update();
+        }
+        assert_eq!((state.players[&0].x, state.players[&0].y), position);
+        assert_eq!(state.current_tick(), tick);
+        assert!(state.players.contains_key(&1));
+        assert!(received(&rx1).contains(&Message::GamePaused { paused: true }));
+
+        state.process_websocket_event(WebSocketEvent::SetPaused { paused: false });
+        state.process_game_update();
+        assert!(state.players[&0].x > position.0);
+        assert!(received(&rx0).contains(&Message::GamePaused { paused: false }));
+    }
+
+    #[test]
+    fn game_is_paused_only_with_admin_password() {
+        for &admin_password in &[None, Some("hunter2")] {
+            let mut state = GameState::new(Options { admin_password: admin_password.map(str::to_string), ..Options::default() });
+            let rx = connect(&mut state, 0);
+            let _ = received(&rx);
+
+            for password in vec!["", "guess"] {
+                state.process_client_message(0,
+                                             Message::SetPaused {
+                                                 paused: true,
+                                                 password: password.to_string(),
+                                             });
+                assert!(!state.is_paused());
+                assert_eq!(received(&rx), vec![Message::Nack { reason: "Not allowed to pause".to_string() }]);
+            }
+
+            if let Some(admin_password) = admin_password {
+                state.process_client_message(0,
+                                             Message::SetPaused {
+                                                 paused: true,
+                                                 password: admin_password.to_string(),
+                                             });
+                assert!(state.is_paused());
+                assert_eq!(received(&rx), vec![Message::GamePaused { paused: true }]);
+            }
+        }
+    }
+
+    #[test]
+    fn inputs_are_refused_while_paused() {
+        let mut state = GameState::new(Options::default());
+        let rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 250.0);
+        state.set_paused(true);
+        let _ = received(&rx);
+
+        state.process_client_message(0,
+                                     Message::StartMoving {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+
+        let nack = Message::Nack { reason: "Game paused".to_string() };
+        assert_eq!(received(&rx), vec![nack.clone(), nack]);
+        assert_eq!(state.bullet_count(), 0);
+
+        state.set_paused(false);
+        state.process_game_update();
+        assert_eq!((state.players[&0].x, state.players[&0].y), (250.0, 250.0));
+    }
+
+    #[test]
+    fn lagging_shooter_hits_where_target_was_only_if_compensated() {
+        assert!(lagging_shot_hits(Some(10)));
+        assert!(!lagging_shot_hits(None));
+    }
+
+    #[test]
+    fn shots_fired_carries_tick_of_firing() {
+        let mut state = GameState::new(Options::default());
+        let rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 250.0);
+        for _ in 0..5 {
+            state.process_game_update();
+        }
+        let _ = received(&rx);
+
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+
+        // Preceded by player_facing, as the player turned to fire
+        match received(&rx).last() {
+            Some(&Message::ShotsFired { tick, .. }) => assert_eq!(tick, state.current_tick()),
+            message => panic!("Expected shots_fired, got {:?}", message),
+        }
+    }
+
+    #[test]
+    fn firing_after_cooldown_succeeds() {
+        let mut state = GameState::new(Options { fire_cooldown: Some(2), ..Options::default() });
+        let _rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 250.0);
+
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        for _ in 0..2 {
+            state.process_game_update();
+        }
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+
+        assert_eq!(state.bullets.len(), 2);
+    }
+
+    #[test]
+    fn firing_into_adjacent_wall_is_blocked() {
+        let mut state = GameState::new(Options::default());
+        let rx = connect(&mut state, 0);
+        let player_radius = state.options.player_size;
+        place_player(&mut state, 0, MAP_WIDTH - player_radius, 250.0);
+        let _ = received(&rx);
+
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        assert!(state.bullets.is_empty());
+        assert_eq!(received(&rx).last(), Some(&Message::ShotBlocked));
+
+        // Firing away from the wall still works
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: -1.0,
+                                         move_y: 0.0,
+                                     });
+        assert_eq!(state.bullets.len(), 1);
+        state.process_game_update();
+        assert_eq!(state.bullets.len(), 1);
+    }
+
+    #[test]
+    fn firing_past_bullet_cap_can_be_rejected() {
+        let mut state = GameState::new(Options {
+            max_bullets: Some(3),
+            bullet_cap_policy: BulletCapPolicy::Reject,
+            ..Options::default()
+        });
+        let _rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 250.0);
+
+        for _ in 0..4 {
+            state.process_client_message(0,
+                                         Message::Fire {
+                                             move_x: 1.0,
+                                             move_y: 0.0,
+                                         });
+        }
+
+        let mut bullet_ids = state.bullets.keys().cloned().collect::<Vec<_>>();
+        bullet_ids.sort();
+        assert_eq!(bullet_ids, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn time_sync_request_is_answered_to_sender() {
+        let mut state = GameState::new(Options::default());
+        let rx0 = connect(&mut state, 0);
+        let rx1 = connect(&mut state, 1);
+        let _ = received(&rx0);
+        let _ = received(&rx1);
+
+        state.process_client_message(0, Message::TimeSyncRequest { client_time: 1234.5 });
+
+        let messages = received(&rx0);
+        assert_eq!(messages.len(), 1);
+        match messages[0] {
+            Message::TimeSyncResponse { client_time, server_time } => {
+                assert_eq!(client_time, 1234.5);
+                assert!(server_time >= 0.0);
+            }
+            ref message => panic!("Expected a time_sync_response, got {:?}", message),
+        }
+        assert_eq!(received(&rx1), vec![]);
+    }
+
+    #[test]
+    fn mover_does_not_receive_own_movement_when_suppressed() {
+        let mut state = GameState::new(Options { suppress_movement_echo: true, ..Options::default() });
+        let rx0 = connect(&mut state, 0);
+        let rx1 = connect(&mut state, 1);
+        let _ = received(&rx0);
+        let _ = received(&rx1);
+
+        state.process_client_message(0,
+                                     Message::StartMoving {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        state.process_client_message(0, Message::StopMoving);
+
+        assert_eq!(received(&rx0), vec![]);
+        assert_eq!(received(&rx1).len(), 2);
+    }
+
+    #[test]
+    fn mover_receives_own_movement_by_default() {
+        let mut state = GameState::new(Options::default());
+        let rx0 = connect(&mut state, 0);
+        let _ = received(&rx0);
+
+        state.process_client_message(0,
+                                     Message::StartMoving {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+
+        assert_eq!(received(&rx0).len(), 1);
+    }
+
+    #[test]
+    fn repeated_violations_kick() {
+        let mut state = GameState::new(Options { kick_threshold: Some(3), ..Options::default() });
+        let rx = connect(&mut state, 0);
+        let _ = received(&rx);
+
+        state.process_client_message(0, Message::PlayerJoined { id: 0 });
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 100.0,
+                                         move_y: 0.0,
+                                     });
+        let _ = received(&rx);
+        state.process_client_message(0,
+                                     Message::StartMoving {
+                                         move_x: 0.0,
+                                         move_y: -50.0,
+                                     });
+
+        let messages = received(&rx);
+        assert_eq!(messages.last(),
+                   Some(&Message::GoAway { reason: "Too many improbable inputs".to_string() }));
+        assert_eq!(state.violations[&0], 3);
+    }
+
+    #[test]
+    fn honest_inputs_are_not_violations() {
+        let mut state = GameState::new(Options { kick_threshold: Some(1), ..Options::default() });
+        let _rx = connect(&mut state, 0);
+
+        state.process_client_message(0,
+                                     Message::StartMoving {
+                                         move_x: 0.6,
+                                         move_y: 0.8,
+                                     });
+        state.process_client_message(0, Message::StopMoving);
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+
+        assert!(!state.violations.contains_key(&0));
+    }
+
+    #[test]
+    fn appearance_is_broadcast_and_sent_to_later_clients() {
+        let mut state = GameState::new(Options::default());
+        let rx0 = connect(&mut state, 0);
+        let rx1 = connect(&mut state, 1);
+        let _ = received(&rx0);
+        let _ = received(&rx1);
+
+        state.process_client_message(0, Message::SetAppearance { color: 0xFF8000 });
+
+        let appearance = Message::PlayerAppearance {
+            id: 0,
+            color: 0xFF8000,
+        };
+        assert_eq!(received(&rx0), vec![appearance.clone()]);
+        assert_eq!(received(&rx1), vec![appearance.clone()]);
+
+        let rx2 = connect(&mut state, 2);
+        assert!(received(&rx2).contains(&appearance));
+    }
+
+    #[test]
+    fn emote_is_broadcast_with_sender_id() {
+        let mut state = GameState::new(Options::default());
+        let rx0 = connect(&mut state, 0);
+        let rx1 = connect(&mut state, 1);
+        let _ = received(&rx0);
+        let _ = received(&rx1);
+
+        state.process_client_message(0, Message::Emote { id: Some(1), emote: 3 });
+        state.process_client_message(0, Message::Emote { id: None, emote: 4 });
+
+        let emote = Message::Emote { id: Some(0), emote: 3 };
+        let id_filled_in = Message::Emote { id: Some(0), emote: 4 };
+        assert_eq!(received(&rx0), vec![emote.clone(), id_filled_in.clone()]);
+        assert_eq!(received(&rx1), vec![emote, id_filled_in]);
+    }
+
+    #[test]
+    fn out_of_range_emote_is_refused() {
+        let mut state = GameState::new(Options { kick_threshold: Some(1), ..Options::default() });
+        let rx0 = connect(&mut state, 0);
+        let rx1 = connect(&mut state, 1);
+        let _ = received(&rx0);
+        let _ = received(&rx1);
+
+        state.process_client_message(0, Message::Emote { id: None, emote: EMOTE_COUNT });
+
+        assert_eq!(received(&rx0),
+                   vec![Message::Nack { reason: format!("emote {} out of {}", EMOTE_COUNT, EMOTE_COUNT) }]);
+        assert!(received(&rx1).is_empty());
+        assert_eq!(state.violations.get(&0), None);
+    }
+
+    #[test]
+    fn dropping_sends_configured_shutdown_reason() {
+        let mut state = GameState::new(Options { shutdown_reason: "Restarting for maintenance".to_string(), ..Options::default() });
+        let rx0 = connect(&mut state, 0);
+        let rx1 = connect(&mut state, 1);
+        let _ = received(&rx0);
+        let _ = received(&rx1);
+
+        drop(state);
+
+        let go_away = Message::GoAway { reason: "Restarting for maintenance".to_string() };
+        assert_eq!(received(&rx0), vec![go_away.clone()]);
+        assert_eq!(received(&rx1), vec![go_away]);
+    }
+
+    #[test]
+    fn dropping_with_disconnected_clients_does_not_panic() {
+        let mut state = GameState::new(Options::default());
+        let rx = connect(&mut state, 0);
+        drop(rx);
+
+        drop(state);
+    }
+
+    #[test]
+    fn summary_reflects_current_counts() {
+        let mut state = GameState::new(Options::default());
+        assert_eq!(state.summary(), "tick 0, clients 0, players 0, bullets 0");
+
+        let _rx0 = connect(&mut state, 0);
+        let _rx1 = connect(&mut state, 1);
+        place_player(&mut state, 0, 100.0, 100.0);
+        place_player(&mut state, 1, 400.0, 400.0);
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 0.0,
+                                         move_y: 1.0,
+                                     });
+        state.process_game_update();
+        state.process_websocket_event(WebSocketEvent::ClientClosed { client_id: 1 });
+        state.process_game_update();
+
+        assert_eq!(state.summary(), "tick 2, clients 1, players 1, bullets 1");
+        assert_eq!(format!("{}", state), state.summary());
+    }
+
+    #[test]
+    fn round_ends_at_its_duration_and_next_starts_with_reset_scores() {
+        let mut state = GameState::new(Options { round_length: Some(100), ..Options::default() });
+        let rx0 = connect(&mut state, 0);
+        let _rx1 = connect(&mut state, 1);
+        assert!(received(&rx0).contains(&Message::RoundStart {
+            round: 1,
+            duration_ticks: 100,
+        }));
+
+        kill(&mut state, 1, 0);
+        while state.current_tick() < 99 {
+            state.process_game_update();
+        }
+        assert!(received(&rx0).iter().all(|m| match m {
+            &Message::RoundEnd { .. } => false,
+            _ => true,
+        }));
+
+        state.process_game_update();
+        let messages = received(&rx0);
+        assert!(messages.contains(&Message::RoundEnd {
+            round: 1,
+            scores: vec![Score::new(0, 1), Score::new(1, 0)],
+        }));
+        assert!(messages.contains(&Message::RoundStart {
+            round: 2,
+            duration_ticks: 100,
+        }));
+        assert!(state.scores.is_empty());
+        assert!(state.players.contains_key(&0));
+        assert!(state.players.contains_key(&1));
+    }
+
+    #[test]
+    fn joining_client_is_told_the_rest_of_the_round() {
+        let mut state = GameState::new(Options { round_length: Some(100), ..Options::default() });
+        for _ in 0..30 {
+            state.process_game_update();
+        }
+
+        let rx = connect(&mut state, 0);
+        assert!(received(&rx).contains(&Message::RoundStart {
+            round: 1,
+            duration_ticks: 70,
+        }));
+    }
+
+    #[test]
+    fn is_in_bounds_inside_map() {
+        let state = GameState::new(Options::default());
+        assert!(state.is_in_bounds(MAP_WIDTH / 2.0, MAP_HEIGHT / 2.0));
+    }
+
+    #[test]
+    fn is_in_bounds_on_map_edge() {
+        let state = GameState::new(Options::default());
+        assert!(state.is_in_bounds(0.0, 0.0));
+        assert!(state.is_in_bounds(MAP_WIDTH, MAP_HEIGHT));
+        assert!(state.is_in_bounds(MAP_WIDTH, 0.0));
+    }
+
+    #[test]
+    fn is_in_bounds_outside_map() {
+        let state = GameState::new(Options::default());
+        assert!(!state.is_in_bounds(-0.5, MAP_HEIGHT / 2.0));
+        assert!(!state.is_in_bounds(MAP_WIDTH / 2.0, MAP_HEIGHT + 0.5));
+    }
+
+    #[test]
+    fn current_tick_advances_once_per_update() {
+        let mut state = GameState::new(Options::default());
+        assert_eq!(state.current_tick(), 0);
+
+        for tick in 1..11 {
+            state.process_game_update();
+            assert_eq!(state.current_tick(), tick);
+        }
+    }
+
+    #[test]
+    fn full_update_sent_every_configured_amount_of_ticks() {
+        let mut state = GameState::new(Options::default());
+        let rx = connect(&mut state, 0);
+        let _ = received(&rx);
+
+        for _ in 0..TICKS_BETWEEN_FULL_UPDATES - 1 {
+            state.process_game_update();
+            state.send_state_updates();
+        }
+        assert_eq!(received(&rx), vec![]);
+
+        state.process_game_update();
+        state.send_state_updates();
+        assert_eq!(received(&rx), vec![state.serialize()]);
+    }
+
+    #[test]
+    fn full_updates_are_culled_to_view_radius() {
+        let mut state = GameState::new(Options { view_radius: Some(100.0), ..Options::default() });
+        let rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 100.0, 100.0);
+        let _ = state.bullets.insert(0, OwnedBullet::new(Bullet::not_moving(0, 150.0, 100.0), 1));
+        let _ = state.bullets.insert(1, OwnedBullet::new(Bullet::not_moving(1, 400.0, 400.0), 1));
+        let _ = received(&rx);
+
+        state.tick = TICKS_BETWEEN_FULL_UPDATES;
+        state.send_state_updates();
+
+        match received(&rx).last() {
+            Some(&Message::WorldState { player_count, ref alive_players, ref alive_bullets }) => {
+                assert_eq!(player_count, 1);
+                assert_eq!(alive_players.len(), 1);
+                assert_eq!(alive_bullets.iter().map(|bullet| bullet.id).collect::<Vec<_>>(), vec![0]);
+            }
+            other => panic!("Expected a world_state, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn full_updates_can_be_disabled_except_for_joiners() {
+        let mut state = GameState::new(Options { full_updates: false, ..Options::default() });
+        let rx0 = connect(&mut state, 0);
+        let _ = received(&rx0);
+
+        for _ in 0..TICKS_BETWEEN_FULL_UPDATES * 3 {
+            state.process_game_update();
+            state.send_state_updates();
+        }
+        assert!(received(&rx0).iter().all(|m| match m {
+            &Message::WorldState { .. } => false,
+            _ => true,
+        }));
+
+        let rx1 = connect(&mut state, 1);
+        assert!(received(&rx1).iter().any(|m| match m {
+            &Message::WorldState { player_count, .. } => player_count == 2,
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn bullets_fly_through_players_without_combat() {
+        let mut state = GameState::new(Options { combat: false, ..Options::default() });
+        let _rx0 = connect(&mut state, 0);
+        let rx1 = connect(&mut state, 1);
+        place_player(&mut state, 0, 100.0, 100.0);
+        place_player(&mut state, 1, 135.0, 100.0);
+        let _ = received(&rx1);
+
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        for _ in 0..30 {
+            state.process_game_update();
+        }
+
+        assert!(state.players.contains_key(&1));
+        assert_eq!(state.health.get(&1), Some(&MAX_HEALTH));
+        let messages = received(&rx1);
+        assert!(messages.iter().any(|m| match m {
+            &Message::ShotsFired { id, .. } => id == 0,
+            _ => false,
+        }));
+        assert!(messages.iter().all(|m| match m {
+            &Message::PlayerDestroyed { .. } => false,
+            _ => true,
+        }));
+        assert_eq!(state.bullets.values().next().map(|bullet| bullet.bullet.x > 135.0), Some(true));
+    }
+
+    #[test]
+    fn deferred_joiner_gets_state_on_next_full_update() {
+        let mut state = GameState::new(Options { defer_join_state: true, ..Options::default() });
+        let rx0 = connect(&mut state, 0);
+        assert!(received(&rx0).iter().all(|m| match m {
+            &Message::WorldState { .. } => false,
+            _ => true,
+        }));
+
+        for _ in 0..TICKS_BETWEEN_FULL_UPDATES {
+            state.process_game_update();
+            state.send_state_updates();
+        }
+        assert!(received(&rx0).iter().any(|m| match m {
+            &Message::WorldState { player_count, .. } => player_count == 1,
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn overlong_shutdown_reason_is_invalid() {
+        let reason = iter::repeat('a').take(MAX_REASON_LENGTH + 1).collect();
+        let state = GameState::new(Options { shutdown_reason: reason, ..Options::default() });
+        assert!(state.validate().is_err());
+    }
+
+    #[test]
+    fn deferred_join_state_without_full_updates_is_invalid() {
+        let state = GameState::new(Options { defer_join_state: true, full_updates: false, ..Options::default() });
+        assert!(state.validate().is_err());
+    }
+
+    #[test]
+    fn adaptive_full_update_interval_lengthens_with_player_count() {
+        let mut state = GameState::new(Options { adaptive_full_updates: Some((60, 1200)), ..Options::default() });
+        let mut intervals = Vec::new();
+        for id in 0..60 {
+            let _ = connect(&mut state, id);
+            intervals.push(state.full_update_interval());
+        }
+
+        assert_eq!(intervals[0], 60);
+        assert!(intervals.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", intervals);
+        assert!(intervals[19] > intervals[9], "{:?}", intervals);
+        assert_eq!(intervals[59], 1200);
+    }
+
+    #[test]
+    fn invalid_adaptive_full_updates_are_invalid() {
+        for &(range, full_updates) in &[((0, 10), true), ((20, 10), true), ((10, 20), false)] {
+            let state = GameState::new(Options {
+                adaptive_full_updates: Some(range),
+                full_updates: full_updates,
+                ..Options::default()
+            });
+            assert!(state.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn client_never_ready_never_spawns() {
+        let mut state = GameState::new(Options { require_ready: true, ..Options::default() });
+        let rx = connect(&mut state, 0);
+
+        for _ in 0..TICKS_BETWEEN_FULL_UPDATES {
+            state.process_game_update();
+        }
+        state.send_state_updates();
+
+        let world_states = received(&rx)
+                               .into_iter()
+                               .filter(|message| match message {
+                                   &Message::WorldState { .. } => true,
+                                   _ => false,
+                               })
+                               .collect::<Vec<_>>();
+        assert_eq!(world_states.len(), 2);
+        for world_state in world_states {
+            match world_state {
+                Message::WorldState { player_count, alive_players, .. } => {
+                    assert_eq!(player_count, 1);
+                    assert!(alive_players.is_empty());
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    #[test]
+    fn ready_client_spawns_once() {
+        let mut state = GameState::new(Options { require_ready: true, ..Options::default() });
+        let rx = connect(&mut state, 0);
+        assert!(!state.players.contains_key(&0));
+        let _ = received(&rx);
+
+        state.process_client_message(0, Message::Ready);
+        state.process_client_message(0, Message::Ready);
+
+        let spawns = received(&rx)
+                         .into_iter()
+                         .filter(|message| match message {
+                             &Message::PlayerSpawned { .. } => true,
+                             _ => false,
+                         })
+                         .count();
+        assert_eq!(spawns, 1);
+        assert!(state.players.contains_key(&0));
+    }
+
+    #[test]
+    fn plausible_position_report_is_accepted() {
+        let mut state = GameState::new(Options { client_positions: true, ..Options::default() });
+        let rx = connect(&mut state, 0);
+        report_position(&mut state, 0, 250.0, 250.0);
+        let _ = received(&rx);
+
+        for _ in 0..10 {
+            state.process_game_update();
         }
+        state.process_client_message(0, Message::PositionReport { x: 260.0, y: 255.0 });
+
+        assert_eq!((state.players[&0].x, state.players[&0].y), (260.0, 255.0));
+        assert_eq!(received(&rx), vec![]);
     }
 
-    /// Moves a player along one axis based on its current position and movement vector.
-    ///
-    /// Returns whether the player crashed into a wall during movement.
-    fn move_player(pos: &mut f32, mov: Option<f32>) -> bool {
-        let new_pos = *pos + mov.unwrap_or(0.0) * PLAYER_SPEED;
-        *pos = new_pos.max(PLAYER_RADIUS)
-                      .min(MAP_WIDTH - PLAYER_RADIUS);
+    #[test]
+    fn teleport_hack_position_report_is_corrected() {
+        let mut state = GameState::new(Options { client_positions: true, ..Options::default() });
+        let rx = connect(&mut state, 0);
+        report_position(&mut state, 0, 250.0, 250.0);
+        let _ = received(&rx);
 
-        new_pos < PLAYER_RADIUS || new_pos > MAP_WIDTH - PLAYER_RADIUS
+        state.process_game_update();
+        state.process_client_message(0, Message::PositionReport { x: 450.0, y: 50.0 });
+
+        assert_eq!((state.players[&0].x, state.players[&0].y), (250.0, 250.0));
+        assert_eq!(received(&rx),
+                   vec![Message::PlayerTeleported {
+                            id: 0,
+                            x: 250.0,
+                            y: 250.0,
+                        }]);
     }
-}
 
-impl Drop for GameState {
-    fn drop(&mut self) {
-        self.send_to_everybody(message::Message::GoAway {
-            reason: "Server termination".to_string(),
+    #[test]
+    fn position_reports_are_ignored_unless_enabled() {
+        let mut state = GameState::new(Options::default());
+        let _rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 250.0);
+
+        state.process_game_update();
+        state.process_client_message(0, Message::PositionReport { x: 251.0, y: 250.0 });
+
+        assert_eq!((state.players[&0].x, state.players[&0].y), (250.0, 250.0));
+    }
+
+    #[test]
+    fn flooding_client_does_not_starve_others() {
+        let mut state = GameState::new(Options { max_inputs_per_tick: Some(2), ..Options::default() });
+        let _rx0 = connect(&mut state, 0);
+        let _rx1 = connect(&mut state, 1);
+        place_player(&mut state, 0, 100.0, 100.0);
+        place_player(&mut state, 1, 400.0, 400.0);
+
+        let (events_tx, events_rx) = mpsc::channel();
+        for _ in 0..50 {
+            events_tx.send(WebSocketEvent::ClientMessage {
+                         client_id: 0,
+                         message: Message::StartMoving {
+                             move_x: 1.0,
+                             move_y: 0.0,
+                         },
+                         seq: None,
+                     })
+                     .unwrap();
+        }
+        events_tx.send(WebSocketEvent::ClientMessage {
+                     client_id: 1,
+                     message: Message::StartMoving {
+                         move_x: 0.0,
+                         move_y: 1.0,
+                     },
+                     seq: None,
+                 })
+                 .unwrap();
+        let _ = state.process_websocket_events(&events_rx);
+
+        assert_eq!((state.players[&1].move_x, state.players[&1].move_y), (Some(0.0), Some(1.0)));
+        assert_eq!(state.input_queues[&0].len(), 48);
+        assert!(!state.input_queues.contains_key(&1));
+    }
+
+    #[test]
+    fn overflowing_input_queue_drops_oldest_inputs() {
+        let mut state = GameState::new(Options { max_inputs_per_tick: Some(1), ..Options::default() });
+        let _rx = connect(&mut state, 0);
+
+        for i in 0..MAX_QUEUED_INPUTS + 1 {
+            state.process_websocket_event(WebSocketEvent::ClientMessage {
+                client_id: 0,
+                message: Message::StartMoving {
+                    move_x: i as f32 / 100.0,
+                    move_y: 0.0,
+                },
+                seq: None,
+            });
+        }
+
+        assert_eq!(state.input_queues[&0].len(), MAX_QUEUED_INPUTS);
+        assert_eq!(state.input_queues[&0].front(),
+                   Some(&(Message::StartMoving {
+                       move_x: 0.01,
+                       move_y: 0.0,
+                   },
+                          None)));
+    }
+
+    #[test]
+    fn processed_inputs_are_acknowledged_once_per_tick() {
+        let mut state = GameState::new(Options { max_inputs_per_tick: Some(2), ..Options::default() });
+        let rx = connect(&mut state, 0);
+        let _ = received(&rx);
+
+        let (events_tx, events_rx) = mpsc::channel();
+        for seq in 1..4 {
+            events_tx.send(WebSocketEvent::ClientMessage {
+                         client_id: 0,
+                         message: Message::StopMoving,
+                         seq: Some(seq),
+                     })
+                     .unwrap();
+        }
+        let acks = |state: &mut GameState| {
+            let _ = state.process_websocket_events(&events_rx);
+            state.process_game_update();
+            received(&rx)
+                .into_iter()
+                .filter(|m| match m {
+                    &Message::InputAck { .. } => true,
+                    _ => false,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(acks(&mut state), vec![Message::InputAck { seq: 2 }]);
+        assert_eq!(acks(&mut state), vec![Message::InputAck { seq: 3 }]);
+        assert_eq!(acks(&mut state), vec![]);
+    }
+
+    #[test]
+    fn merged_movement_is_broadcast_once_per_tick() {
+        let mut state = GameState::new(Options { merge_movement: true, ..Options::default() });
+        let rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 250.0);
+        let _ = received(&rx);
+
+        state.process_client_message(0,
+                                     Message::StartMoving {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        state.process_client_message(0,
+                                     Message::StartMoving {
+                                         move_x: 0.0,
+                                         move_y: 1.0,
+                                     });
+        state.process_client_message(0,
+                                     Message::StartMoving {
+                                         move_x: -1.0,
+                                         move_y: 0.0,
+                                     });
+        assert_eq!(received(&rx), vec![]);
+
+        state.process_game_update();
+        assert_eq!(received(&rx),
+                   vec![Message::PlayerMoving {
+                            id: 0,
+                            x: 250.0,
+                            y: 250.0,
+                            move_x: -1.0,
+                            move_y: 0.0,
+                        }]);
+        assert_eq!(state.players[&0].x, 250.0 - PLAYER_SPEED);
+    }
+
+    #[test]
+    fn merged_stop_overrides_earlier_start() {
+        let mut state = GameState::new(Options { merge_movement: true, ..Options::default() });
+        let rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 250.0);
+        let _ = received(&rx);
+
+        state.process_client_message(0,
+                                     Message::StartMoving {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        state.process_client_message(0, Message::StopMoving);
+        state.process_game_update();
+
+        assert_eq!(received(&rx),
+                   vec![Message::PlayerStopped {
+                            id: 0,
+                            x: 250.0,
+                            y: 250.0,
+                        }]);
+        assert_eq!(state.players[&0].x, 250.0);
+    }
+
+    #[test]
+    fn standing_player_announces_where_it_faces() {
+        let mut state = GameState::new(Options::default());
+        let rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 250.0);
+        let _ = received(&rx);
+
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 0.0,
+                                         move_y: 1.0,
+                                     });
+        assert_eq!(received(&rx).first(),
+                   Some(&Message::PlayerFacing {
+                       id: 0,
+                       facing_x: 0.0,
+                       facing_y: 1.0,
+                   }));
+
+        // Facing the same way again isn't news
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 0.0,
+                                         move_y: 1.0,
+                                     });
+        assert!(received(&rx).iter().all(|message| match message {
+            &Message::PlayerFacing { .. } => false,
+            _ => true,
+        }));
+
+        match state.serialize() {
+            Message::WorldState { ref alive_players, .. } => {
+                assert_eq!((alive_players[0].facing_x, alive_players[0].facing_y), (Some(0.0), Some(1.0)));
+            }
+            other => panic!("Expected a world_state, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn stats_count_broadcast_world_states() {
+        let mut state = GameState::new(Options { debug_stats: true, ..Options::default() });
+        let rx0 = connect(&mut state, 0);
+        let _rx1 = connect(&mut state, 1);
+        let world_state_stats = |state: &mut GameState| {
+            state.process_client_message(0, Message::RequestStats);
+            match received(&rx0).pop() {
+                Some(Message::Stats { per_type }) => {
+                    per_type.into_iter()
+                            .find(|&(ref msg_type, _, _)| msg_type == "world_state")
+                            .map(|(_, count, bytes)| (count, bytes))
+                            .unwrap()
+                }
+                other => panic!("Expected stats, got {:?}", other),
+            }
+        };
+
+        let (count, bytes) = world_state_stats(&mut state);
+
+        let length = state.serialize().to_string().len() as u64;
+        for _ in 0..3 {
+            state.send_to_everybody(state.serialize());
+        }
+        assert_eq!(world_state_stats(&mut state), (count + 6, bytes + 6 * length));
+    }
+
+    #[test]
+    fn identical_states_send_identical_message_sequences() {
+        let run = || {
+            let mut state = GameState::new(Options::default());
+            let receivers: Vec<_> = (0..8).map(|id| connect(&mut state, id)).collect();
+            for id in 0..8 {
+                // Standing in a row close enough to stop each other and be pushed apart, all on the same tick
+                place_player(&mut state, id, 30.0 + 19.0 * id as f32, 100.0);
+                state.process_client_message(id,
+                                             Message::StartMoving {
+                                                 move_x: 0.0,
+                                                 move_y: -1.0,
+                                             });
+            }
+            let _ = received(&receivers[0]);
+
+            for _ in 0..3 {
+                state.process_game_update();
+            }
+            received(&receivers[0])
+        };
+
+        let messages = run();
+        let stopped_ids: Vec<_> = messages.iter()
+                                          .filter_map(|m| match m {
+                                              &Message::PlayerStopped { id, .. } => Some(id),
+                                              _ => None,
+                                          })
+                                          .collect();
+        assert!(stopped_ids.len() >= 8);
+        assert_eq!(stopped_ids[..8].to_vec(), (0..8).collect::<Vec<_>>());
+        assert_eq!(messages, run());
+    }
+
+    #[test]
+    fn player_stats_reflect_kills_and_deaths() {
+        let mut state = GameState::new(Options::default());
+        let rx0 = connect(&mut state, 0);
+        let _rx1 = connect(&mut state, 1);
+
+        kill(&mut state, 1, 0);
+        state.spawn_player(1, true);
+        kill(&mut state, 1, 0);
+        let _ = received(&rx0);
+
+        state.process_client_message(0, Message::RequestPlayerStats { id: 1 });
+        state.process_client_message(0, Message::RequestPlayerStats { id: 0 });
+        state.process_client_message(0, Message::RequestPlayerStats { id: 7 });
+        assert_eq!(received(&rx0),
+                   vec![Message::PlayerStats {
+                            id: 1,
+                            kills: 0,
+                            deaths: 2,
+                            health: 0.0,
+                        },
+                        Message::PlayerStats {
+                            id: 0,
+                            kills: 2,
+                            deaths: 0,
+                            health: MAX_HEALTH,
+                        },
+                        Message::Nack { reason: "player 7 not connected".to_string() }]);
+    }
+
+    #[test]
+    fn denormalised_outgoing_vectors_are_caught_under_strict_mode() {
+        let denormalised = Message::PlayerMoving {
+            id: 0,
+            x: 100.0,
+            y: 100.0,
+            move_x: 0.5,
+            move_y: 0.0,
+        };
+        let normalised = Message::PlayerMoving {
+            id: 0,
+            x: 100.0,
+            y: 100.0,
+            move_x: 0.6,
+            move_y: 0.8,
+        };
+
+        let state = GameState::new(Options { strict: true, ..Options::default() });
+        assert!(state.check_outgoing(&denormalised).is_err());
+        assert!(state.check_outgoing(&normalised).is_ok());
+
+        let state = GameState::new(Options::default());
+        assert!(state.check_outgoing(&denormalised).is_ok());
+    }
+
+    #[test]
+    fn kill_streak_is_announced_and_reset_by_death() {
+        let mut state = GameState::new(Options { kill_streak_window: Some(600), ..Options::default() });
+        let rx0 = connect(&mut state, 0);
+        let _rx1 = connect(&mut state, 1);
+        let _rx2 = connect(&mut state, 2);
+        place_player(&mut state, 2, 400.0, 400.0);
+
+        kill(&mut state, 1, 0);
+        kill(&mut state, 2, 0);
+        state.spawn_player(1, true);
+        kill(&mut state, 1, 0);
+        assert!(received(&rx0).contains(&Message::KillStreak {
+            id: 0,
+            streak: 3,
+        }));
+
+        // Had the streak gone on, these would make it 5
+        state.spawn_player(1, true);
+        state.spawn_player(2, true);
+        place_player(&mut state, 2, 400.0, 400.0);
+        kill(&mut state, 0, 1);
+        state.spawn_player(0, true);
+        kill(&mut state, 1, 0);
+        state.spawn_player(1, true);
+        kill(&mut state, 1, 0);
+        assert!(received(&rx0).iter().all(|message| match message {
+            &Message::KillStreak { .. } => false,
+            _ => true,
+        }));
+        assert_eq!(state.kill_streaks.get(&0).map(|&(streak, _)| streak), Some(2));
+    }
+
+    #[test]
+    fn merged_movement_of_several_players_is_batched() {
+        let mut state = GameState::new(Options { merge_movement: true, ..Options::default() });
+        let rx = connect(&mut state, 0);
+        let _rx1 = connect(&mut state, 1);
+        place_player(&mut state, 0, 100.0, 100.0);
+        place_player(&mut state, 1, 400.0, 400.0);
+        let _ = received(&rx);
+
+        state.process_client_message(1,
+                                     Message::StartMoving {
+                                         move_x: 0.0,
+                                         move_y: -1.0,
+                                     });
+        state.process_client_message(0,
+                                     Message::StartMoving {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        state.process_game_update();
+
+        assert_eq!(received(&rx),
+                   vec![Message::PlayersMoving {
+                            updates: vec![Player::moving(0, 100.0, 100.0, 1.0, 0.0),
+                                          Player::moving(1, 400.0, 400.0, 0.0, -1.0)],
+                        }]);
+    }
+
+    #[test]
+    fn large_spawn_clearance_reports_overcrowding() {
+        let mut state = GameState::new(Options { spawn_clearance: Some(MAP_WIDTH + MAP_HEIGHT), ..Options::default() });
+        let _rx0 = connect(&mut state, 0);
+        assert!(state.players.contains_key(&0));
+
+        assert_eq!(state.random_free_spot(&mut thread_rng()), None);
+
+        let _rx1 = connect(&mut state, 1);
+        assert!(!state.players.contains_key(&1));
+        assert_eq!(state.dead_players.get(&1), Some(&RESPAWN_DELAY));
+    }
+
+    #[test]
+    fn spawn_clearance_below_player_size_is_invalid() {
+        let state = GameState::new(Options { spawn_clearance: Some(Options::default().player_size), ..Options::default() });
+        assert!(state.validate().is_err());
+    }
+
+    #[test]
+    fn accelerating_player_reaches_max_speed_after_ramp() {
+        let mut state = GameState::new(Options { acceleration_ticks: Some(10), ..Options::default() });
+        let rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 100.0, 250.0);
+        let _ = received(&rx);
+
+        state.process_client_message(0,
+                                     Message::StartMoving {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        assert_eq!(state.players[&0].movement(), None);
+
+        for _ in 0..9 {
+            state.process_game_update();
+            assert!(state.players[&0].move_x.unwrap() < 1.0);
+        }
+        state.process_game_update();
+        assert_eq!(state.players[&0].movement(), Some((1.0, 0.0)));
+
+        let updates = received(&rx);
+        assert!(updates.len() < 10, "{:?}", updates);
+        match updates.last() {
+            Some(&Message::PlayerMoving { move_x, move_y, .. }) => assert_eq!((move_x, move_y), (1.0, 0.0)),
+            update => panic!("Expected a final player_moving, got {:?}", update),
+        }
+    }
+
+    #[test]
+    fn accelerating_player_slows_down_to_stop() {
+        let mut state = GameState::new(Options { acceleration_ticks: Some(10), ..Options::default() });
+        let rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 100.0, 250.0);
+
+        state.process_client_message(0,
+                                     Message::StartMoving {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        for _ in 0..10 {
+            state.process_game_update();
+        }
+        let _ = received(&rx);
+
+        state.process_client_message(0, Message::StopMoving);
+        state.process_game_update();
+        assert!(state.players[&0].movement().is_some());
+
+        for _ in 0..9 {
+            state.process_game_update();
+        }
+        assert_eq!(state.players[&0].movement(), None);
+        match received(&rx).last() {
+            Some(&Message::PlayerStopped { id, .. }) => assert_eq!(id, 0),
+            update => panic!("Expected a final player_stopped, got {:?}", update),
+        }
+    }
+
+    #[test]
+    fn player_count_is_broadcast_on_join_and_leave() {
+        let mut state = GameState::new(Options::default());
+        let rx0 = connect(&mut state, 0);
+        assert_eq!(received(&rx0).last(), Some(&Message::PlayerCount { count: 1 }));
+
+        let rx1 = connect(&mut state, 1);
+        assert_eq!(received(&rx0).last(), Some(&Message::PlayerCount { count: 2 }));
+        assert_eq!(received(&rx1).last(), Some(&Message::PlayerCount { count: 2 }));
+        match state.serialize() {
+            Message::WorldState { player_count, .. } => assert_eq!(player_count, 2),
+            message => panic!("Expected world_state, got {:?}", message),
+        }
+
+        state.process_websocket_event(WebSocketEvent::ClientClosed { client_id: 1 });
+        assert_eq!(received(&rx0).last(), Some(&Message::PlayerCount { count: 1 }));
+    }
+
+    #[test]
+    fn membership_is_not_broadcast_above_threshold() {
+        let mut state = GameState::new(Options { quiet_membership_above: Some(2), ..Options::default() });
+        let rx0 = connect(&mut state, 0);
+        let _rx1 = connect(&mut state, 1);
+        let _ = received(&rx0);
+
+        let rx2 = connect(&mut state, 2);
+        assert_eq!(received(&rx0), vec![]);
+        assert!(received(&rx2).iter().any(|m| match m {
+            &Message::WorldState { player_count, ref alive_players, .. } => player_count == 3 && alive_players.len() == 3,
+            _ => false,
+        }));
+
+        state.process_websocket_event(WebSocketEvent::ClientClosed { client_id: 2 });
+        assert_eq!(received(&rx0), vec![]);
+
+        state.process_websocket_event(WebSocketEvent::ClientClosed { client_id: 1 });
+        assert_eq!(received(&rx0),
+                   vec![Message::PlayerLeft { id: 1 }, Message::PlayerCount { count: 1 }]);
+    }
+
+    #[test]
+    fn spectator_receives_broadcasts_but_is_not_a_player() {
+        let mut state = GameState::new(Options::default());
+        let (tx, spectator_rx) = mpsc::channel();
+        state.process_websocket_event(WebSocketEvent::SpectatorCreated { client: Client::new(0, tx) });
+        match received(&spectator_rx).first() {
+            Some(&Message::WorldState { player_count, .. }) => assert_eq!(player_count, 0),
+            message => panic!("Expected world_state, got {:?}", message),
+        }
+
+        let _rx = connect(&mut state, 1);
+        state.process_client_message(1,
+                                     Message::StartMoving {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        let spectated = received(&spectator_rx);
+        assert!(spectated.contains(&Message::PlayerJoined { id: 1 }));
+        assert!(spectated.contains(&Message::PlayerCount { count: 1 }));
+        assert!(spectated.iter().any(|message| match message {
+            &Message::PlayerMoving { id, .. } => id == 1,
+            _ => false,
+        }));
+        assert!(!spectated.iter().any(|message| match message {
+            &Message::Welcome { .. } => true,
+            _ => false,
+        }));
+
+        assert_eq!(state.player_count(), 1);
+        assert!(!state.players.contains_key(&0));
+
+        state.process_websocket_event(WebSocketEvent::SpectatorClosed { client_id: 0 });
+        assert!(state.spectators.is_empty());
+        assert_eq!(state.player_count(), 1);
+    }
+
+    #[test]
+    fn send_to_reaches_only_targeted_client() {
+        let mut state = GameState::new(Options::default());
+        let rx0 = connect(&mut state, 0);
+        let rx1 = connect(&mut state, 1);
+        let rx2 = connect(&mut state, 2);
+        let _ = received(&rx0);
+        let _ = received(&rx1);
+        let _ = received(&rx2);
+
+        state.send_to(1, Message::PlayerLeft { id: 5 });
+        state.send_to(3, Message::PlayerLeft { id: 6 });
+
+        assert_eq!(received(&rx0), vec![]);
+        assert_eq!(received(&rx1), vec![Message::PlayerLeft { id: 5 }]);
+        assert_eq!(received(&rx2), vec![]);
+    }
+
+    #[test]
+    fn bullet_slowed_by_drag_notifies_only_of_big_changes() {
+        let mut state = GameState::new(Options { bullet_drag: Some(0.05), ..Options::default() });
+        let rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 250.0);
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 0.0,
+                                         move_y: 1.0,
+                                     });
+        let _ = received(&rx);
+
+        let mut updates = 0;
+        for _ in 0..4 {
+            state.process_game_update();
+            updates += received(&rx).iter().filter(|m| match m {
+                &&Message::BulletMoving { .. } => true,
+                _ => false,
+            }).count();
+        }
+        assert_eq!(updates, 0);
+
+        // 0.95^5 is the first velocity at least MIN_VELOCITY_UPDATE slower than the bullet was fired at
+        state.process_game_update();
+        assert!(received(&rx).iter().any(|m| match m {
+            &Message::BulletMoving { move_y, .. } => (move_y - 0.95f32.powi(5)).abs() < 0.0001,
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn bullet_stopped_by_drag_is_announced() {
+        let mut state = GameState::new(Options { bullet_drag: Some(0.5), ..Options::default() });
+        let rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 250.0);
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        let bullet_id = state.bullets.values().next().unwrap().bullet.id;
+        let _ = received(&rx);
+
+        // 1 / 2^4 is below MIN_BULLET_VELOCITY
+        for _ in 0..4 {
+            state.process_game_update();
+        }
+        assert_eq!(state.bullet_count(), 0);
+        assert!(received(&rx).contains(&Message::BulletDestroyed { id: bullet_id }));
+    }
+
+    #[test]
+    fn bullet_slowed_by_drag_notifies() {
+        let mut state = GameState::new(Options { bullet_drag: Some(0.5), ..Options::default() });
+        let rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 250.0);
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        let _ = received(&rx);
+
+        state.process_game_update();
+        assert!(received(&rx).iter().any(|m| match m {
+            &Message::BulletMoving { move_x, move_y, .. } => move_x == 0.5 && move_y == 0.0,
+            _ => false,
+        }));
+
+        state.process_game_update();
+        assert!(received(&rx).iter().any(|m| match m {
+            &Message::BulletMoving { move_x, move_y, .. } => move_x == 0.25 && move_y == 0.0,
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn bullet_without_drag_does_not_notify() {
+        let mut state = GameState::new(Options::default());
+        let rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 250.0);
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        let _ = received(&rx);
+
+        state.process_game_update();
+
+        assert!(received(&rx).iter().all(|m| match m {
+            &Message::BulletMoving { .. } => false,
+            _ => true,
+        }));
+    }
+
+    #[test]
+    fn messages_from_unspawned_player_are_ignored() {
+        let mut state = GameState::new(Options::default());
+        let rx = connect(&mut state, 0);
+        let _ = received(&rx);
+
+        state.process_client_message(1,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        state.process_client_message(1,
+                                     Message::StartMoving {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        state.process_client_message(1, Message::StopMoving);
+
+        assert!(state.bullets.is_empty());
+        assert!(received(&rx).is_empty());
+    }
+
+    #[test]
+    fn messages_from_dead_player_are_ignored() {
+        let mut state = GameState::new(Options::default());
+        let rx = connect(&mut state, 0);
+        let _ = connect(&mut state, 1);
+        kill(&mut state, 0, 1);
+        let _ = received(&rx);
+
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+
+        assert!(received(&rx).is_empty());
+    }
+
+    #[test]
+    fn player_wraps_around_under_wrap() {
+        let mut state = GameState::new(Options { boundary_mode: BoundaryMode::Wrap, ..Options::default() });
+        let rx = connect(&mut state, 0);
+        let player_radius = state.options.player_size;
+        place_player(&mut state, 0, MAP_WIDTH - player_radius - 1.0, 250.0);
+        state.process_client_message(0,
+                                     Message::StartMoving {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        let _ = received(&rx);
+
+        state.process_game_update();
+
+        // The player reappears just as far past the opposite edge's radius, never overlapping the edge itself
+        let player = *state.players.get(&0).unwrap();
+        assert_eq!((player.x, player.y), (player_radius + PLAYER_SPEED - 1.0, 250.0));
+        assert!(player.move_x.is_some());
+        assert!(received(&rx).contains(&Message::PlayerTeleported {
+            id: 0,
+            x: player_radius + PLAYER_SPEED - 1.0,
+            y: 250.0,
+        }));
+    }
+
+    #[test]
+    fn player_stops_at_wall_under_clamp() {
+        let mut state = GameState::new(Options::default());
+        let _ = connect(&mut state, 0);
+        let player_radius = Options::default().player_size;
+        place_player(&mut state, 0, MAP_WIDTH - player_radius - 1.0, 250.0);
+        state.process_client_message(0,
+                                     Message::StartMoving {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+
+        state.process_game_update();
+
+        let player = *state.players.get(&0).unwrap();
+        assert_eq!((player.x, player.y), (MAP_WIDTH - player_radius, 250.0));
+        assert!(player.move_x.is_none());
+    }
+
+    #[test]
+    fn player_overshooting_wall_is_reported_against_it() {
+        let mut state = GameState::new(Options::default());
+        let rx = connect(&mut state, 0);
+        let player_radius = state.options.player_size;
+        place_player(&mut state, 0, 250.0, player_radius + 0.25);
+        state.process_client_message(0,
+                                     Message::StartMoving {
+                                         move_x: 0.0,
+                                         move_y: -1.0,
+                                     });
+        let _ = received(&rx);
+
+        state.process_game_update();
+
+        assert_eq!(received(&rx),
+                   vec![Message::PlayerStopped {
+                            id: 0,
+                            x: 250.0,
+                            y: player_radius,
+                        }]);
+    }
+
+    #[test]
+    fn player_slides_along_wall_under_slide() {
+        let mut state = GameState::new(Options { wall_response: WallResponse::Slide, ..Options::default() });
+        let rx = connect(&mut state, 0);
+        let player_radius = Options::default().player_size;
+        place_player(&mut state, 0, MAP_WIDTH - player_radius - 1.0, 250.0);
+        state.process_client_message(0,
+                                     Message::StartMoving {
+                                         move_x: 1.0,
+                                         move_y: 1.0,
+                                     });
+
+        state.process_game_update();
+
+        let player = *state.players.get(&0).unwrap();
+        assert_eq!((player.x, player.y), (MAP_WIDTH - player_radius, 250.0 + PLAYER_SPEED));
+        assert_eq!(player.movement(), Some((0.0, 1.0)));
+        assert!(received(&rx).contains(&Message::PlayerMoving {
+            id: 0,
+            x: MAP_WIDTH - player_radius,
+            y: 250.0 + PLAYER_SPEED,
+            move_x: 0.0,
+            move_y: 1.0,
+        }));
+
+        state.process_game_update();
+
+        let player = *state.players.get(&0).unwrap();
+        assert_eq!((player.x, player.y), (MAP_WIDTH - player_radius, 250.0 + 2.0 * PLAYER_SPEED));
+        assert!(received(&rx).iter().all(|message| match message {
+            &Message::PlayerStopped { .. } => false,
+            _ => true,
+        }));
+    }
+
+    #[test]
+    fn bullet_bounces_off_wall_under_bounce() {
+        let mut state = GameState::new(Options { boundary_mode: BoundaryMode::Bounce, ..Options::default() });
+        let _ = connect(&mut state, 0);
+        place_player(&mut state, 0, MAP_WIDTH - 20.0, 250.0);
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+
+        for _ in 0..5 {
+            state.process_game_update();
+        }
+
+        let bullet = state.bullets.values().next().unwrap().bullet;
+        assert_eq!((bullet.move_x, bullet.move_y), (Some(-1.0), Some(0.0)));
+        assert!(bullet.x <= MAP_WIDTH);
+    }
+
+    #[test]
+    fn bullet_fired_diagonally_out_of_corner_spawns_within_map() {
+        for &boundary_mode in &[BoundaryMode::Wrap, BoundaryMode::Bounce] {
+            let mut state = GameState::new(Options { boundary_mode: boundary_mode, ..Options::default() });
+            let rx = connect(&mut state, 0);
+            let player_radius = state.options.player_size;
+            place_player(&mut state, 0, player_radius, player_radius);
+            let _ = received(&rx);
+
+            state.process_client_message(0,
+                                         Message::Fire {
+                                             move_x: -0.7071,
+                                             move_y: -0.7071,
+                                         });
+
+            let bullet = state.bullets.values().next().unwrap().bullet;
+            assert!(GameState::map_contains(bullet.x, bullet.y));
+            assert_eq!((bullet.x, bullet.y), (0.0, 0.0));
+            match received(&rx).last() {
+                Some(&Message::ShotsFired { x, y, .. }) => assert_eq!((x, y), (bullet.x, bullet.y)),
+                other => panic!("Expected shots_fired, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn bullet_fired_diagonally_out_of_corner_is_blocked_under_clamp() {
+        let mut state = GameState::new(Options { boundary_mode: BoundaryMode::Clamp, ..Options::default() });
+        let rx = connect(&mut state, 0);
+        let player_radius = state.options.player_size;
+        place_player(&mut state, 0, player_radius, player_radius);
+        let _ = received(&rx);
+
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: -0.7071,
+                                         move_y: -0.7071,
+                                     });
+        assert!(state.bullets.is_empty());
+        assert_eq!(received(&rx).last(), Some(&Message::ShotBlocked));
+
+        // Firing out of the corner still works
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 0.7071,
+                                         move_y: 0.7071,
+                                     });
+        assert_eq!(state.bullets.len(), 1);
+        state.process_game_update();
+        assert_eq!(state.bullets.len(), 1);
+    }
+
+    #[test]
+    fn bullet_wraps_around_under_wrap() {
+        let mut state = GameState::new(Options { boundary_mode: BoundaryMode::Wrap, ..Options::default() });
+        let _ = connect(&mut state, 0);
+        place_player(&mut state, 0, MAP_WIDTH - 20.0, 250.0);
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+
+        for _ in 0..5 {
+            state.process_game_update();
+        }
+
+        let bullet = state.bullets.values().next().unwrap().bullet;
+        assert_eq!(bullet.move_x, Some(1.0));
+        assert!(bullet.x >= state.options.bullet_size && bullet.x < MAP_WIDTH / 2.0);
+    }
+
+    #[test]
+    fn bouncing_bullet_is_destroyed_once_out_of_range() {
+        let mut state = GameState::new(Options { boundary_mode: BoundaryMode::Bounce, ..Options::default() });
+        let rx = connect(&mut state, 0);
+        place_player(&mut state, 0, 250.0, 20.0);
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        let bullet_id = state.bullets.values().next().unwrap().bullet.id;
+        let _ = received(&rx);
+
+        let ticks_in_range = (MAX_BULLET_RANGE / BULLET_SPEED) as usize;
+        for _ in 0..ticks_in_range {
+            state.process_game_update();
+        }
+        assert_eq!(state.bullet_count(), 1);
+        assert!(!received(&rx).contains(&Message::BulletDestroyed { id: bullet_id }));
+
+        state.process_game_update();
+        state.process_game_update();
+        assert_eq!(state.bullet_count(), 0);
+        assert!(received(&rx).contains(&Message::BulletDestroyed { id: bullet_id }));
+    }
+
+    #[test]
+    fn serialization_is_reproducible_and_sorted() {
+        let mut state = GameState::new(Options::default());
+        for id in 0..10 {
+            let _ = connect(&mut state, id);
+            place_player(&mut state, id, 25.0 + 45.0 * id as f32, 250.0);
+        }
+        for id in 0..10 {
+            state.process_client_message(id,
+                                         Message::Fire {
+                                             move_x: 0.0,
+                                             move_y: 1.0,
+                                         });
+        }
+
+        assert_eq!(state.serialize().to_string(), state.serialize().to_string());
+        match state.serialize() {
+            Message::WorldState { alive_players, alive_bullets, .. } => {
+                assert_eq!(alive_players.iter().map(|p| p.id).collect::<Vec<_>>(),
+                           (0..10).collect::<Vec<_>>());
+                assert_eq!(alive_bullets.iter().map(|b| b.id).collect::<Vec<_>>(),
+                           (0..10).collect::<Vec<_>>());
+            }
+            message => panic!("Expected world_state, got {:?}", message),
+        }
+    }
+
+    #[test]
+    fn close_range_hit_deals_more_damage() {
+        let close_damage = damage_dealt_at_range(35.0);
+        let far_damage = damage_dealt_at_range(300.0);
+
+        assert!(close_damage > far_damage,
+                "close: {}, far: {}",
+                close_damage,
+                far_damage);
+    }
+
+    #[test]
+    fn hit_without_falloff_kills() {
+        let mut state = GameState::new(Options::default());
+        let _rx0 = connect(&mut state, 0);
+        let _rx1 = connect(&mut state, 1);
+
+        kill(&mut state, 1, 0);
+        assert!(state.dead_players.contains_key(&1));
+        assert!(!state.health.contains_key(&1));
+    }
+
+    #[test]
+    fn overlapping_players_are_separated() {
+        let mut state = GameState::new(Options::default());
+        let rx0 = connect(&mut state, 0);
+        let _rx1 = connect(&mut state, 1);
+        place_player(&mut state, 0, 100.0, 100.0);
+        place_player(&mut state, 1, 105.0, 100.0);
+        let _ = received(&rx0);
+
+        for _ in 0..10 {
+            state.process_game_update();
+        }
+
+        let (player0, player1) = (*state.players.get(&0).unwrap(), *state.players.get(&1).unwrap());
+        assert!(distance_between(player0.x, player0.y, player1.x, player1.y) >= 2.0 * state.options.player_size - 0.001);
+        assert!(player0.x < player1.x);
+        assert!(received(&rx0).iter().any(|m| match m {
+            &Message::PlayerStopped { id, x, .. } => id == 0 && x == player0.x,
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn moving_player_stops_just_short_of_another() {
+        let mut state = GameState::new(Options::default());
+        let rx0 = connect(&mut state, 0);
+        let _rx1 = connect(&mut state, 1);
+        // Collisions are checked one movement vector ahead, so the player stops once that would overlap
+        let gap = 2.0 * state.options.player_size + PLAYER_SPEED + 0.5;
+        place_player(&mut state, 0, 100.0, 250.0);
+        place_player(&mut state, 1, 100.0 + gap, 250.0);
+        state.players.get_mut(&0).unwrap().move_x = Some(1.0);
+        let _ = received(&rx0);
+
+        state.process_game_update();
+        assert_eq!(state.players.get(&0).unwrap().x, 100.0 + PLAYER_SPEED);
+        state.process_game_update();
+
+        let player = *state.players.get(&0).unwrap();
+        assert_eq!((player.x, player.y), (100.0 + PLAYER_SPEED, 250.0));
+        assert_eq!(player.movement(), None);
+        assert_eq!(received(&rx0),
+                   vec![Message::PlayerStopped {
+                            id: 0,
+                            x: 100.0 + PLAYER_SPEED,
+                            y: 250.0,
+                        }]);
+    }
+
+    #[test]
+    fn collision_results_match_distance_between() {
+        // Players stop exactly when the pre-Vec2 check, distance_between() one movement vector ahead, says they would overlap
+        let player_radius = Options::default().player_size;
+        let cases = [(2.0 * player_radius + 0.5, 1.0, 0.0),
+                     (2.0 * player_radius + 1.0, 1.0, 0.0),
+                     (2.0 * player_radius + 1.5, 1.0, 0.0),
+                     (2.0 * player_radius + 0.9, 0.6, 0.8),
+                     (2.0 * player_radius + 0.7, 0.7071, 0.7071)];
+
+        for &(gap, move_x, move_y) in &cases {
+            let mut state = GameState::new(Options::default());
+            let _rx0 = connect(&mut state, 0);
+            let _rx1 = connect(&mut state, 1);
+            place_player(&mut state, 0, 100.0, 250.0);
+            place_player(&mut state, 1, 100.0 + gap, 250.0);
+            {
+                let player = state.players.get_mut(&0).unwrap();
+                player.move_x = Some(move_x);
+                player.move_y = Some(move_y);
+            }
+
+            let collides = distance_between(100.0 + move_x, 250.0 + move_y, 100.0 + gap, 250.0) < 2.0 * player_radius;
+            state.process_game_update();
+            assert_eq!(!state.players[&0].is_moving(), collides, "gap {}, movement ({}, {})", gap, move_x, move_y);
+        }
+    }
+
+    #[test]
+    fn respawn_clears_nearby_bullets_if_enabled() {
+        let mut state = GameState::new(Options { respawn_bullet_clearance: Some(50.0), ..Options::default() });
+        let rx0 = connect(&mut state, 0);
+        let _rx1 = connect(&mut state, 1);
+        kill(&mut state, 1, 0);
+        for _ in 0..RESPAWN_DELAY {
+            state.process_game_update();
+        }
+
+        // Surround every possible spawn with bullets of the killer
+        for i in 0..25 {
+            for j in 0..25 {
+                let id = 1000 + i * 25 + j;
+                let bullet = Bullet::not_moving(id, 10.0 + i as f32 * 20.0, 10.0 + j as f32 * 20.0);
+                let _ = state.bullets.insert(id, OwnedBullet::new(bullet, 0));
+            }
+        }
+        let _ = received(&rx0);
+        state.process_client_message(1, Message::RequestRespawn);
+
+        let spawned = *state.players.get(&1).unwrap();
+        assert!(state.bullets.values().all(|bullet| {
+            distance_between(bullet.bullet.x, bullet.bullet.y, spawned.x, spawned.y) >= 50.0
+        }));
+        assert!(!state.bullets.is_empty());
+        assert!(received(&rx0).iter().any(|m| match m {
+            &Message::BulletDestroyed { .. } => true,
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn destroyed_player_bullets_are_cleared_if_enabled() {
+        let mut state = GameState::new(Options { respawn_bullet_clearance: Some(50.0), ..Options::default() });
+        let _rx0 = connect(&mut state, 0);
+        let _rx1 = connect(&mut state, 1);
+        place_player(&mut state, 1, 135.0, 100.0);
+        state.process_client_message(1,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+
+        kill(&mut state, 1, 0);
+
+        assert!(state.bullets.values().all(|bullet| bullet.owner_id != 1));
+    }
+
+    #[test]
+    fn bullets_are_left_alone_by_default() {
+        let mut state = GameState::new(Options::default());
+        let _rx0 = connect(&mut state, 0);
+        let _rx1 = connect(&mut state, 1);
+        place_player(&mut state, 1, 135.0, 100.0);
+        state.process_client_message(1,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+
+        kill(&mut state, 1, 0);
+
+        assert!(state.bullets.values().any(|bullet| bullet.owner_id == 1));
+    }
+
+    /// Have a shooter lagging 3 ticks behind hit where its target was 3 ticks ago, returning whether the target died.
+    fn lagging_shot_hits(lag_compensation: Option<u32>) -> bool {
+        let mut state = GameState::new(Options { lag_compensation: lag_compensation, ..Options::default() });
+        let _rx0 = connect(&mut state, 0);
+        let _rx1 = connect(&mut state, 1);
+        place_player(&mut state, 0, 50.0, 50.0);
+        place_player(&mut state, 1, 250.0, 250.0);
+        state.process_websocket_event(WebSocketEvent::ClientLatency {
+            client_id: 0,
+            latency_ms: 50,
         });
+        for _ in 0..3 {
+            state.process_game_update();
+        }
+
+        place_player(&mut state, 1, 400.0, 400.0);
+        let _ = state.bullets.insert(0, OwnedBullet::new(Bullet::not_moving(0, 250.0, 250.0), 0));
+        state.process_game_update();
+
+        !state.players.contains_key(&1)
+    }
+
+    /// Have player 0 shoot player 1 from the specified distance with falloff enabled, returning the damage dealt.
+    fn damage_dealt_at_range(range: f32) -> f32 {
+        let mut state = GameState::new(Options { damage_falloff: Some(0.001), ..Options::default() });
+        let _rx0 = connect(&mut state, 0);
+        let _rx1 = connect(&mut state, 1);
+        place_player(&mut state, 0, 100.0, 100.0);
+        place_player(&mut state, 1, 100.0 + range, 100.0);
+
+        state.process_client_message(0,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        while !state.bullets.is_empty() {
+            state.process_game_update();
+        }
+
+        assert!(state.players.contains_key(&1));
+        MAX_HEALTH - state.health[&1]
+    }
+
+    /// Connect a client with the specified ID, returning the receiving end of its channel.
+    fn connect(state: &mut GameState, id: u32) -> mpsc::Receiver<Option<Arc<String>>> {
+        let (tx, rx) = mpsc::channel();
+        state.process_websocket_event(WebSocketEvent::ClientCreated { client: Client::new(id, tx) });
+        rx
+    }
+
+    fn place_player(state: &mut GameState, id: u32, x: f32, y: f32) {
+        let player = state.players.get_mut(&id).unwrap();
+        player.x = x;
+        player.y = y;
+    }
+
+    /// Place the specified player as if it had just reported the specified position.
+    fn report_position(state: &mut GameState, id: u32, x: f32, y: f32) {
+        place_player(state, id, x, y);
+        let _ = state.last_position_reports.insert(id, (state.tick, Vec2::new(x, y)));
+    }
+
+    /// Have the killer shoot the victim point-blank, ticking until the victim dies.
+    fn kill(state: &mut GameState, victim: u32, killer: u32) {
+        place_player(state, killer, 100.0, 100.0);
+        place_player(state, victim, 135.0, 100.0);
+
+        state.process_client_message(killer,
+                                     Message::Fire {
+                                         move_x: 1.0,
+                                         move_y: 0.0,
+                                     });
+        while state.players.contains_key(&victim) {
+            state.process_game_update();
+        }
+    }
+
+    /// Find the first **world_state** sent so far down the specified client channel, as it was sent.
+    fn world_state_payload(rx: &mpsc::Receiver<Option<Arc<String>>>) -> Arc<String> {
+        while let Ok(Some(text)) = rx.try_recv() {
+            if let Message::WorldState { .. } = str::parse(&text).unwrap() {
+                return text;
+            }
+        }
+        panic!("No world_state was sent");
+    }
+
+    /// Parse all messages sent so far down the specified client channel.
+    fn received(rx: &mpsc::Receiver<Option<Arc<String>>>) -> Vec<Message> {
+        let mut messages = Vec::new();
+        while let Ok(Some(text)) = rx.try_recv() {
+            messages.push(str::parse(&text).unwrap());
+        }
+        messages
     }
 }
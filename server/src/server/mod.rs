@@ -5,6 +5,8 @@
 
 mod events;
 mod gamestate;
+mod observer;
+mod rooms;
 
 use std::sync::mpsc::channel;
 use std::thread;
@@ -12,87 +14,391 @@ use std::thread;
 use websocket;
 use message;
 use std::io;
+use std::io::Write;
+use std::collections::VecDeque;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
 
+use flate2::Compression;
+use flate2::write::DeflateEncoder;
 use websocket::message::Type;
-use websocket::{Server, Message, Receiver};
-use websocket::server::Connection;
+use websocket::{Message, Receiver};
+use websocket::header::WebSocketProtocol;
+use websocket::result::WebSocketError;
+use websocket::server::request::{Request, RequestUri};
 use websocket::stream::WebSocketStream;
-use std::sync::{mpsc, Arc, RwLock};
+use std::sync::{mpsc, Arc, Mutex, PoisonError, RwLock, Weak};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use time;
-use std::cmp;
-use std::str::{self, FromStr};
+use options::Options;
+use std::str;
 use std::time::Duration;
 
 pub use self::events::*;
 pub use self::gamestate::GameState;
+pub use self::observer::GameObserver;
+pub use self::rooms::RoomRegistry;
+
+/// WebSocket subprotocol a client can request during the handshake to receive compressed messages.
+///
+/// Compressed messages are sent in binary frames containing raw deflate data,
+/// messages to clients which didn't request compression are sent in text frames as usual.
+pub static COMPRESSED_PROTOCOL: &'static str = "tatsoryk-deflate";
+
+/// Query flag a client can set in the URL it connects to, e.g. `/?seq=1`, to have every message it's sent numbered.
+///
+/// See the `message` module for how the number is sent.
+pub static SEQUENCE_FLAG: &'static str = "seq";
+
+/// Summary of a server's run, returned by `listen` once it stops.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ListenSummary {
+    /// Address the server was listening on.
+    pub address: SocketAddr,
+    /// Amount of client connections accepted.
+    pub connections: u32,
+}
+
+/// How a listener accepts connections and treats the clients connecting through it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListenConfig {
+    /// Host to listen on, either an IP or a hostname.
+    pub host: String,
+    /// Port to listen on.
+    pub port: u16,
+    /// Time after which clients which haven't sent anything are disconnected, if any.
+    pub client_timeout: Option<Duration>,
+    /// Origins browser clients may connect from, if limited.
+    pub allowed_origins: Option<Vec<String>>,
+    /// Maximum length of arrays in messages from clients.
+    pub max_array_length: usize,
+    /// Maximum amount of connections in the middle of their handshake at once, if limited.
+    pub max_handshakes: Option<usize>,
+    /// Whether the clients are read-only spectators instead of players.
+    pub spectators: bool,
+}
+
+impl ListenConfig {
+    /// Configure a listener for players on the host and port from the specified options.
+    pub fn new(options: &Options) -> ListenConfig {
+        ListenConfig {
+            host: options.host.clone(),
+            port: options.port,
+            client_timeout: options.client_timeout.map(Duration::from_millis),
+            allowed_origins: options.allowed_origins.clone(),
+            max_array_length: options.max_array_length as usize,
+            max_handshakes: options.max_handshakes.map(|max_handshakes| max_handshakes as usize),
+            spectators: false,
+        }
+    }
+}
+
+/// Place taken by a connection in the middle of its handshake, given up once dropped.
+#[derive(Debug)]
+struct HandshakeSlot {
+    in_handshake: Arc<AtomicUsize>,
+}
+
+impl HandshakeSlot {
+    /// Take a place among the connections counted by `in_handshake`, unless there already are `max_handshakes` of them.
+    fn acquire(in_handshake: &Arc<AtomicUsize>, max_handshakes: Option<usize>) -> Option<HandshakeSlot> {
+        let previous = in_handshake.fetch_add(1, Ordering::SeqCst);
+        if max_handshakes.map_or(false, |max_handshakes| previous >= max_handshakes) {
+            let _ = in_handshake.fetch_sub(1, Ordering::SeqCst);
+            return None;
+        }
+
+        Some(HandshakeSlot { in_handshake: in_handshake.clone() })
+    }
+}
+
+impl Drop for HandshakeSlot {
+    fn drop(&mut self) {
+        let _ = self.in_handshake.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A connection's place in a room, given up once dropped so that the room can be removed after its last client leaves.
+#[derive(Debug)]
+struct RoomMembership {
+    rooms: Arc<Mutex<RoomRegistry>>,
+    room: String,
+}
+
+impl Drop for RoomMembership {
+    fn drop(&mut self) {
+        self.rooms.lock().unwrap_or_else(PoisonError::into_inner).leave(&self.room);
+    }
+}
 
 /// The main listening loop for the server.
-pub fn listen(host: &str,
-              port: u16,
-              game_messages_sender: mpsc::Sender<WebSocketEvent>,
-              cont: &Arc<RwLock<bool>>) {
-    let cont = cont.clone();
+///
+/// Every connection is routed to the room named by the path it connected to, or the default room if only one may run.
+/// Messages from clients with arrays longer than the configured `max_array_length` are rejected.
+///
+/// If `max_handshakes` is configured, connections accepted while that many others are still in the middle of
+/// their handshake are closed immediately, so that half-open handshakes can't pile up threads.
+///
+/// If `spectators` is configured, connections become read-only spectators instead of players,
+/// which receive everything broadcast in their room, but whose messages are ignored.
+///
+/// Connections are accepted without blocking, so that `cont` can be checked in between.
+/// Once it's cleared, connections still waiting to be accepted are sent **go_away** and closed instead of joining the game.
+///
+/// Listens until `cont` is cleared, returning a summary of the run,
+/// or an error describing the problem if the server can't start listening.
+pub fn listen(config: &ListenConfig,
+              rooms: Arc<Mutex<RoomRegistry>>,
+              cont: &Arc<RwLock<bool>>)
+              -> Result<ListenSummary, String> {
+    static ACCEPT_POLL_INTERVAL: u64 = 10; // ms
 
-    println!("Listening on {}:{}", host, port);
-    let server = Server::bind((host, port)).unwrap();
+    let (host, port) = (&config.host[..], config.port);
+    let listener = try!(bind(host, port));
+    let address = match listener.local_addr() {
+        Ok(address) => address,
+        Err(error) => return Err(format!("failed to get address of {}:{}: {}", host, port, error)),
+    };
+    if let Err(error) = listener.set_nonblocking(true) {
+        return Err(format!("failed to stop {}:{} from blocking: {}", host, port, error));
+    }
+    println!("Listening on {}:{} ({})", host, port, address);
 
-    let mut next_client_id = 0;
+    let mut connections = 0;
+    let in_handshake = Arc::new(AtomicUsize::new(0));
 
-    for connection in server {
-        if !*cont.read().unwrap() {
-            break;
+    let mut draining = false;
+    loop {
+        let connection = match listener.accept() {
+            Ok((connection, _)) => connection,
+            Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => {
+                // Once told to stop, wait one more interval for connections already on their way, then stop
+                if draining {
+                    break;
+                }
+                draining = !*cont.read().unwrap();
+                thread::sleep(Duration::from_millis(ACCEPT_POLL_INTERVAL));
+                continue;
+            }
+            Err(error) => {
+                println!("Failed to accept connection: {}", error);
+
+                // Errors like running out of file descriptors tend to persist, so don't spin on them, nor past shutdown
+                if !*cont.read().unwrap() {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(ACCEPT_POLL_INTERVAL));
+                continue;
+            }
+        };
+        let shutting_down = !*cont.read().unwrap();
+
+        let handshake = match HandshakeSlot::acquire(&in_handshake, config.max_handshakes) {
+            Some(handshake) => handshake,
+            None => {
+                println!("Refusing connection, too many handshakes in progress");
+                continue;
+            }
+        };
+
+        if shutting_down {
+            println!("Turning away connection, shutting down");
+            let _ = thread::spawn(move || {
+                if let Err(e) = turn_away(connection, handshake, "Server shutting down") {
+                    println!("Failed to turn away connection: {:?}", e);
+                }
+            });
+            continue;
         }
 
-        let temp = game_messages_sender.clone();
-        let id = next_client_id;
-        next_client_id += 1;
+        let rooms = rooms.clone();
+        let config = config.clone();
+        let id = rooms.lock().unwrap().next_client_id();
+        connections += 1;
         // Spawn a new thread for each connection.
         let _ = thread::spawn(move || {
-            if let Err(e) = handle_connection(id, connection, temp) {
-                panic!("Connection {} quit with error {:?}", id, e)
+            match handle_connection(id, connection, handshake, &config, rooms) {
+                Ok(()) => {}
+                Err(ServerError::ProtocolError(e)) => println!("Client {} sent an invalid message: {:?}", id, e),
+                Err(e) => panic!("Connection {} quit with error {:?}", id, e),
             }
         });
     }
 
-    println!("Server killed after {} connections",
-             cmp::max(next_client_id, 1) - 1);
+    println!("Server killed after {} connections", connections);
+    Ok(ListenSummary {
+        address: address,
+        connections: connections,
+    })
+}
+
+/// Bind a listener to the specified host, which can be either an IP or a hostname, and port.
+///
+/// Hostnames can resolve to multiple addresses, the first of which that can be bound is used.
+fn bind(host: &str, port: u16) -> Result<TcpListener, String> {
+    let addresses = match (host, port).to_socket_addrs() {
+        Ok(addresses) => addresses,
+        Err(error) => return Err(format!("failed to resolve {}: {}", host, error)),
+    };
+
+    let mut last_error = None;
+    for address in addresses {
+        match TcpListener::bind(address) {
+            Ok(listener) => return Ok(listener),
+            Err(error) => last_error = Some(error),
+        }
+    }
+
+    match last_error {
+        Some(error) => Err(format!("failed to bind {}:{}: {}", host, port, error)),
+        None => Err(format!("failed to resolve {}: no addresses found", host)),
+    }
+}
+
+/// Validate the configuration without listening or starting the game loop, returning a summary thereof.
+pub fn check(options: &Options) -> Result<String, String> {
+    let game_state = GameState::new(options.clone());
+    try!(game_state.validate());
+
+    Ok(format!("Configuration valid, would listen on {}:{}\n{:?}",
+               options.host,
+               options.port,
+               options))
 }
 
 /// Spawns the main game loop in a separate thread and returns the handle therefor. Non-blocking.
 ///
 /// The general idea for the game loop is to update the game state every 16 milliseconds (60 FPS), processing messages along the way.
 pub fn start_game_loop(game_messages: mpsc::Receiver<WebSocketEvent>,
+                       options: Options,
                        cont: &Arc<RwLock<bool>>)
                        -> thread::JoinHandle<()> {
+    let game_messages = Arc::new(Mutex::new(game_messages));
+    let loop_cont = cont.clone();
+    supervise(move || {
+                  // A panicked game loop poisons the lock, but leaves the channel itself usable
+                  let game_messages = game_messages.lock().unwrap_or_else(PoisonError::into_inner);
+                  let _ = run_game_loop(&game_messages, options.clone(), &loop_cont);
+              },
+              cont.clone())
+}
+
+/// Run the game loop with a fresh `GameState` until `cont` is cleared and the shutdown grace period, if any, passes,
+/// returning the amount of ticks processed.
+///
+/// If the server threads sending events all go away, the loop stops right away, as nobody can reach the game anymore;
+/// either way, clients are told to go away as the `GameState` is dropped.
+///
+/// While the game is idle, it isn't updated at all, and events are only polled for every `IDLE_POLL_INTERVAL`.
+fn run_game_loop(game_messages: &mpsc::Receiver<WebSocketEvent>, options: Options, cont: &Arc<RwLock<bool>>) -> u64 {
     static ITER_LENGTH: u64 = 16 * 1000000; // 16 milliseconds
+    static IDLE_POLL_INTERVAL: u64 = 100; // ms
+
+    let shutdown_grace_period = options.shutdown_grace_period.unwrap_or(0) as u64 * 1000000000;
+    let mut game_state = GameState::new(options);
+
+    let mut start_time = time::precise_time_ns();
+    let mut iter: u64 = 1;
+    let mut shutdown_at = None;
+    loop {
+        if shutdown_at.is_none() && !*cont.read().unwrap() {
+            game_state.announce_shutdown();
+            shutdown_at = Some(time::precise_time_ns() + shutdown_grace_period);
+        }
+        if let Some(shutdown_at) = shutdown_at {
+            if time::precise_time_ns() >= shutdown_at {
+                break;
+            }
+        }
+
+        let tick_start = time::precise_time_ns();
+        if !game_state.process_websocket_events(game_messages) {
+            println!("Game loop lost all its event senders, stopping: {}", game_state);
+            break;
+        }
+        if game_state.is_idle() {
+            thread::sleep(Duration::from_millis(IDLE_POLL_INTERVAL));
+
+            // Don't try to catch up on the ticks skipped while idle
+            start_time = time::precise_time_ns();
+            iter = 1;
+            continue;
+        }
+        game_state.process_game_update();
+        game_state.send_state_updates();
+
+        let tick_length = time::precise_time_ns() - tick_start;
+        if tick_length > ITER_LENGTH {
+            println!("Slow tick took {}ms: {}", tick_length / 1000000, game_state);
+        }
+
+        // Sleep if needed to the next update
+        let time_till_next = ((iter * ITER_LENGTH) as i64) - ((time::precise_time_ns() - start_time) as i64);
+        iter += 1;
+        if time_till_next > 0 {
+            thread::sleep(Duration::new(0, time_till_next as u32));
+        }
+    }
 
-    let cont = cont.clone();
+    game_state.current_tick()
+}
+
+/// Run the specified body in a new thread, running it again in a fresh thread whenever it panics.
+///
+/// Without this a panicking game loop would silently freeze the game while its connections stay open.
+/// The panicking `GameState` tells its clients to go away as it's dropped, so they can reconnect to the fresh one.
+///
+/// Restarts are spaced out exponentially, and a body which keeps panicking soon after every restart is given up on
+/// after `MAX_RESTARTS` of them, as it'd most likely panic the same way forever.
+///
+/// Returns the handle of the supervising thread, which finishes once the body returns normally,
+/// panics after `cont` has been cleared, or is given up on.
+fn supervise<F>(body: F, cont: Arc<RwLock<bool>>) -> thread::JoinHandle<()>
+    where F: Fn() + Send + Sync + 'static
+{
+    static MAX_RESTARTS: u32 = 5; // In a row, each panicking within STABLE_RUN_LENGTH
+    static RESTART_BACKOFF: u64 = 20; // ms, doubled with every restart in a row
+    static STABLE_RUN_LENGTH: u64 = 60; // s, bodies running for longer before panicking start a fresh count of restarts
+
+    let body = Arc::new(body);
     thread::spawn(move || {
-        let mut game_state = GameState::new();
+        let mut restarts = 0;
+        loop {
+            let body = body.clone();
+            let start_time = time::precise_time_ns();
+            if thread::spawn(move || body()).join().is_ok() {
+                return;
+            }
 
-        let start_time = time::precise_time_ns();
-        let mut iter: u64 = 1;
-        while *cont.read().unwrap() {
-            game_state.process_websocket_events(&game_messages);
-            game_state.process_game_update();
-            game_state.send_state_updates();
+            if !*cont.read().unwrap() {
+                println!("Game loop thread panicked during shutdown");
+                return;
+            }
 
-            // Sleep if needed to the next update
-            let time_till_next = ((iter * ITER_LENGTH) as i64) -
-                                 ((time::precise_time_ns() - start_time) as i64);
-            iter += 1;
-            if time_till_next > 0 {
-                thread::sleep(Duration::new(0, time_till_next as u32));
+            if time::precise_time_ns() - start_time > STABLE_RUN_LENGTH * 1000000000 {
+                restarts = 0;
             }
+            if restarts == MAX_RESTARTS {
+                println!("Game loop thread panicked {} times in a row, each within {}s of starting, giving up on it",
+                         restarts + 1,
+                         STABLE_RUN_LENGTH);
+                return;
+            }
+
+            let backoff = RESTART_BACKOFF << restarts;
+            restarts += 1;
+            println!("Game loop thread panicked, restarting it in {}ms", backoff);
+            thread::sleep(Duration::from_millis(backoff));
         }
     })
 }
 
+/// Error ending a connection, either in the transport, or in what the client sent over it.
 #[derive(Debug)]
 enum ServerError {
     WebSocketError(websocket::result::WebSocketError),
     IoError(io::Error),
+    ProtocolError(message::MessageError),
 }
 
 impl From<io::Error> for ServerError {
@@ -107,66 +413,210 @@ impl From<websocket::result::WebSocketError> for ServerError {
     }
 }
 
+impl From<message::MessageError> for ServerError {
+    fn from(error: message::MessageError) -> ServerError {
+        ServerError::ProtocolError(error)
+    }
+}
+
 /// Handle a given connection.
 ///
 /// The basic idea is what we create two infinite loops:
 /// One which forever reads from the game loop via a channel and sends stuff to the websocket when requested.
 /// And one which forever reads from a websocket and sends the stuff to the game loop via a channel.
+///
+/// The `handshake` slot is given up once the client is handed over to the game loop.
+///
+/// If `client_timeout` is configured, a client which doesn't send anything for that long is considered disconnected,
+/// so that half-open connections don't keep the thread around forever.
+///
+/// If `allowed_origins` are configured, the handshake fails for browser clients connecting from other origins.
+///
+/// Messages with arrays longer than the configured `max_array_length` are rejected before their elements are unpacked.
+///
+/// If `spectators` is configured, the client is only ever sent what's broadcast, and everything it sends is ignored.
+///
+/// A client sending a text frame which isn't valid UTF-8 is told to go away and disconnected.
+/// So is one sending an invalid message, which is returned as `ServerError::ProtocolError`.
+///
+/// Clients which set `SEQUENCE_FLAG` when connecting have every message they're sent numbered.
 fn handle_connection(id: u32,
-                     connection: io::Result<Connection<WebSocketStream, WebSocketStream>>,
-                     game_messages_sender: mpsc::Sender<WebSocketEvent>)
+                     connection: TcpStream,
+                     handshake: HandshakeSlot,
+                     config: &ListenConfig,
+                     rooms: Arc<Mutex<RoomRegistry>>)
                      -> Result<(), ServerError> {
-    let request = try!(try!(connection).read_request()); // Get the request
+    let spectator = config.spectators;
+    let request = try!(read_request(connection)); // Get the request
 
     try!(request.validate()); // Validate the request
-    let response = request.accept(); // Form a response
+
+    let origin = request.origin().map(|origin| origin.0.clone());
+    if !origin_allowed(origin.as_ref().map(|origin| &origin[..]), &config.allowed_origins) {
+        println!("Refusing client {} from disallowed origin {:?}", id, origin);
+        let _ = try!(request.fail().send_into_inner());
+        return Ok(());
+    }
+
+    let room = match request.url {
+        RequestUri::AbsolutePath(ref path) => rooms::room_name(path).to_string(),
+        _ => String::new(),
+    };
+    let (room_sender, measure_latency) = {
+        let mut rooms = rooms.lock().unwrap();
+        (rooms.room(&room), !spectator && rooms.options().lag_compensation.is_some())
+    };
+    let game_messages_sender = match room_sender {
+        Some(sender) => sender,
+        None => {
+            println!("Refusing client {} for room {:?}, too many rooms running", id, room);
+            let _ = try!(request.fail().send_into_inner());
+            return Ok(());
+        }
+    };
+    let _membership = RoomMembership {
+        rooms: rooms,
+        room: room,
+    };
+
+    let sequenced = match request.url {
+        RequestUri::AbsolutePath(ref path) => query_flag(path, SEQUENCE_FLAG),
+        _ => false,
+    };
+    let compress = match request.protocol() {
+        Some(protocols) => protocols.iter().any(|protocol| protocol == COMPRESSED_PROTOCOL),
+        None => false,
+    };
+    let mut response = request.accept(); // Form a response
+    if compress {
+        response.headers.set(WebSocketProtocol(vec![COMPRESSED_PROTOCOL.to_string()]));
+    }
     let mut client = try!(response.send()); // Send the response
+    if let Some(client_timeout) = config.client_timeout {
+        try!(set_read_timeout(client.get_mut_receiver().get_mut().get_mut(), client_timeout));
+    }
 
     let ip = try!(client.get_mut_sender()
                         .get_mut()
                         .peer_addr());
 
-    println!("Connection from {} with id {}", ip, id);
+    println!("{} from {} with id {}",
+             if spectator { "Spectator" } else { "Connection" },
+             ip,
+             id);
 
     let (sender, mut receiver) = client.split();
+    let sender = Arc::new(Mutex::new(sender));
 
     // Create the channel which will allow the game loop to send messages to websockets.
     let (tx, rx) = channel();
+    let closed_event = if spectator {
+        WebSocketEvent::SpectatorClosed { client_id: id }
+    } else {
+        WebSocketEvent::ClientClosed { client_id: id }
+    };
 
     // Should never fail
-    game_messages_sender.send(WebSocketEvent::ClientCreated { client: Client::new(id, tx) })
+    game_messages_sender.send(if spectator {
+                                  WebSocketEvent::SpectatorCreated { client: Client::new(id, tx.clone()) }
+                              } else {
+                                  WebSocketEvent::ClientCreated { client: Client::new(id, tx.clone()) }
+                              })
                         .unwrap();
+    drop(handshake);
+
+    // Measure players' latency, if it's compensated for; the pings stop once the send loop lets go of the sender.
+    let sent_pings = Arc::new(Mutex::new(VecDeque::new()));
+    if measure_latency {
+        let sender = Arc::downgrade(&sender);
+        let sent_pings = sent_pings.clone();
+        let _ = thread::spawn(move || websocket_ping_loop(sender, sent_pings));
+    }
 
     // Create the thread for sending websocket messages.
     let _ = thread::spawn(move || {
-        if let Err(e) = websocket_send_loop(rx, sender) {
+        if let Err(e) = websocket_send_loop(rx, sender, compress, sequenced) {
             panic!("Send loop had an error for client {} , {:?}", id, e)
         }
     });
 
     // Handle all incoming messages by forwarding them to the game loop.
     for message in receiver.incoming_messages() {
-        let message: Message = try!(message);
+        let message: Message = match message {
+            Ok(message) => message,
+            Err(WebSocketError::IoError(ref error)) if is_timeout(error) => {
+                println!("Client {} timed out", id);
+
+                // Should never fail
+                game_messages_sender.send(closed_event).unwrap();
+                return Ok(());
+            }
+            Err(error) => return Err(ServerError::from(error)),
+        };
 
         match message.opcode {
             Type::Close => {
                 println!("Client {} disconnected", id);
 
                 // Should never fail
-                game_messages_sender.send(WebSocketEvent::ClientClosed { client_id: id })
-                                    .unwrap();
+                game_messages_sender.send(closed_event).unwrap();
                 return Ok(());
             }
+            Type::Text if spectator => {}
             Type::Text => {
-                let text = str::from_utf8(&message.payload).unwrap();
+                let text = match str::from_utf8(&message.payload) {
+                    Ok(text) => text,
+                    Err(error) => {
+                        println!("Client {} sent invalid UTF-8: {}", id, error);
+
+                        // The send loop closes the websocket after the last message
+                        let _ = tx.send(Some(Arc::new(message::Message::GoAway { reason: "invalid UTF-8".to_string() }
+                                                          .to_string())));
+                        let _ = tx.send(None);
+
+                        // Should never fail
+                        game_messages_sender.send(closed_event).unwrap();
+                        return Ok(());
+                    }
+                };
+
+                let (parsed, seq) = match message::Message::from_str_with_seq(text, config.max_array_length) {
+                    Ok(parsed) => parsed,
+                    Err(error) => {
+                        let _ = tx.send(Some(Arc::new(message::Message::GoAway { reason: "invalid message".to_string() }
+                                                          .to_string())));
+                        let _ = tx.send(None);
+
+                        // Should never fail
+                        game_messages_sender.send(closed_event).unwrap();
+                        return Err(ServerError::from(error));
+                    }
+                };
 
                 // Should never fail
                 game_messages_sender.send(WebSocketEvent::ClientMessage {
                                         client_id: id,
-                                        message: message::Message::from_str(text).unwrap(),
+                                        message: parsed,
+                                        seq: seq,
                                     })
                                     .unwrap();
             }
+            Type::Pong if !spectator => {
+                // Only pongs answering pings actually sent count, so that clients can't make up their latency
+                match decode_ping_payload(&message.payload) {
+                    Some(sent) if answer_ping(&sent_pings, sent) => {
+                        let latency_ms = (time::precise_time_ns().saturating_sub(sent) / 2 / 1000000) as u32;
+
+                        // Should never fail
+                        game_messages_sender.send(WebSocketEvent::ClientLatency {
+                                                client_id: id,
+                                                latency_ms: latency_ms,
+                                            })
+                                            .unwrap();
+                    }
+                    _ => {}
+                }
+            }
             _ => {
                 panic!("Unknown message type {:?}", message);
             }
@@ -176,14 +626,87 @@ fn handle_connection(id: u32,
     Ok(())
 }
 
-/// Constantly send messages over the websocket.
-fn websocket_send_loop<S: websocket::Sender>(rx: mpsc::Receiver<Option<String>>,
-                                             mut sender: S)
+/// Complete the handshake of a connection which won't become a client, then send it **go_away** with the specified reason
+/// and close it.
+fn turn_away(connection: TcpStream, handshake: HandshakeSlot, reason: &str) -> Result<(), ServerError> {
+    let request = try!(read_request(connection));
+    try!(request.validate());
+
+    let mut client = try!(request.accept().send());
+    drop(handshake);
+
+    let go_away = message::Message::GoAway { reason: reason.to_string() }.to_string();
+    try!(client.send_message(&Message::text(&go_away[..])));
+    try!(client.send_message(&Message::close()));
+    Ok(())
+}
+
+/// Read the handshake request from the specified freshly accepted connection.
+fn read_request(connection: TcpStream) -> Result<Request<WebSocketStream, WebSocketStream>, ServerError> {
+    // Depending on the platform, connections accepted from a non-blocking listener can be non-blocking themselves
+    try!(connection.set_nonblocking(false));
+
+    let stream = WebSocketStream::Tcp(connection);
+    Ok(try!(Request::read(try!(stream.try_clone()), stream)))
+}
+
+/// Check whether the query of the specified path sets the specified flag, either bare or as `flag=1`.
+fn query_flag(path: &str, flag: &str) -> bool {
+    match path.find('?') {
+        Some(query_start) => path[query_start + 1..].split('&').any(|param| param == flag || param == format!("{}=1", flag)),
+        None => false,
+    }
+}
+
+/// Check whether a client which sent the specified `Origin` header may connect, given the allowed origins, if any.
+///
+/// Only browsers are bound to send a truthful `Origin`, so clients which didn't send one at all are let through.
+fn origin_allowed(origin: Option<&str>, allowed_origins: &Option<Vec<String>>) -> bool {
+    match (origin, allowed_origins) {
+        (Some(origin), &Some(ref allowed_origins)) => allowed_origins.iter().any(|allowed| allowed == origin),
+        _ => true,
+    }
+}
+
+/// Make reads from the specified stream fail after blocking for the specified duration.
+fn set_read_timeout(stream: &mut WebSocketStream, timeout: Duration) -> io::Result<()> {
+    match stream {
+        &mut WebSocketStream::Tcp(ref inner) => inner.set_read_timeout(Some(timeout)),
+        &mut WebSocketStream::Ssl(ref inner) => inner.get_ref().set_read_timeout(Some(timeout)),
+    }
+}
+
+/// Check whether the specified error comes from a read timing out.
+///
+/// Depending on the platform, timed out reads return either `WouldBlock` or `TimedOut`.
+fn is_timeout(error: &io::Error) -> bool {
+    error.kind() == io::ErrorKind::WouldBlock || error.kind() == io::ErrorKind::TimedOut
+}
+
+/// Constantly send messages over the websocket, compressing them if so negotiated,
+/// and numbering them with `seq` if the client asked for it.
+fn websocket_send_loop<S: websocket::Sender>(rx: mpsc::Receiver<Option<Arc<String>>>,
+                                             sender: Arc<Mutex<S>>,
+                                             compress: bool,
+                                             sequenced: bool)
                                              -> Result<(), ServerError> {
+    let mut seq = 0;
     for message in rx {
+        let mut sender = sender.lock().unwrap();
         match message {
             Some(text) => {
-                try!(sender.send_message(&Message::text(text)));
+                let text = if sequenced {
+                    seq += 1;
+                    Arc::new(message::with_seq(&text, seq))
+                } else {
+                    text
+                };
+
+                if compress {
+                    try!(sender.send_message(&Message::binary(try!(compress_payload(&text)))));
+                } else {
+                    try!(sender.send_message(&Message::text(&text[..])));
+                }
             }
             None => {
                 try!(sender.send_message(&Message::close()));
@@ -194,3 +717,610 @@ fn websocket_send_loop<S: websocket::Sender>(rx: mpsc::Receiver<Option<String>>,
 
     Ok(())
 }
+
+/// Ping the client every `PING_INTERVAL` with the time of sending, so that its latency can be measured from the pongs.
+///
+/// The times of the latest pings are kept in `sent_pings`, until they're answered.
+///
+/// Stops once the sender goes away or fails.
+fn websocket_ping_loop<S: websocket::Sender>(sender: Weak<Mutex<S>>, sent_pings: Arc<Mutex<VecDeque<u64>>>) {
+    static PING_INTERVAL: u64 = 1000; // ms
+    static MAX_UNANSWERED_PINGS: usize = 4; // Older ones are forgotten, and pongs answering them ignored
+
+    loop {
+        thread::sleep(Duration::from_millis(PING_INTERVAL));
+
+        let sender = match sender.upgrade() {
+            Some(sender) => sender,
+            None => return,
+        };
+        let sent = time::precise_time_ns();
+        {
+            let mut sent_pings = sent_pings.lock().unwrap();
+            sent_pings.push_back(sent);
+            if sent_pings.len() > MAX_UNANSWERED_PINGS {
+                let _ = sent_pings.pop_front();
+            }
+        }
+        if sender.lock().unwrap().send_message(&Message::ping(encode_ping_payload(sent))).is_err() {
+            return;
+        }
+    }
+}
+
+/// Forget the ping sent at the specified time, returning whether it was sent and not answered yet.
+fn answer_ping(sent_pings: &Mutex<VecDeque<u64>>, sent: u64) -> bool {
+    let mut sent_pings = sent_pings.lock().unwrap();
+    match sent_pings.iter().position(|&ping| ping == sent) {
+        Some(index) => {
+            let _ = sent_pings.remove(index);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Encode the specified monotonic clock value as a ping payload, big-endian.
+fn encode_ping_payload(time: u64) -> Vec<u8> {
+    (0..8).map(|i| (time >> (56 - 8 * i)) as u8).collect()
+}
+
+/// Decode the monotonic clock value sent in a ping from the payload of the pong answering it.
+///
+/// Returns `None` for payloads not sent by `websocket_ping_loop()`.
+fn decode_ping_payload(payload: &[u8]) -> Option<u64> {
+    if payload.len() == 8 {
+        Some(payload.iter().fold(0, |time, &byte| (time << 8) | byte as u64))
+    } else {
+        None
+    }
+}
+
+/// Deflate the specified message text for a client which negotiated compression.
+fn compress_payload(text: &str) -> io::Result<Vec<u8>> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::Default);
+    try!(encoder.write_all(text.as_bytes()));
+    encoder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::str;
+    use std::io::Read;
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+    use std::u64;
+    use flate2::read::DeflateDecoder;
+    use serde_json::{self, Value};
+    use websocket::Client;
+    use websocket::Message as WebSocketMessage;
+    use websocket::message::Type;
+    use websocket::client::request::Url;
+    use websocket::header::Origin;
+    use message::{Message, Player, Bullet};
+    use options::Options;
+    use std::sync::{Arc, Mutex, RwLock};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use self::super::{answer_ping, bind, check, compress_payload, decode_ping_payload, encode_ping_payload, handle_connection,
+                      is_timeout, listen, origin_allowed, query_flag, run_game_loop, supervise, Client as GameClient, HandshakeSlot,
+                      ListenConfig, RoomRegistry, ServerError, WebSocketEvent};
+
+    #[test]
+    fn compressed_world_state_decompresses_identically() {
+        let world_state = Message::world_state(2,
+                                               vec![Player::not_moving(0, 34.5, 21.5),
+                                                    Player::moving(1, 67.5, 22.5, 1.0, 0.0)],
+                                               vec![Bullet::moving(0, 10.5, 10.5, 0.0, 1.0)])
+                              .to_string();
+
+        let compressed = compress_payload(&world_state).unwrap();
+        let mut decompressed = String::new();
+        let _ = DeflateDecoder::new(&compressed[..]).read_to_string(&mut decompressed).unwrap();
+
+        assert_eq!(decompressed, world_state);
+    }
+
+    #[test]
+    fn ping_payload_round_trips() {
+        for &time in &[0, 1, 0x0102030405060708, u64::MAX] {
+            assert_eq!(decode_ping_payload(&encode_ping_payload(time)), Some(time));
+        }
+        assert_eq!(decode_ping_payload(b"hello"), None);
+    }
+
+    #[test]
+    fn only_pings_sent_can_be_answered_once() {
+        let sent_pings = Mutex::new(vec![10, 20].into_iter().collect());
+
+        assert!(!answer_ping(&sent_pings, 0));
+        assert!(answer_ping(&sent_pings, 20));
+        assert!(!answer_ping(&sent_pings, 20));
+        assert!(answer_ping(&sent_pings, 10));
+    }
+
+    #[test]
+    fn check_does_not_listen() {
+        // Would fail if check tried to bind the port
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        assert!(check(&Options { port: port, ..Options::default() }).is_ok());
+    }
+
+    #[test]
+    fn check_rejects_invalid_options() {
+        assert!(check(&Options { bullet_drag: Some(2.0), ..Options::default() }).is_err());
+        assert!(check(&Options { max_move_distance: Some(-1.0), ..Options::default() }).is_err());
+    }
+
+    #[test]
+    fn listen_on_bound_port_fails_descriptively() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let cont = Arc::new(RwLock::new(true));
+        let rooms = Arc::new(Mutex::new(RoomRegistry::new(Options::default(), &cont)));
+
+        let error = listen(&listen_config(port), rooms, &cont).unwrap_err();
+        assert!(error.starts_with(&format!("failed to bind 127.0.0.1:{}: ", port)),
+                "{}",
+                error);
+    }
+
+    #[test]
+    fn listen_returns_once_told_to_stop() {
+        let cont = Arc::new(RwLock::new(true));
+        let rooms = Arc::new(Mutex::new(RoomRegistry::new(Options::default(), &cont)));
+
+        let (done_tx, done_rx) = mpsc::channel();
+        {
+            let cont = cont.clone();
+            let _ = thread::spawn(move || {
+                done_tx.send(listen(&listen_config(0), rooms, &cont)).unwrap();
+            });
+        }
+
+        thread::sleep(Duration::from_millis(100));
+        assert!(done_rx.try_recv().is_err(), "listen returned before being told to stop");
+        *cont.write().unwrap() = false;
+
+        let mut result = done_rx.try_recv();
+        let mut waited = 0;
+        while result.is_err() {
+            assert!(waited < 2000, "listen still running after {}ms", waited);
+            thread::sleep(Duration::from_millis(10));
+            waited += 10;
+            result = done_rx.try_recv();
+        }
+        assert_eq!(result.unwrap().unwrap().connections, 0);
+    }
+
+    #[test]
+    fn listen_told_to_stop_before_starting_returns() {
+        let cont = Arc::new(RwLock::new(false));
+        let rooms = Arc::new(Mutex::new(RoomRegistry::new(Options::default(), &cont)));
+
+        let summary = listen(&listen_config(0), rooms, &cont).unwrap();
+        assert_eq!(summary.connections, 0);
+
+        // Nothing's left listening
+        assert!(TcpStream::connect(summary.address).is_err());
+    }
+
+    #[test]
+    fn handshakes_beyond_cap_are_closed_immediately() {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let cont = Arc::new(RwLock::new(true));
+        let rooms = Arc::new(Mutex::new(RoomRegistry::new(Options::default(), &cont)));
+        {
+            let cont = cont.clone();
+            let _ = thread::spawn(move || {
+                let _ = listen(&ListenConfig { max_handshakes: Some(2), ..listen_config(port) }, rooms, &cont);
+            });
+        }
+        thread::sleep(Duration::from_millis(100));
+
+        // None of these ever send their request, so they stay mid-handshake
+        let mut streams = (0..8).map(|_| TcpStream::connect(("127.0.0.1", port)).unwrap()).collect::<Vec<_>>();
+        let mut open = 0;
+        for stream in &mut streams {
+            stream.set_read_timeout(Some(Duration::from_millis(200))).unwrap();
+            match stream.read(&mut [0; 1]) {
+                Ok(0) => {}
+                Ok(_) => panic!("Received data without sending a request"),
+                Err(ref error) if is_timeout(error) => open += 1,
+                Err(_) => {}
+            }
+        }
+        assert_eq!(open, 2);
+
+        *cont.write().unwrap() = false;
+    }
+
+    #[test]
+    fn connection_during_shutdown_is_sent_go_away() {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let cont = Arc::new(RwLock::new(true));
+        let (events_tx, events_rx) = mpsc::channel();
+        let mut rooms = RoomRegistry::new(Options::default(), &cont);
+        rooms.add_room("", events_tx);
+        {
+            let cont = cont.clone();
+            let rooms = Arc::new(Mutex::new(rooms));
+            let _ = thread::spawn(move || {
+                let _ = listen(&listen_config(port), rooms, &cont);
+            });
+        }
+        thread::sleep(Duration::from_millis(100));
+
+        *cont.write().unwrap() = false;
+        let mut client = Client::connect(Url::parse(&format!("ws://127.0.0.1:{}", port)[..]).unwrap())
+                             .unwrap()
+                             .send()
+                             .unwrap()
+                             .begin();
+
+        let go_away: WebSocketMessage = client.recv_message().unwrap();
+        assert_eq!(str::parse::<Message>(str::from_utf8(&go_away.payload).unwrap()).unwrap(),
+                   Message::GoAway { reason: "Server shutting down".to_string() });
+        let close: WebSocketMessage = client.recv_message().unwrap();
+        assert_eq!(close.opcode, Type::Close);
+        assert!(events_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn bind_resolves_hostnames() {
+        let listener = bind("localhost", 0).unwrap();
+
+        assert!(listener.local_addr().unwrap().port() != 0);
+    }
+
+    #[test]
+    fn idle_game_loop_does_not_tick() {
+        let (_events_tx, events_rx) = mpsc::channel();
+
+        assert_eq!(run_game_loop_for(&events_rx, Duration::from_millis(300)), 0);
+    }
+
+    #[test]
+    fn game_loop_ticks_once_somebody_connects() {
+        let (events_tx, events_rx) = mpsc::channel();
+        let (client_tx, _client_rx) = mpsc::channel();
+        events_tx.send(WebSocketEvent::ClientCreated { client: GameClient::new(0, client_tx) }).unwrap();
+
+        assert!(run_game_loop_for(&events_rx, Duration::from_millis(300)) > 0);
+    }
+
+    #[test]
+    fn shutdown_is_announced_before_going_away() {
+        let (events_tx, events_rx) = mpsc::channel();
+        let (client_tx, client_rx) = mpsc::channel();
+        events_tx.send(WebSocketEvent::ClientCreated { client: GameClient::new(0, client_tx) }).unwrap();
+        let _ = run_game_loop_for(&events_rx, Duration::from_millis(100));
+
+        let mut received = Vec::new();
+        while let Ok(Some(text)) = client_rx.try_recv() {
+            received.push(str::parse::<Message>(&text).unwrap());
+        }
+        let shutdown = received.iter().position(|message| *message == Message::ServerShutdown { seconds_remaining: 0 });
+        let go_away = received.iter().position(|message| match message {
+            &Message::GoAway { .. } => true,
+            _ => false,
+        });
+        assert!(shutdown.is_some());
+        assert_eq!(go_away, Some(received.len() - 1));
+        assert!(shutdown < go_away);
+    }
+
+    #[test]
+    fn game_loop_stops_once_event_senders_are_gone() {
+        let (events_tx, events_rx) = mpsc::channel();
+        let (client_tx, client_rx) = mpsc::channel();
+        events_tx.send(WebSocketEvent::ClientCreated { client: GameClient::new(0, client_tx) }).unwrap();
+        drop(events_tx);
+
+        let (done_tx, done_rx) = mpsc::channel();
+        let _ = thread::spawn(move || {
+            let cont = Arc::new(RwLock::new(true));
+            done_tx.send(run_game_loop(&events_rx, Options::default(), &cont)).unwrap();
+        });
+
+        let mut result = done_rx.try_recv();
+        let mut waited = 0;
+        while result.is_err() {
+            assert!(waited < 2000, "game loop still running after {}ms", waited);
+            thread::sleep(Duration::from_millis(10));
+            waited += 10;
+            result = done_rx.try_recv();
+        }
+
+        let mut received = Vec::new();
+        while let Ok(Some(text)) = client_rx.try_recv() {
+            received.push(str::parse::<Message>(&text).unwrap());
+        }
+        match received.last() {
+            Some(&Message::GoAway { .. }) => {}
+            other => panic!("Expected go_away last, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn supervisor_restarts_panicked_body() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let body_runs = runs.clone();
+
+        let supervisor = supervise(move || {
+                                       if body_runs.fetch_add(1, Ordering::SeqCst) == 0 {
+                                           panic!("Injected game loop panic");
+                                       }
+                                   },
+                                   Arc::new(RwLock::new(true)));
+
+        assert!(supervisor.join().is_ok());
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn supervisor_gives_up_during_shutdown() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let body_runs = runs.clone();
+
+        let supervisor = supervise(move || {
+                                       let _ = body_runs.fetch_add(1, Ordering::SeqCst);
+                                       panic!("Injected game loop panic");
+                                   },
+                                   Arc::new(RwLock::new(false)));
+
+        assert!(supervisor.join().is_ok());
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn supervisor_gives_up_on_body_which_always_panics() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let body_runs = runs.clone();
+
+        let supervisor = supervise(move || {
+                                       let _ = body_runs.fetch_add(1, Ordering::SeqCst);
+                                       panic!("Injected game loop panic");
+                                   },
+                                   Arc::new(RwLock::new(true)));
+
+        // Without a limit, this would never finish; the body runs once, then for every one of the 5 restarts
+        assert!(supervisor.join().is_ok());
+        assert_eq!(runs.load(Ordering::SeqCst), 6);
+    }
+
+    #[test]
+    fn origins_are_allowed_only_if_listed() {
+        let allowed_origins = Some(vec!["https://example.com".to_string()]);
+
+        assert!(origin_allowed(Some("https://example.com"), &allowed_origins));
+        assert!(!origin_allowed(Some("https://evil.example.com"), &allowed_origins));
+        assert!(origin_allowed(None, &allowed_origins));
+        assert!(origin_allowed(Some("https://evil.example.com"), &None));
+    }
+
+    #[test]
+    fn disallowed_origin_is_refused_before_player_is_created() {
+        let allowed_origins = Some(vec!["https://example.com".to_string()]);
+        let (port, events_rx, done_rx) = serve_connection(ListenConfig { allowed_origins: allowed_origins, ..listen_config(0) });
+
+        let mut request = Client::connect(Url::parse(&format!("ws://127.0.0.1:{}", port)[..]).unwrap()).unwrap();
+        request.headers.set(Origin("https://evil.example.com".to_string()));
+        let response = request.send().unwrap();
+
+        assert!(response.validate().is_err());
+        assert!(done_rx.recv().unwrap().is_ok());
+        assert!(events_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn silent_client_times_out() {
+        let client_timeout = Some(Duration::from_millis(100));
+        let (port, events_rx, done_rx) = serve_connection(ListenConfig { client_timeout: client_timeout, ..listen_config(0) });
+
+        // Connect, then never send anything
+        let _response = Client::connect(Url::parse(&format!("ws://127.0.0.1:{}", port)[..]).unwrap())
+                            .unwrap()
+                            .send()
+                            .unwrap();
+
+        let mut result = done_rx.try_recv();
+        let mut waited = 0;
+        while result.is_err() {
+            assert!(waited < 2000, "Handler thread still running after {}ms", waited);
+            thread::sleep(Duration::from_millis(10));
+            waited += 10;
+            result = done_rx.try_recv();
+        }
+        assert!(result.unwrap().is_ok());
+
+        match events_rx.try_recv().unwrap() {
+            WebSocketEvent::ClientCreated { client } => assert_eq!(client.id, 0),
+            event => panic!("Expected ClientCreated, got {:?}", event),
+        }
+        match events_rx.try_recv().unwrap() {
+            WebSocketEvent::ClientClosed { client_id } => assert_eq!(client_id, 0),
+            event => panic!("Expected ClientClosed, got {:?}", event),
+        }
+    }
+
+    #[test]
+    fn spectator_input_is_ignored() {
+        let (port, events_rx, done_rx) = serve_connection(ListenConfig { spectators: true, ..listen_config(0) });
+
+        let mut client = Client::connect(Url::parse(&format!("ws://127.0.0.1:{}", port)[..]).unwrap())
+                             .unwrap()
+                             .send()
+                             .unwrap()
+                             .begin();
+        client.send_message(&WebSocketMessage::text(Message::Fire {
+                                                            move_x: 1.0,
+                                                            move_y: 0.0,
+                                                        }
+                                                        .to_string()))
+              .unwrap();
+        client.send_message(&WebSocketMessage::close()).unwrap();
+        assert!(done_rx.recv().unwrap().is_ok());
+
+        match events_rx.try_recv().unwrap() {
+            WebSocketEvent::SpectatorCreated { client } => assert_eq!(client.id, 0),
+            event => panic!("Expected SpectatorCreated, got {:?}", event),
+        }
+        match events_rx.try_recv().unwrap() {
+            WebSocketEvent::SpectatorClosed { client_id } => assert_eq!(client_id, 0),
+            event => panic!("Expected SpectatorClosed, got {:?}", event),
+        }
+        assert!(events_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn invalid_utf8_is_answered_with_go_away() {
+        let (port, events_rx, done_rx) = serve_connection(listen_config(0));
+
+        let mut client = Client::connect(Url::parse(&format!("ws://127.0.0.1:{}", port)[..]).unwrap())
+                             .unwrap()
+                             .send()
+                             .unwrap()
+                             .begin();
+        client.send_message(&WebSocketMessage {
+                  opcode: Type::Text,
+                  cd_status_code: None,
+                  payload: Cow::Owned(vec![0xC3, 0x28]),
+              })
+              .unwrap();
+        assert!(done_rx.recv().unwrap().is_ok());
+
+        let go_away: WebSocketMessage = client.recv_message().unwrap();
+        assert_eq!(str::parse::<Message>(str::from_utf8(&go_away.payload).unwrap()).unwrap(),
+                   Message::GoAway { reason: "invalid UTF-8".to_string() });
+        let close: WebSocketMessage = client.recv_message().unwrap();
+        assert_eq!(close.opcode, Type::Close);
+
+        match events_rx.try_recv().unwrap() {
+            WebSocketEvent::ClientCreated { client } => assert_eq!(client.id, 0),
+            event => panic!("Expected ClientCreated, got {:?}", event),
+        }
+        match events_rx.try_recv().unwrap() {
+            WebSocketEvent::ClientClosed { client_id } => assert_eq!(client_id, 0),
+            event => panic!("Expected ClientClosed, got {:?}", event),
+        }
+    }
+
+    #[test]
+    fn invalid_message_is_a_protocol_error() {
+        let (port, events_rx, done_rx) = serve_connection(listen_config(0));
+
+        let mut client = Client::connect(Url::parse(&format!("ws://127.0.0.1:{}", port)[..]).unwrap())
+                             .unwrap()
+                             .send()
+                             .unwrap()
+                             .begin();
+        client.send_message(&WebSocketMessage::text(r#"{"type": "fire"}"#)).unwrap();
+        match done_rx.recv().unwrap() {
+            Err(ServerError::ProtocolError(_)) => {}
+            result => panic!("Expected ProtocolError, got {:?}", result),
+        }
+
+        let go_away: WebSocketMessage = client.recv_message().unwrap();
+        assert_eq!(str::parse::<Message>(str::from_utf8(&go_away.payload).unwrap()).unwrap(),
+                   Message::GoAway { reason: "invalid message".to_string() });
+        let close: WebSocketMessage = client.recv_message().unwrap();
+        assert_eq!(close.opcode, Type::Close);
+
+        match events_rx.try_recv().unwrap() {
+            WebSocketEvent::ClientCreated { client } => assert_eq!(client.id, 0),
+            event => panic!("Expected ClientCreated, got {:?}", event),
+        }
+        match events_rx.try_recv().unwrap() {
+            WebSocketEvent::ClientClosed { client_id } => assert_eq!(client_id, 0),
+            event => panic!("Expected ClientClosed, got {:?}", event),
+        }
+    }
+
+    #[test]
+    fn query_flag_is_set_bare_or_to_one() {
+        assert!(query_flag("/?seq", "seq"));
+        assert!(query_flag("/lobby?compress=1&seq=1", "seq"));
+        assert!(!query_flag("/lobby?seq=0", "seq"));
+        assert!(!query_flag("/seq", "seq"));
+    }
+
+    #[test]
+    fn consecutive_messages_carry_increasing_seq_if_requested() {
+        let (port, events_rx, _done_rx) = serve_connection(listen_config(0));
+
+        let mut client = Client::connect(Url::parse(&format!("ws://127.0.0.1:{}/?seq=1", port)[..]).unwrap())
+                             .unwrap()
+                             .send()
+                             .unwrap()
+                             .begin();
+        let game_client = match events_rx.recv().unwrap() {
+            WebSocketEvent::ClientCreated { client } => client,
+            event => panic!("Expected ClientCreated, got {:?}", event),
+        };
+        game_client.send(Message::PlayerJoined { id: 1 }.to_string()).unwrap();
+        game_client.send(Message::PlayerLeft { id: 1 }.to_string()).unwrap();
+
+        let mut last_seq = 0;
+        for expected in vec![Message::PlayerJoined { id: 1 }, Message::PlayerLeft { id: 1 }] {
+            let received: WebSocketMessage = client.recv_message().unwrap();
+            let text = str::from_utf8(&received.payload).unwrap();
+            let seq = match serde_json::from_str::<Value>(text).unwrap().as_object().unwrap().get("seq") {
+                Some(&Value::U64(seq)) => seq,
+                seq => panic!("Expected numeric seq, got {:?}", seq),
+            };
+            assert!(seq > last_seq, "{} after {}", seq, last_seq);
+            assert_eq!(str::parse::<Message>(text).unwrap(), expected);
+            last_seq = seq;
+        }
+    }
+
+    /// Configure a listener for players on the specified port of the loopback interface.
+    fn listen_config(port: u16) -> ListenConfig {
+        ListenConfig { port: port, ..ListenConfig::new(&Options::default()) }
+    }
+
+    /// Serve a single connection to the default room, configured as specified, on a port of the loopback interface.
+    ///
+    /// Returns the port to connect to, the room's events, and the connection's result once it ends.
+    fn serve_connection(config: ListenConfig)
+                        -> (u16, mpsc::Receiver<WebSocketEvent>, mpsc::Receiver<Result<(), ServerError>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let (events_tx, events_rx) = mpsc::channel();
+        let mut rooms = RoomRegistry::new(Options::default(), &Arc::new(RwLock::new(true)));
+        rooms.add_room("", events_tx);
+
+        let (done_tx, done_rx) = mpsc::channel();
+        let _ = thread::spawn(move || {
+            let (connection, _) = listener.accept().unwrap();
+            let _ = done_tx.send(handle_connection(0, connection, handshake_slot(), &config, Arc::new(Mutex::new(rooms))));
+        });
+
+        (port, events_rx, done_rx)
+    }
+
+    /// Take a place among handshakes which aren't limited.
+    fn handshake_slot() -> HandshakeSlot {
+        HandshakeSlot::acquire(&Arc::new(AtomicUsize::new(0)), None).unwrap()
+    }
+
+    /// Run a game loop until the specified amount of time passes, returning the amount of ticks it processed.
+    fn run_game_loop_for(events_rx: &mpsc::Receiver<WebSocketEvent>, duration: Duration) -> u64 {
+        let cont = Arc::new(RwLock::new(true));
+        {
+            let cont = cont.clone();
+            let _ = thread::spawn(move || {
+                thread::sleep(duration);
+                *cont.write().unwrap() = false;
+            });
+        }
+
+        run_game_loop(events_rx, Options::default(), &cont)
+    }
+}
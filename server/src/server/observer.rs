@@ -0,0 +1,24 @@
+//! Hooks allowing embedders to react to what's happening in the game.
+//!
+//! Useful for things like analytics or persistence, which don't belong in the game logic itself.
+
+use std::fmt;
+
+use self::super::GameState;
+
+/// An observer of the game's lifecycle events, attached via `GameState::set_observer()`.
+///
+/// All methods do nothing by default, so implementors need only override the ones they care about.
+pub trait GameObserver: fmt::Debug {
+    /// Called after a player with the specified ID joins the game.
+    fn on_join(&mut self, _id: u32) {}
+
+    /// Called after a player with the specified ID leaves the game.
+    fn on_leave(&mut self, _id: u32) {}
+
+    /// Called after the victim is killed by the killer's bullet.
+    fn on_kill(&mut self, _victim_id: u32, _killer_id: u32) {}
+
+    /// Called at the end of every game update.
+    fn on_tick(&mut self, _state: &GameState) {}
+}
@@ -0,0 +1,275 @@
+//! Routing of connections to independent game rooms.
+//!
+//! Every room has its own `GameState` running in its own game loop, and clients in one room never hear about the others.
+
+use std::collections::HashMap;
+use std::sync::{mpsc, Arc, RwLock};
+use std::thread;
+use std::fmt;
+
+use options::Options;
+
+use self::super::WebSocketEvent;
+use self::super::start_game_loop;
+
+/// Registry of the rooms running in this process, mapping room names to their game loops' channels.
+///
+/// Rooms are started on demand, up to the configured maximum, and removed once their last client leaves.
+/// If only one room may run, every path leads to the default room.
+///
+/// Client IDs are handed out here too, so that they're unique across all the listeners sharing the registry.
+pub struct RoomRegistry {
+    rooms: HashMap<String, Room>,
+    next_client_id: u32,
+    options: Options,
+    cont: Arc<RwLock<bool>>,
+}
+
+impl RoomRegistry {
+    /// Create a new registry with no rooms, which will be configured according to the specified options.
+    pub fn new(options: Options, cont: &Arc<RwLock<bool>>) -> RoomRegistry {
+        RoomRegistry {
+            rooms: HashMap::new(),
+            next_client_id: 0,
+            options: options,
+            cont: cont.clone(),
+        }
+    }
+
+    /// Join the specified room, getting the channel to its game loop and starting the room if it isn't running yet.
+    ///
+    /// Every successful call must be matched by a call to `leave()` once the client is gone.
+    ///
+    /// Returns `None` if the room would have to be started, but the maximum amount of rooms are already running.
+    pub fn room(&mut self, name: &str) -> Option<mpsc::Sender<WebSocketEvent>> {
+        let name = self.room_key(name).to_string();
+        if !self.rooms.contains_key(&name) {
+            if self.rooms.len() >= self.options.max_rooms as usize {
+                return None;
+            }
+
+            println!("Starting room {:?}", name);
+            let (tx, rx) = mpsc::channel();
+            let room = Room {
+                sender: tx,
+                members: 0,
+                game_loop: Some(start_game_loop(rx, self.options.clone(), &self.cont)),
+            };
+            let _ = self.rooms.insert(name.clone(), room);
+        }
+
+        let room = self.rooms.get_mut(&name).unwrap();
+        room.members += 1;
+        Some(room.sender.clone())
+    }
+
+    /// Leave the specified room, removing it once its last client is gone.
+    ///
+    /// The room's game loop stops by itself once the connections' channels to it are dropped as well,
+    /// so it's let go of instead of being waited for.
+    pub fn leave(&mut self, name: &str) {
+        let name = self.room_key(name).to_string();
+        let empty = match self.rooms.get_mut(&name) {
+            Some(room) => {
+                room.members -= 1;
+                room.members == 0
+            }
+            None => false,
+        };
+
+        if empty {
+            println!("Removing empty room {:?}", name);
+            let _ = self.rooms.remove(&name);
+        }
+    }
+
+    /// Take the next client ID, which no other connection to any room has had.
+    pub fn next_client_id(&mut self) -> u32 {
+        let id = self.next_client_id;
+        self.next_client_id += 1;
+        id
+    }
+
+    /// The options the rooms are configured with.
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /// The amount of rooms currently running.
+    pub fn len(&self) -> usize {
+        self.rooms.len()
+    }
+
+    /// Whether there are no rooms running.
+    pub fn is_empty(&self) -> bool {
+        self.rooms.is_empty()
+    }
+
+    /// Wait for the game loops of all the rooms still running to finish.
+    pub fn join(&mut self) {
+        for room in self.rooms.values_mut() {
+            if let Some(game_loop) = room.game_loop.take() {
+                if let Err(error) = game_loop.join() {
+                    println!("Game loop thread failed: {:?}", error);
+                }
+            }
+        }
+    }
+
+    /// Route the specified room to the specified channel instead of a game loop.
+    ///
+    /// The room is kept as though it had a client of its own, so it's never removed.
+    #[cfg(test)]
+    pub fn add_room(&mut self, name: &str, sender: mpsc::Sender<WebSocketEvent>) {
+        let room = Room {
+            sender: sender,
+            members: 1,
+            game_loop: None,
+        };
+        let _ = self.rooms.insert(name.to_string(), room);
+    }
+
+    /// The name the specified room is kept under, which is the default room's if only one room may run.
+    fn room_key<'a>(&self, name: &'a str) -> &'a str {
+        if self.options.max_rooms == 1 { "" } else { name }
+    }
+}
+
+impl fmt::Debug for RoomRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RoomRegistry {:?}", self.rooms.keys().collect::<Vec<_>>())
+    }
+}
+
+/// A running room, with the channel to its game loop and the amount of clients in it.
+struct Room {
+    sender: mpsc::Sender<WebSocketEvent>,
+    members: usize,
+    game_loop: Option<thread::JoinHandle<()>>,
+}
+
+/// Extract the name of the room a client requested from the path it connected to, ignoring any query.
+///
+/// Connecting to `/` (or not specifying a path) joins the default room, whose name is empty.
+pub fn room_name(path: &str) -> &str {
+    let path = match path.find('?') {
+        Some(query_start) => &path[..query_start],
+        None => path,
+    };
+    path.trim_matches('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str;
+    use std::sync::{mpsc, Arc, Mutex, RwLock};
+    use std::thread;
+    use std::time::Duration;
+    use message::Message;
+    use options::Options;
+    use self::super::{room_name, RoomRegistry};
+    use self::super::super::{Client, WebSocketEvent};
+
+    #[test]
+    fn clients_in_different_rooms_never_see_each_other() {
+        let cont = Arc::new(RwLock::new(true));
+        let mut rooms = RoomRegistry::new(Options { max_rooms: 2, ..Options::default() }, &cont);
+
+        let (tx0, rx0) = mpsc::channel();
+        let (tx1, rx1) = mpsc::channel();
+        rooms.room("a").unwrap().send(WebSocketEvent::ClientCreated { client: Client::new(0, tx0) }).unwrap();
+        rooms.room("b").unwrap().send(WebSocketEvent::ClientCreated { client: Client::new(1, tx1) }).unwrap();
+
+        thread::sleep(Duration::from_millis(100));
+        *cont.write().unwrap() = false;
+        rooms.join();
+
+        for &(id, ref rx) in &[(0, rx0), (1, rx1)] {
+            let mut saw_world_state = false;
+            while let Ok(Some(text)) = rx.try_recv() {
+                if let Message::WorldState { player_count, ref alive_players, .. } = str::parse::<Message>(&text).unwrap() {
+                    assert_eq!(player_count, 1);
+                    assert!(alive_players.iter().all(|player| player.id == id));
+                    saw_world_state = true;
+                }
+            }
+            assert!(saw_world_state);
+        }
+    }
+
+    #[test]
+    fn same_room_is_reused_and_cap_is_enforced() {
+        let cont = Arc::new(RwLock::new(true));
+        let mut rooms = RoomRegistry::new(Options { max_rooms: 2, ..Options::default() }, &cont);
+
+        assert!(rooms.room("").is_some());
+        assert!(rooms.room("").is_some());
+        assert!(rooms.room("other").is_some());
+        assert!(rooms.room("third").is_none());
+        assert_eq!(rooms.len(), 2);
+
+        *cont.write().unwrap() = false;
+        rooms.join();
+    }
+
+    #[test]
+    fn every_path_leads_to_the_only_room() {
+        let cont = Arc::new(RwLock::new(true));
+        let mut rooms = RoomRegistry::new(Options { max_rooms: 1, ..Options::default() }, &cont);
+
+        assert!(rooms.room("").is_some());
+        assert!(rooms.room("other").is_some());
+        assert_eq!(rooms.len(), 1);
+
+        *cont.write().unwrap() = false;
+        rooms.join();
+    }
+
+    #[test]
+    fn rooms_are_removed_once_their_last_client_leaves() {
+        let cont = Arc::new(RwLock::new(true));
+        let mut rooms = RoomRegistry::new(Options { max_rooms: 1, ..Options::default() }, &cont);
+
+        assert!(rooms.room("a").is_some());
+        assert!(rooms.room("b").is_some());
+        rooms.leave("a");
+        assert_eq!(rooms.len(), 1);
+        rooms.leave("b");
+        assert!(rooms.is_empty());
+
+        // The room's game loop was let go of along with it, so there's nothing to wait for while the server's still running
+        rooms.join();
+
+        let mut rooms = RoomRegistry::new(Options { max_rooms: 2, ..Options::default() }, &cont);
+        assert!(rooms.room("a").is_some());
+        assert!(rooms.room("b").is_some());
+        rooms.leave("a");
+        assert!(rooms.room("c").is_some());
+        assert_eq!(rooms.len(), 2);
+
+        *cont.write().unwrap() = false;
+        rooms.join();
+    }
+
+    #[test]
+    fn client_ids_are_never_reused() {
+        let rooms = RoomRegistry::new(Options::default(), &Arc::new(RwLock::new(true)));
+        let rooms = Arc::new(Mutex::new(rooms));
+
+        // As both listeners would, for players and for spectators
+        let players = rooms.clone();
+        let spectators = rooms.clone();
+        let ids = vec![players.lock().unwrap().next_client_id(),
+                       spectators.lock().unwrap().next_client_id(),
+                       players.lock().unwrap().next_client_id()];
+        assert_eq!(ids, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn room_name_ignores_slashes_and_query() {
+        assert_eq!(room_name("/"), "");
+        assert_eq!(room_name(""), "");
+        assert_eq!(room_name("/lobby"), "lobby");
+        assert_eq!(room_name("/lobby/?compress=1"), "lobby");
+    }
+}